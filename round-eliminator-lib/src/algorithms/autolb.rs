@@ -1,23 +1,134 @@
-use std::collections::{HashSet, HashMap};
+use std::{cell::RefCell, collections::{HashSet, HashMap}, sync::{atomic::{AtomicBool, Ordering}, Arc}};
 
-use crate::{problem::Problem, group::Label, serial::AutoOperation, line::Degree};
+use crate::{problem::Problem, group::Label, serial::{fix_problem, AutoOperation}, line::Degree};
 
-use super::event::EventHandler;
+use super::{event::EventHandler, fixpoint::FixpointType};
 use itertools::Itertools;
 use permutator::Combination;
+use serde::{Deserialize, Serialize};
+
+/// Length reported by `autolb` for sequences whose last problem is equal to an earlier one.
+const REPEATED : usize = 999;
+
+type Step = (Vec<(Label,Label)>,Problem,Problem,String);
+
+/// A lower bound for the complexity of a problem.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LowerBound {
+    /// At least this number of rounds.
+    Rounds(usize),
+    /// Ω(log* n) rounds.
+    LogStar,
+    /// Ω(log n) rounds for deterministic algorithms.
+    Log,
+    /// Ω(log n) rounds for deterministic algorithms, and the problem cannot be solved by Borel functions.
+    NonBorel,
+}
+
+impl std::fmt::Display for LowerBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LowerBound::Rounds(x) => write!(f, "{} rounds", x),
+            LowerBound::LogStar => write!(f, "Ω(log* n)"),
+            LowerBound::Log => write!(f, "Ω(log n)"),
+            LowerBound::NonBorel => write!(f, "Ω(log n), not Borel"),
+        }
+    }
+}
+
+/// How a lower bound has been obtained for the last problem of a sequence.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum LowerBoundTechnique {
+    /// Round elimination: each problem of the sequence is not 0-round solvable.
+    RoundElimination,
+    /// The last problem of the sequence is equal to an earlier one, and hence it relaxes to itself after some steps.
+    Cycle,
+    /// The fixed point procedure applied to the last problem gives this fixed point, that is not 0-round solvable.
+    FixedPoint(Problem),
+    /// The target problem cannot be solved in 0 rounds given the dual of the last problem with respect to it.
+    Dual { target : Problem, dual : Problem },
+    /// The marks technique shows that the last problem of the sequence cannot be solved by Borel functions.
+    Marks,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LowerBoundCertificate {
+    pub bound : LowerBound,
+    pub technique : LowerBoundTechnique,
+    /// The speedups and merges that give, starting from the original problem, the problem to which the technique is applied.
+    pub sequence : Vec<(AutoOperation,Problem)>,
+}
 
 
 impl Problem {
     pub fn autolb<F>(&self, max_labels : usize, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler)  -> bool  where F : FnMut(usize, Vec<(AutoOperation,Problem)>){
-        let mut problems = vec![(vec![],self.clone(),self.clone(),self.to_string())];
+        let mut problems = vec![(vec![],self.clone(),self.clone(),self.to_string_with_input())];
         let mut best = usize::MAX;
         let mut seen = HashMap::new();
     
-        automatic_lower_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, min_steps, max_steps, coloring, coloring_passive, &mut handler, eh);
+        automatic_lower_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, min_steps, max_steps, coloring, coloring_passive, &mut handler, &mut |_ : &[Step]|false, eh);
 
         return best >= max_steps;
     }
 
+    /// Searches for a lower bound by combining round elimination with the fixed point procedure and with duals.
+    /// Speedups and merges are explored as in `autolb`, and each problem that is reached and that is not 0-round solvable is given to the fixed point procedure and,
+    /// if `target` is given together with a lower bound that is known for it, it is checked whether `target` can be solved in 0 rounds given the dual of the problem with respect to it,
+    /// and if not, the known lower bound of `target` is reported for the problem.
+    /// `handler` is called each time a better lower bound is found, and the search stops as soon as an Ω(log n) lower bound is found, or as soon as `done` is set, if it is given.
+    pub fn autolb_combined<F>(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, target : Option<(&Problem, LowerBound)>, handler : F, eh: &mut EventHandler, done : Option<Arc<AtomicBool>>) -> Option<LowerBoundCertificate> where F : FnMut(&LowerBoundCertificate) {
+        let target = target.map(|(target, bound)|{
+            let mut target = target.clone();
+            target.passive.maximize(eh);
+            target.compute_diagram(eh);
+            (target, bound)
+        });
+
+        let state = RefCell::new((None::<LowerBoundCertificate>, handler));
+        let improve = |certificate : LowerBoundCertificate|{
+            let mut state = state.borrow_mut();
+            if state.0.as_ref().map_or(true, |best|certificate.bound > best.bound) {
+                (state.1)(&certificate);
+                state.0 = Some(certificate);
+            }
+        };
+
+        {
+            let mut rounds = |len : usize, sequence : Vec<(AutoOperation,Problem)>|{
+                let (bound, technique) = if len == REPEATED {
+                    (LowerBound::Log, LowerBoundTechnique::Cycle)
+                } else {
+                    (LowerBound::Rounds(len), LowerBoundTechnique::RoundElimination)
+                };
+                improve(LowerBoundCertificate { bound, technique, sequence });
+            };
+
+            let mut tried = HashSet::new();
+            let mut visit = |problems : &[Step]|{
+                if done.as_ref().map_or(false, |done|done.load(Ordering::Relaxed)) {
+                    return true;
+                }
+                let (_, _, p, s) = problems.last().unwrap();
+                if !tried.insert(s.clone()) || !p.trivial_sets.as_ref().unwrap().is_empty() || p.is_trivial_given_input() {
+                    return false;
+                }
+                // sides are swapped at each step
+                let coloring = if problems.len() % 2 == 1 { coloring } else { coloring_passive };
+                if let Some((bound, technique)) = nonconstant_lower_bound(p, coloring, target.as_ref().map(|(target, bound)|(target, *bound))) {
+                    improve(LowerBoundCertificate { bound, technique, sequence : sequence_of(problems) });
+                }
+                state.borrow().0.as_ref().map_or(false, |best|best.bound == LowerBound::Log)
+            };
+
+            let mut problems = vec![(vec![],self.clone(),self.clone(),self.to_string_with_input())];
+            let mut best = usize::MAX;
+            let mut seen = HashMap::new();
+            automatic_lower_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, 1, max_steps, coloring, coloring_passive, &mut rounds, &mut visit, eh);
+        }
+
+        state.into_inner().0
+    }
+
 
     pub fn autoautolb<F>(&self, b_max_labels : bool, max_labels : usize, b_branching : bool, branching : usize, b_max_steps : bool, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
         if b_max_labels && b_branching && b_max_steps {
@@ -124,27 +235,79 @@ fn best_merges(np : &Problem, branching : usize, max_labels : usize, coloring :
     candidates.into_iter().take(branching).map(|v|v.into_iter().map(|(p,_)|p).collect()).collect()
 }
 
-fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &mut Vec<(Vec<(Label,Label)>,Problem,Problem,String)>, best : &mut usize, max_labels : usize, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, handler : &mut F, eh: &mut EventHandler) where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
+/// Tries to obtain a lower bound that does not depend on the number of steps for a problem that is not 0-round solvable, by applying the fixed point procedure and by computing its dual with respect to `target`.
+/// A fixed point that can be solved in 0 rounds given a `coloring` only gives an Ω(log* n) lower bound.
+/// The dual only transfers the lower bound that is known for `target`.
+fn nonconstant_lower_bound(p : &Problem, coloring : Option<usize>, target : Option<(&Problem, LowerBound)>) -> Option<(LowerBound, LowerBoundTechnique)> {
+    let eh = &mut EventHandler::null();
+    let mut result = None;
 
-    let mut send_sequence = |len : usize, problems : &Vec<(Vec<(Label,Label)>,Problem,Problem,String)>|{
-        *best = len + 1;
-        let mut sequence = vec![];
-        sequence.push((AutoOperation::Initial,problems[0].2.clone()));
-        for (merges,after_speedup, after_merge,_) in problems.iter().skip(1) {
-            sequence.push((AutoOperation::Speedup,after_speedup.clone()));
-            if !merges.is_empty() {
-                sequence.push((AutoOperation::Merge(merges.clone(),after_speedup.clone()),after_merge.clone()));
+    let mut p = p.clone();
+    if p.diagram_indirect.is_none() {
+        p.compute_partial_diagram(eh);
+    }
+    if let Ok((mut fixpoint, _, _)) = p.fixpoint_generic(None, FixpointType::Basic, false, eh) {
+        fix_problem(&mut fixpoint, true, true, eh);
+        if fixpoint.trivial_sets.as_ref().unwrap().is_empty() && !fixpoint.is_trivial_given_input() {
+            let bound = match coloring {
+                Some(c) => {
+                    if fixpoint.coloring_sets.is_none() {
+                        fixpoint.compute_coloring_solvability(eh);
+                    }
+                    if fixpoint.coloring_sets.as_ref().unwrap().len() >= c { LowerBound::LogStar } else { LowerBound::Log }
+                }
+                None => LowerBound::Log,
+            };
+            if bound == LowerBound::Log {
+                return Some((bound, LowerBoundTechnique::FixedPoint(fixpoint)));
+            }
+            result = Some((bound, LowerBoundTechnique::FixedPoint(fixpoint)));
+        }
+    }
+
+    if let Some((target, target_bound)) = target {
+        if result.as_ref().map_or(true, |(bound, _)|target_bound > *bound) && target.active.degree == p.active.degree && target.passive.degree == p.passive.degree {
+            p.passive.maximize(eh);
+            if let Ok((mut dual, _, _)) = p.dual_problem(target, eh) {
+                fix_problem(&mut dual, true, true, eh);
+                let mut target = target.clone();
+                if target.compute_triviality_with_input(dual.clone(), true).is_ok() && target.triviality_with_input.is_none() {
+                    return Some((target_bound, LowerBoundTechnique::Dual { target, dual }));
+                }
             }
         }
-        handler(len,sequence);
-    };
+    }
+
+    result
+}
+
+/// The operations that give the last problem of `problems`, in the form reported by `autolb`.
+fn sequence_of(problems : &[Step]) -> Vec<(AutoOperation,Problem)> {
+    let mut sequence = vec![];
+    sequence.push((AutoOperation::Initial,problems[0].2.clone()));
+    for (merges,after_speedup, after_merge,_) in problems.iter().skip(1) {
+        sequence.push((AutoOperation::Speedup,after_speedup.clone()));
+        if !merges.is_empty() {
+            sequence.push((AutoOperation::Merge(merges.clone(),after_speedup.clone()),after_merge.clone()));
+        }
+    }
+    sequence
+}
+
+fn send_sequence<F>(len : usize, problems : &[Step], best : &mut usize, handler : &mut F) where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
+    *best = len + 1;
+    handler(len,sequence_of(problems));
+}
+
+/// `visit` is called on each sequence whose last problem has been checked for triviality, and if it returns true, the search stops.
+fn automatic_lower_bound_rec<F,V>(seen : &mut HashMap<String,usize>, problems : &mut Vec<Step>, best : &mut usize, max_labels : usize, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, handler : &mut F, visit : &mut V, eh: &mut EventHandler) where F : FnMut(usize, Vec<(AutoOperation,Problem)>), V : FnMut(&[Step]) -> bool {
 
     {
         let p_s = &problems.last().unwrap().3;
         if problems.len() >=2 {
             for i in (0..problems.len()-2).rev() {
                 if &problems[i].3 == p_s {
-                    send_sequence(999, problems);
+                    send_sequence(REPEATED, problems, best, handler);
                     return;
                 }
             }
@@ -167,15 +330,22 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
         if p.orientation_given.is_some() && p.orientation_trivial_sets.is_none() {
             p.compute_triviality_given_orientation(p.orientation_given.unwrap(), eh);
         }
+        p.compute_triviality_given_input();
+    }
+
+    if visit(problems) {
+        *best = usize::MAX;
+        return;
     }
 
     let p = &problems.last().unwrap().2;  
 
     if problems.len() > max_steps 
         || p.trivial_sets.as_ref().unwrap().len() > 0 
+        || p.is_trivial_given_input()
         || (coloring.is_some() && p.coloring_sets.is_some() && p.coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap())
         || (p.orientation_given.is_some() && p.orientation_trivial_sets.as_ref().unwrap().len() > 0)  {
-            send_sequence(problems.len()-1, problems);
+            send_sequence(problems.len()-1, problems, best, handler);
             return;
     }
     
@@ -201,10 +371,11 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
         if merged.orientation_given.is_some() && merged.orientation_trivial_sets.is_none() {
             merged.compute_triviality_given_orientation(merged.orientation_given.unwrap(), eh);
         }
-        let m_s = merged.to_string();
+        merged.compute_triviality_given_input();
+        let m_s = merged.to_string_with_input();
 
         problems.push((merges,np.clone(),merged.clone(),m_s));
-        automatic_lower_bound_rec(seen, problems, best, max_labels, branching, min_steps, max_steps, coloring, coloring_passive, handler, eh);
+        automatic_lower_bound_rec(seen, problems, best, max_labels, branching, min_steps, max_steps, coloring, coloring_passive, handler, visit, eh);
         problems.pop();
         if *best > max_steps {
             return;
@@ -214,3 +385,24 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
     
 }
 
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::{LowerBound, LowerBoundTechnique};
+
+    #[test]
+    fn autolb_combined() {
+        let eh = &mut EventHandler::null();
+        // sinkless orientation is a fixed point
+        let p = Problem::from_string("O I I\n\nI OI").unwrap();
+        let mut reported = vec![];
+        let found = p.autolb_combined(4, 2, 3, None, None, None, |c|reported.push(c.bound), eh, None).unwrap();
+        assert_eq!(found.bound, LowerBound::Log);
+        assert!(matches!(found.technique, LowerBoundTechnique::FixedPoint(_) | LowerBoundTechnique::Cycle));
+        assert_eq!(reported.last(), Some(&LowerBound::Log));
+        assert!(reported.windows(2).all(|w|w[0] < w[1]));
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use dashmap::DashSet;
+use itertools::Itertools;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{constraint::Constraint, group::{Group, GroupType, Label}, line::Line, problem::Problem};
+
+use super::{event::EventHandler, maximize::{combine_lines, without_one}};
+
+/// A permutation of the labels, labels that are not in the map are fixed.
+pub type LabelPermutation = HashMap<Label, Label>;
+
+/// Maximum number of nodes visited while searching for automorphisms.
+/// If the search is stopped early, the automorphisms found so far still generate a subgroup of the automorphism group, which is enough for `maximize_symmetric`.
+const SEARCH_BUDGET : usize = 1_000_000;
+
+impl Line {
+    pub fn permuted(&self, perm : &LabelPermutation) -> Line {
+        let mut line = self.edited(|g|Group::from(g.iter().map(|l|*perm.get(l).unwrap_or(l)).sorted().collect()));
+        line.normalize();
+        line
+    }
+}
+
+impl Constraint {
+    pub fn permuted(&self, perm : &LabelPermutation) -> Constraint {
+        Constraint { lines : self.lines.iter().map(|line|line.permuted(perm)).collect(), is_maximized : self.is_maximized, degree : self.degree }
+    }
+
+    /// Label permutations that map the set of lines of the constraint to itself.
+    pub fn automorphism_generators(&self) -> Vec<LabelPermutation> {
+        let labels = self.labels_appearing().into_iter().sorted().collect_vec();
+        automorphism_generators(&labels, &[self])
+    }
+
+    /// Same as `maximize`, but only the orbit representatives of the lines are combined with the other lines.
+    /// The generators must generate a group of label permutations that preserve the constraint, for example, the ones given by `automorphism_generators`.
+    /// Unlike `maximize`, this does not use remote workers, checkpoints or the memory budget, unless there are no generators.
+    pub fn maximize_symmetric(&mut self, generators : &[LabelPermutation], eh : &mut EventHandler) {
+        if self.is_maximized || self.lines.is_empty() {
+            self.is_maximized = true;
+            return;
+        }
+        if generators.is_empty() {
+            self.maximize(eh);
+            return;
+        }
+
+        let becomes_star = 100;
+        let seen = DashSet::new();
+
+        // the constraint is made closed under the group, so that the lines of each step are a union of orbits
+        let lines = std::mem::take(&mut self.lines);
+        for mut line in lines {
+            line.normalize();
+            for line in orbit(&line, generators) {
+                seen.insert(line.compressed());
+                self.add_line_and_discard_non_maximal(line);
+            }
+        }
+
+        let mut lines_of_previous_step = HashSet::new();
+        loop {
+            let lines = self.lines.clone();
+            let without_one = without_one(&lines);
+            let representatives = orbit_representatives(&lines, generators);
+
+            // every pair of lines is the image of a pair containing a representative
+            let pairs = representatives.iter()
+                .flat_map(|&i|(0..lines.len()).map(move |j|(i,j)))
+                .filter(|&(i,j)|!(lines_of_previous_step.contains(&lines[i]) && lines_of_previous_step.contains(&lines[j])))
+                .collect_vec();
+
+            let mut newconstraint = self.clone();
+            for (done, chunk) in pairs.chunks(1024).enumerate() {
+                eh.notify("combining line pairs", done * 1024, pairs.len());
+                let candidates : Vec<Vec<Line>> = chunk.par_iter().map(|&(i,j)|{
+                    combine_lines(&lines[i], &lines[j], &without_one[i], &without_one[j], &seen, becomes_star, false)
+                }).collect();
+                for newline in candidates.into_iter().flatten() {
+                    // newconstraint is closed under the group, hence if a line is dominated, so are all the lines of its orbit
+                    if newconstraint.is_included_with_custom_supersets(&newline, None::<fn(&'_ _, &'_ _) -> _>) {
+                        continue;
+                    }
+                    for line in orbit(&newline, generators) {
+                        newconstraint.add_line_and_discard_non_maximal(line);
+                    }
+                }
+            }
+
+            if newconstraint == *self {
+                break;
+            }
+            *self = newconstraint;
+            lines_of_previous_step = lines.into_iter().collect();
+        }
+
+        self.is_maximized = true;
+    }
+}
+
+impl Problem {
+    /// Label permutations that map the active and the passive constraints (of all degrees) to themselves.
+    /// The group generated by them is the automorphism group of the problem, unless the search is too large, in which case it is a subgroup.
+    pub fn automorphism_generators(&self) -> Vec<LabelPermutation> {
+        let constraints = self.active_constraints().into_iter().chain(self.passive_constraints()).collect_vec();
+        automorphism_generators(&self.labels(), &constraints)
+    }
+}
+
+/// All the lines that can be obtained by applying the generators to the line.
+pub fn orbit(line : &Line, generators : &[LabelPermutation]) -> Vec<Line> {
+    let mut visited = HashSet::new();
+    let mut result = vec![];
+    let mut queue = VecDeque::new();
+    visited.insert(line.clone());
+    queue.push_back(line.clone());
+    while let Some(line) = queue.pop_front() {
+        for perm in generators {
+            let image = line.permuted(perm);
+            if visited.insert(image.clone()) {
+                queue.push_back(image);
+            }
+        }
+        result.push(line);
+    }
+    result
+}
+
+/// Indices of one line for each orbit, assuming that the set of lines is closed under the generators.
+pub fn orbit_representatives(lines : &[Line], generators : &[LabelPermutation]) -> Vec<usize> {
+    let mut covered = HashSet::new();
+    let mut representatives = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        if covered.contains(line) {
+            continue;
+        }
+        representatives.push(i);
+        covered.extend(orbit(line, generators));
+    }
+    representatives
+}
+
+/// Orbit of a label under the generators.
+fn label_orbit(label : Label, generators : &[LabelPermutation]) -> HashSet<Label> {
+    let mut orbit = HashSet::new();
+    let mut queue = vec![label];
+    orbit.insert(label);
+    while let Some(l) = queue.pop() {
+        for perm in generators {
+            let image = *perm.get(&l).unwrap_or(&l);
+            if orbit.insert(image) {
+                queue.push(image);
+            }
+        }
+    }
+    orbit
+}
+
+struct AutomorphismSearch<'a> {
+    labels : &'a [Label],
+    constraints : Vec<(&'a Constraint, HashSet<Line>)>,
+    /// For each position in `labels`, the lines (constraint index, line index) whose labels all appear at positions up to that one.
+    complete_at : Vec<Vec<(usize, usize)>>,
+    /// Labels can only be mapped to labels with the same signature.
+    signature : HashMap<Label, Vec<(usize, GroupType, usize)>>,
+    budget : usize,
+}
+
+impl<'a> AutomorphismSearch<'a> {
+    fn new(labels : &'a [Label], constraints : &[&'a Constraint]) -> Self {
+        let position : HashMap<Label, usize> = labels.iter().enumerate().map(|(i,&l)|(l,i)).collect();
+        let mut complete_at = vec![vec![]; labels.len()];
+        let mut signature : HashMap<Label, Vec<(usize, GroupType, usize)>> = labels.iter().map(|&l|(l, vec![])).collect();
+        for (c, constraint) in constraints.iter().enumerate() {
+            for (i, line) in constraint.lines.iter().enumerate() {
+                if let Some(last) = line.groups().flat_map(|g|g.iter()).map(|l|position[l]).max() {
+                    complete_at[last].push((c, i));
+                }
+                for part in &line.parts {
+                    for l in part.group.iter() {
+                        signature.get_mut(l).unwrap().push((c, part.gtype, part.group.len()));
+                    }
+                }
+            }
+        }
+        for s in signature.values_mut() {
+            s.sort();
+        }
+        let constraints = constraints.iter().map(|&c|{
+            let lines = c.lines.iter().map(|line|{ let mut line = line.clone(); line.normalize(); line }).collect();
+            (c, lines)
+        }).collect();
+        Self { labels, constraints, complete_at, signature, budget : SEARCH_BUDGET }
+    }
+
+    /// Checks whether the lines that only contain assigned labels are mapped to lines of the same constraint.
+    fn consistent(&self, perm : &LabelPermutation, position : usize) -> bool {
+        self.complete_at[position].iter().all(|&(c, i)|{
+            let (constraint, lines) = &self.constraints[c];
+            lines.contains(&constraint.lines[i].permuted(perm))
+        })
+    }
+
+    /// Searches for an automorphism that fixes the first `fixed` labels and maps the label at position `fixed` to `target`.
+    fn find(&mut self, fixed : usize, target : Label) -> Option<LabelPermutation> {
+        let mut perm = LabelPermutation::new();
+        let mut used = HashSet::new();
+        for &l in &self.labels[..fixed] {
+            perm.insert(l, l);
+            used.insert(l);
+        }
+        for position in 0..fixed {
+            if !self.consistent(&perm, position) {
+                return None;
+            }
+        }
+        perm.insert(self.labels[fixed], target);
+        used.insert(target);
+        if !self.consistent(&perm, fixed) {
+            return None;
+        }
+        if self.extend(&mut perm, &mut used, fixed + 1) {
+            Some(perm)
+        } else {
+            None
+        }
+    }
+
+    fn extend(&mut self, perm : &mut LabelPermutation, used : &mut HashSet<Label>, position : usize) -> bool {
+        if position == self.labels.len() {
+            return true;
+        }
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+        let label = self.labels[position];
+        let candidates = self.labels.iter().cloned()
+            .filter(|l|!used.contains(l) && self.signature[l] == self.signature[&label])
+            .collect_vec();
+        for candidate in candidates {
+            perm.insert(label, candidate);
+            used.insert(candidate);
+            if self.consistent(perm, position) && self.extend(perm, used, position + 1) {
+                return true;
+            }
+            used.remove(&candidate);
+            perm.remove(&label);
+        }
+        false
+    }
+}
+
+/// Generators of the group of permutations of the given labels that preserve all the given constraints,
+/// computed level by level along the chain of pointwise stabilizers of the labels.
+fn automorphism_generators(labels : &[Label], constraints : &[&Constraint]) -> Vec<LabelPermutation> {
+    let mut search = AutomorphismSearch::new(labels, constraints);
+    let mut generators : Vec<LabelPermutation> = vec![];
+    for fixed in (0..labels.len()).rev() {
+        let label = labels[fixed];
+        let mut orbit = label_orbit(label, &generators);
+        for &target in &labels[fixed+1..] {
+            if orbit.contains(&target) || search.signature[&target] != search.signature[&label] {
+                continue;
+            }
+            if let Some(perm) = search.find(fixed, target) {
+                let perm : LabelPermutation = perm.into_iter().filter(|(a,b)|a != b).collect();
+                generators.push(perm);
+                orbit = label_orbit(label, &generators);
+            }
+        }
+    }
+    generators
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::{algorithms::event::EventHandler, constraint::Constraint, problem::Problem};
+
+    #[test]
+    fn automorphisms() {
+        // 3-coloring, the automorphism group is the symmetric group on 3 labels
+        let p = Problem::from_string("A A A\nB B B\nC C C\n\nA B\nA C\nB C").unwrap();
+        let generators = p.automorphism_generators();
+        let line = p.active.lines[0].clone();
+        assert_eq!(super::orbit(&line, &generators).len(), 3);
+        for perm in &generators {
+            assert_eq!(p.active.permuted(perm).lines.iter().cloned().collect::<HashSet<_>>(), p.active.lines.iter().cloned().collect());
+            assert_eq!(p.passive.permuted(perm).lines.iter().cloned().collect::<HashSet<_>>(), p.passive.lines.iter().cloned().collect());
+        }
+
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(p.automorphism_generators().is_empty());
+    }
+
+    #[test]
+    fn symmetric_maximize() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A A A\nB B B\nC C C\nD D D\n\nA B\nA C\nA D\nB C\nB D\nC D").unwrap();
+        let generators = p.passive.automorphism_generators();
+        assert!(!generators.is_empty());
+
+        let mut expected = p.passive.clone();
+        expected.maximize(eh);
+        let mut c = p.passive.clone();
+        c.maximize_symmetric(&generators, eh);
+
+        let sorted = |c : &Constraint| { let mut v = c.lines.clone(); v.sort(); v };
+        assert_eq!(sorted(&c), sorted(&expected));
+
+        let sp = p.speedup(eh);
+        assert_eq!(sp.active.lines.len(), expected.lines.len());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{HashSet, HashMap};
+use std::{collections::{HashSet, HashMap}, sync::{atomic::{AtomicBool, Ordering}, Arc}};
 
 use crate::{problem::Problem, group::Label, serial::AutoOperation, line::Degree};
 
@@ -9,18 +9,23 @@ use rand::prelude::SliceRandom;
 
 
 impl Problem {
-    pub fn autoub<F>(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+    /// If `done` is given, the search stops as soon as it is set.
+    pub fn autoub<F>(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler, done : Option<Arc<AtomicBool>>) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        let done = done.as_deref();
         if self.labels().len() <= max_labels {
-            let mut problems = vec![(self.labels(),self.clone(),self.clone(),self.to_string())];
+            let mut problems = vec![(self.labels(),self.clone(),self.clone(),self.to_string_with_input())];
             let mut best = usize::MAX;
             let mut seen = HashMap::new();
             //println!("calling rec");
-            automatic_upper_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, max_steps, coloring, coloring_passive, &mut handler, eh);
+            automatic_upper_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, max_steps, coloring, coloring_passive, &mut handler, eh, done);
         } else {
             //println!("too many labels");
             let mut best = usize::MAX;
             let mut seen = HashMap::new();
             for candidate in best_hardenings(self, branching, max_labels, coloring, eh).into_iter().take(branching) {        
+                if is_done(done) {
+                    return;
+                }
                 let tokeep = candidate.iter().cloned().collect();
                 let mut hardened = self.harden_keep(&tokeep, true);
                 hardened.discard_useless_stuff(false, eh);
@@ -29,16 +34,16 @@ impl Problem {
                 if coloring.is_some() {
                     hardened.compute_coloring_solvability(eh);
                 }
-                let h_s = hardened.to_string();
+                let h_s = hardened.to_string_with_input();
                 let mut problems = vec![(candidate,self.clone(),hardened.clone(),h_s)];
-                automatic_upper_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, max_steps, coloring, coloring_passive, &mut handler, eh);
+                automatic_upper_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, max_steps, coloring, coloring_passive, &mut handler, eh, done);
             }
         }
     }
 
     pub fn autoautoub<F>(&self, b_max_labels : bool, max_labels : usize, b_branching : bool, branching : usize, b_max_steps : bool, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
         if b_max_labels && b_branching && b_max_steps {
-            return self.autoub(max_labels, branching, max_steps, coloring, coloring_passive, handler, eh);
+            return self.autoub(max_labels, branching, max_steps, coloring, coloring_passive, handler, eh, None);
         }
 
         let mut max_steps = if b_max_steps {max_steps} else {usize::MAX};
@@ -55,7 +60,7 @@ impl Problem {
                         max_steps = len-1;
                         handler(len,trivial,seq);
                     }
-                },eh);
+                },eh, None);
                 if max_steps == 0 {
                     return;
                 }
@@ -145,7 +150,14 @@ fn best_hardenings(np : &Problem, branching : usize, max_labels : usize, colorin
     candidates.into_iter().take(branching).collect()
 }
 
-fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &mut Vec<(Vec<Label>,Problem,Problem,String)>, best : &mut usize, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, handler : &mut F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+fn is_done(done : Option<&AtomicBool>) -> bool {
+    done.map_or(false, |done|done.load(Ordering::Relaxed))
+}
+
+fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &mut Vec<(Vec<Label>,Problem,Problem,String)>, best : &mut usize, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, handler : &mut F, eh: &mut EventHandler, done : Option<&AtomicBool>) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+    if is_done(done) {
+        return;
+    }
     //println!("{} {} {}", max_labels, branching, max_steps);
     let mut send_sequence = |problems : &Vec<(Vec<Label>,Problem,Problem,String)>|{
         *best = problems.len();
@@ -158,7 +170,8 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
             sequence.push((AutoOperation::Speedup,after_speedup.clone()));
             sequence.push((AutoOperation::Harden(kept_labels.clone()),after_harden.clone()));
         }
-        handler(problems.len() - 1,!problems.last().as_ref().unwrap().2.trivial_sets.as_ref().unwrap().is_empty(), sequence);
+        let last = &problems.last().as_ref().unwrap().2;
+        handler(problems.len() - 1,!last.trivial_sets.as_ref().unwrap().is_empty() || last.is_trivial_given_input(), sequence);
     };
 
     {
@@ -196,8 +209,9 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
                 }
             }
         }
+        p.compute_triviality_given_input();
 
-        if p.trivial_sets.as_ref().unwrap().len() > 0 ||
+        if p.trivial_sets.as_ref().unwrap().len() > 0 || p.is_trivial_given_input() ||
            (p.orientation_trivial_sets.is_some() && p.orientation_trivial_sets.as_ref().unwrap().len() > 0) ||
            (coloring.is_some() && p.coloring_sets.is_some() && p.coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap()) ||
            (coloring.is_some() && p.orientation_coloring_sets.is_some() && p.orientation_coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap())
@@ -233,13 +247,14 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
             }
         }
     }
+    np.compute_triviality_given_input();
 
-    if np.trivial_sets.as_ref().unwrap().len() > 0 ||
+    if np.trivial_sets.as_ref().unwrap().len() > 0 || np.is_trivial_given_input() ||
     (np.orientation_trivial_sets.is_some() && np.orientation_trivial_sets.as_ref().unwrap().len() > 0) ||
     (coloring.is_some() && np.coloring_sets.is_some() && np.coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap()) ||
     (coloring.is_some() && np.orientation_coloring_sets.is_some() && np.orientation_coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap())
  {
-        problems.push((np.labels(),np.clone(),np.clone(),np.to_string()));
+        problems.push((np.labels(),np.clone(),np.clone(),np.to_string_with_input()));
         send_sequence(problems);
         return;
     }
@@ -248,7 +263,7 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
     let candidates = best_hardenings(&np, branching, max_labels, coloring, eh);
     
     for candidate in candidates.into_iter().take(branching) {
-        if *best <= problems.len() + 1 {
+        if *best <= problems.len() + 1 || is_done(done) {
             return;
         } 
 
@@ -260,10 +275,10 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
         if coloring.is_some() {
             hardened.compute_coloring_solvability(eh);
         }
-        let h_s = hardened.to_string();
+        let h_s = hardened.to_string_with_input();
 
         problems.push((candidate,np.clone(),hardened.clone(),h_s));
-        automatic_upper_bound_rec(seen, problems, best, max_labels, branching, max_steps, coloring, coloring_passive, handler, eh);
+        automatic_upper_bound_rec(seen, problems, best, max_labels, branching, max_steps, coloring, coloring_passive, handler, eh, done);
         problems.pop();
     }
 
@@ -0,0 +1,53 @@
+use std::{path::{Path, PathBuf}, time::Duration};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Where and how often long computations store their state.
+/// Checkpoints are identified by a hash of the input of the computation,
+/// so running again the same computation with the same directory resumes from the last checkpoint.
+#[derive(Clone, Debug)]
+pub struct CheckpointConfig {
+    pub dir : PathBuf,
+    /// A computation writes its first checkpoint only after running for this long, and then again every time this much time passes.
+    pub interval : Duration,
+}
+
+impl CheckpointConfig {
+    /// Reads the configuration from the environment:
+    /// RE_CHECKPOINT_DIR enables checkpoints and sets the directory where they are stored,
+    /// RE_CHECKPOINT_INTERVAL is the number of seconds between checkpoints (default 300).
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var("RE_CHECKPOINT_DIR").ok().filter(|s|!s.is_empty())?;
+        let interval = std::env::var("RE_CHECKPOINT_INTERVAL").ok()
+            .and_then(|s|s.parse::<u64>().ok())
+            .unwrap_or(300);
+        Some(Self { dir : dir.into(), interval : Duration::from_secs(interval) })
+    }
+
+    pub fn path(&self, kind : &str, key : &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.ckpt", kind, key))
+    }
+}
+
+/// A hash of the serialized value, used to name checkpoint files.
+pub fn checkpoint_key<T : Serialize>(value : &T) -> String {
+    sha256::digest(bincode::serialize(value).unwrap())
+}
+
+/// Writes the value compressed, first to a temporary file that is then renamed, so that a crash while writing does not corrupt the previous checkpoint.
+pub fn save_checkpoint<T : Serialize>(path : &Path, value : &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e|format!("cannot create {}: {}", dir.display(), e))?;
+    }
+    let encoded = bincode::serialize(value).map_err(|e|e.to_string())?;
+    let compressed = lz4_flex::compress_prepend_size(&encoded[..]);
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, compressed).map_err(|e|format!("cannot write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e|format!("cannot write {}: {}", path.display(), e))
+}
+
+pub fn load_checkpoint<T : DeserializeOwned>(path : &Path) -> Result<T, String> {
+    let compressed = std::fs::read(path).map_err(|e|format!("cannot read {}: {}", path.display(), e))?;
+    let encoded = lz4_flex::decompress_size_prepended(&compressed).map_err(|e|e.to_string())?;
+    bincode::deserialize(&encoded).map_err(|e|e.to_string())
+}
@@ -0,0 +1,377 @@
+use std::{fmt::Display, sync::{atomic::{AtomicBool, Ordering}, Arc}};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{line::Degree, problem::Problem, serial::AutoOperation};
+
+use super::{autolb::{LowerBound, LowerBoundCertificate, LowerBoundTechnique}, event::EventHandler};
+
+/// Maximum number of active and passive lines of the problems explored by the automatic log* upper bound.
+const LOGSTAR_MAX_LINES : usize = 8;
+/// Marks' technique is tried only if its tables have at most this number of entries.
+const MARKS_MAX_TABLE_ENTRIES : u64 = 1 << 16;
+
+/// The resources shared by all the strategies used by `classify`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClassificationBudget {
+    /// Maximum number of labels of the problems explored by the automatic bounds.
+    pub max_labels : usize,
+    /// Number of relaxations (hardenings) tried at each step by the automatic lower (upper) bound.
+    pub branching : usize,
+    /// Maximum number of round elimination steps.
+    pub max_steps : usize,
+    /// After this time, the automatic bounds are stopped (except on wasm, where no new strategy is started, but the running one is not stopped).
+    pub seconds : u64,
+}
+
+impl ClassificationBudget {
+    pub fn new(p : &Problem) -> Self {
+        ClassificationBudget { max_labels : p.labels().len() + 4, branching : 4, max_steps : 15, seconds : 600 }
+    }
+}
+
+/// An upper bound for the complexity of a problem.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UpperBound {
+    /// At most this number of rounds.
+    Rounds(usize),
+    /// O(log* n) rounds.
+    LogStar,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum UpperBoundTechnique {
+    /// Round elimination with hardenings: the last problem of the sequence is 0-round solvable, or, if the bound is O(log* n), it is 0-round solvable given a coloring.
+    RoundElimination,
+    /// Round elimination: the last problem of the sequence is 0-round solvable given a coloring with this number of colors, that can be computed in O(log* n) rounds.
+    Coloring(usize),
+    /// The problem is 0-round solvable given a solution of the last problem of the sequence, that is obtained from maximal independent set by operations that preserve O(log* n) complexity.
+    Logstar,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UpperBoundCertificate {
+    pub bound : UpperBound,
+    pub technique : UpperBoundTechnique,
+    pub sequence : Vec<(AutoOperation,Problem)>,
+}
+
+/// The complexity of a problem, as far as `classify` could determine it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Complexity {
+    /// Exactly this number of rounds.
+    Constant(usize),
+    /// Θ(log* n).
+    LogStar,
+    /// The bounds found so far do not match.
+    Between { lower : Option<LowerBound>, upper : Option<UpperBound> },
+}
+
+impl Complexity {
+    fn new(lower : Option<LowerBound>, upper : Option<UpperBound>) -> Self {
+        // any problem requires at least 0 rounds
+        match (lower.unwrap_or(LowerBound::Rounds(0)), upper) {
+            (LowerBound::Rounds(a), Some(UpperBound::Rounds(b))) if a == b => Complexity::Constant(a),
+            (LowerBound::LogStar, Some(UpperBound::LogStar)) => Complexity::LogStar,
+            _ => Complexity::Between { lower, upper },
+        }
+    }
+
+    pub fn is_tight(&self) -> bool {
+        !matches!(self, Complexity::Between { .. })
+    }
+}
+
+/// The verdict of `classify`, together with the certificates of the bounds.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Classification {
+    pub complexity : Complexity,
+    pub lower : Option<LowerBoundCertificate>,
+    pub upper : Option<UpperBoundCertificate>,
+    /// The verdict and the techniques that gave the bounds, in text form.
+    pub text : String,
+}
+
+impl Display for UpperBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpperBound::Rounds(x) => write!(f, "{} rounds", x),
+            UpperBound::LogStar => write!(f, "O(log* n)"),
+        }
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Complexity::Constant(x) => write!(f, "Θ(1), exactly {} rounds", x),
+            Complexity::LogStar => write!(f, "Θ(log* n)"),
+            Complexity::Between { lower, upper } => {
+                let lower = lower.map_or("unknown".to_owned(), |b|b.to_string());
+                let upper = upper.map_or("unknown".to_owned(), |b|b.to_string());
+                write!(f, "lower bound {}, upper bound {}", lower, upper)
+            }
+        }
+    }
+}
+
+impl Classification {
+    fn new(lower : Option<LowerBoundCertificate>, upper : Option<UpperBoundCertificate>) -> Self {
+        let complexity = Complexity::new(lower.as_ref().map(|c|c.bound), upper.as_ref().map(|c|c.bound));
+        let mut text = format!("Complexity: {}", complexity);
+        if let Some(c) = &lower {
+            let technique = match &c.technique {
+                LowerBoundTechnique::RoundElimination => "round elimination".to_owned(),
+                LowerBoundTechnique::Cycle => "round elimination, reaching the same problem again".to_owned(),
+                LowerBoundTechnique::FixedPoint(_) => "the fixed point procedure".to_owned(),
+                LowerBoundTechnique::Dual { .. } => "the dual with respect to a target problem".to_owned(),
+                LowerBoundTechnique::Marks => "the marks technique".to_owned(),
+            };
+            text += &format!("\nLower bound {} obtained by {}, after {} steps.", c.bound, technique, c.sequence.len() - 1);
+        }
+        if let Some(c) = &upper {
+            let technique = match &c.technique {
+                UpperBoundTechnique::RoundElimination => "round elimination with hardenings".to_owned(),
+                UpperBoundTechnique::Coloring(colors) => format!("round elimination and a {}-coloring", colors),
+                UpperBoundTechnique::Logstar => "relaxations of maximal independent set".to_owned(),
+            };
+            text += &format!("\nUpper bound {} obtained by {}, after {} steps.", c.bound, technique, c.sequence.len() - 1);
+        }
+        Classification { complexity, lower, upper, text }
+    }
+}
+
+/// Keeps the best bounds found so far, and reports each improvement.
+struct Classifier<F> {
+    lower : Option<LowerBoundCertificate>,
+    upper : Option<UpperBoundCertificate>,
+    handler : F,
+}
+
+impl<F> Classifier<F> where F : FnMut(&Classification) {
+    fn new_lower(&mut self, certificate : LowerBoundCertificate) {
+        if self.lower.as_ref().map_or(true, |c|certificate.bound > c.bound) {
+            self.lower = Some(certificate);
+            self.report();
+        }
+    }
+
+    fn new_upper(&mut self, certificate : UpperBoundCertificate) {
+        if self.upper.as_ref().map_or(true, |c|certificate.bound < c.bound) {
+            self.upper = Some(certificate);
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        let classification = self.classification();
+        (self.handler)(&classification);
+    }
+
+    fn classification(&self) -> Classification {
+        Classification::new(self.lower.clone(), self.upper.clone())
+    }
+
+    fn is_done(&self) -> bool {
+        self.classification().complexity.is_tight()
+    }
+
+    fn lower_bound(&self) -> Option<LowerBound> {
+        self.lower.as_ref().map(|c|c.bound)
+    }
+
+    fn upper_bound(&self) -> Option<UpperBound> {
+        self.upper.as_ref().map(|c|c.bound)
+    }
+}
+
+/// Sets a flag when the given number of seconds have passed, unless it is dropped before, in which case the thread that waits is stopped and joined.
+#[cfg(not(target_arch = "wasm32"))]
+struct Timer {
+    cancel : Option<std::sync::mpsc::Sender<()>>,
+    thread : Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Timer {
+    fn new(seconds : u64, done : Arc<AtomicBool>) -> Self {
+        let (cancel, cancelled) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move ||{
+            // the sender is never used, receiving returns early only when it is dropped
+            if cancelled.recv_timeout(std::time::Duration::from_secs(seconds)) == Err(std::sync::mpsc::RecvTimeoutError::Timeout) {
+                done.store(true, Ordering::Relaxed);
+            }
+        });
+        Timer { cancel : Some(cancel), thread : Some(thread) }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.cancel.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Applies speedups without relaxing, each problem that is not 0-round solvable gives a lower bound, the first one that is 0-round solvable gives an upper bound,
+/// and a problem that is 0-round solvable given a coloring that can be computed in O(log* n) rounds gives an O(log* n) upper bound.
+fn speedups<F>(p : &Problem, budget : &ClassificationBudget, colors : [Option<usize>; 2], classifier : &mut Classifier<F>, done : &AtomicBool, eh : &mut EventHandler) where F : FnMut(&Classification) {
+    let mut p = p.clone();
+    let mut sequence = vec![(AutoOperation::Initial, p.clone())];
+    for i in 0..=budget.max_steps {
+        if p.trivial_sets.is_none() {
+            p.compute_triviality(eh);
+        }
+        if !p.trivial_sets.as_ref().unwrap().is_empty() {
+            classifier.new_upper(UpperBoundCertificate { bound : UpperBound::Rounds(i), technique : UpperBoundTechnique::RoundElimination, sequence });
+            return;
+        }
+        classifier.new_lower(LowerBoundCertificate { bound : LowerBound::Rounds(i + 1), technique : LowerBoundTechnique::RoundElimination, sequence : sequence.clone() });
+
+        // sides are swapped at each step
+        if let Some(c) = colors[i % 2] {
+            if p.coloring_sets.is_none() {
+                p.compute_coloring_solvability(eh);
+            }
+            if p.coloring_sets.as_ref().unwrap().len() >= c {
+                classifier.new_upper(UpperBoundCertificate { bound : UpperBound::LogStar, technique : UpperBoundTechnique::Coloring(c), sequence : sequence.clone() });
+            }
+        }
+
+        if classifier.is_done() || done.load(Ordering::Relaxed) || i == budget.max_steps || p.labels().len() > budget.max_labels {
+            return;
+        }
+        p = p.speedup(eh);
+        p.discard_useless_stuff(false, eh);
+        sequence.push((AutoOperation::Speedup, p.clone()));
+    }
+}
+
+impl Problem {
+    /// Tries to determine the complexity of the problem by running the automatic upper bounds (round elimination with hardenings, colorings, relaxations of maximal independent set)
+    /// and the automatic lower bounds (round elimination with relaxations, fixed points, duals with respect to sinkless orientation, marks) within `budget`.
+    /// `coloring` and `coloring_passive` are the number of colors of a coloring that is given, or that can be computed in O(log* n) rounds, on the two sides,
+    /// if they are not given, the number of colors of a distance-2 coloring is used.
+    /// `handler` is called each time a better bound is found, and the search stops as soon as the bounds match.
+    /// The strategies are run one after the other, and all of them share a flag that is set when the time is over, so that the one that is running stops.
+    pub fn classify<F>(&self, budget : &ClassificationBudget, coloring : Option<usize>, coloring_passive : Option<usize>, handler : F, eh : &mut EventHandler) -> Classification where F : FnMut(&Classification) {
+        let deadline = Utc::now() + chrono::Duration::seconds(budget.seconds as i64);
+        let done = Arc::new(AtomicBool::new(false));
+        // the strategies only check the flag, it is set when the deadline is reached, and the timer is stopped when this function returns
+        #[cfg(not(target_arch = "wasm32"))]
+        let _timer = Timer::new(budget.seconds, done.clone());
+        let time_left = || Utc::now() < deadline && !done.load(Ordering::Relaxed);
+        let mut classifier = Classifier { lower : None, upper : None, handler };
+
+        let degrees = match (self.active.degree, self.passive.degree) {
+            (Degree::Finite(d), Degree::Finite(pd)) => Some((d, pd)),
+            _ => None,
+        };
+        let colors = match degrees {
+            Some((d, pd)) => [coloring.or(Some(d * (pd - 1) + 1)), coloring_passive.or(Some(pd * (d - 1) + 1))],
+            None => [coloring, coloring_passive],
+        };
+
+        eh.notify("classify speedups", 0, 0);
+        speedups(self, budget, colors, &mut classifier, &done, eh);
+
+        // the other strategies only support problems where all nodes have the same degree
+        if !self.is_regular() {
+            return classifier.classification();
+        }
+
+        if !classifier.is_done() && time_left() {
+            eh.notify("autoub", 0, 0);
+            self.autoub(budget.max_labels, budget.branching, budget.max_steps, colors[0], colors[1], |len, is_trivial, sequence|{
+                let bound = if is_trivial { UpperBound::Rounds(len) } else { UpperBound::LogStar };
+                classifier.new_upper(UpperBoundCertificate { bound, technique : UpperBoundTechnique::RoundElimination, sequence });
+            }, eh, Some(done.clone()));
+        }
+
+        if !classifier.is_done() && time_left() {
+            // sinkless orientation, that requires Ω(log n) rounds, for the dual
+            let target = match degrees {
+                Some((d, 2)) if d >= 2 => Problem::from_string(format!("A B^{}\n\nAB B", d - 1)).ok(),
+                _ => None,
+            };
+            eh.notify("autolb", 0, 0);
+            self.autolb_combined(budget.max_labels, budget.branching, budget.max_steps, colors[0], colors[1], target.as_ref().map(|target|(target, LowerBound::Log)), |certificate|{
+                classifier.new_lower(certificate.clone());
+            }, eh, Some(done.clone()));
+        }
+
+        // the size of the tables of Marks' technique grows exponentially with the number of labels and the degrees
+        let marks_fits = degrees.is_some() && self.marks_table_size().map_or(false, |(node, edge)|node <= MARKS_MAX_TABLE_ENTRIES && edge <= MARKS_MAX_TABLE_ENTRIES);
+        if !classifier.is_done() && time_left() && marks_fits && classifier.lower_bound() < Some(LowerBound::NonBorel) {
+            eh.notify("marks", 0, 0);
+            if self.marks(eh) == Ok(true) {
+                classifier.new_lower(LowerBoundCertificate { bound : LowerBound::NonBorel, technique : LowerBoundTechnique::Marks, sequence : vec![(AutoOperation::Initial, self.clone())] });
+            }
+        }
+
+        // the automatic log* upper bound does not terminate by itself, it is stopped when the time is over
+        #[cfg(not(target_arch = "wasm32"))]
+        if !classifier.is_done() && time_left() && degrees.is_some() && budget.max_steps > 0 && classifier.upper_bound() != Some(UpperBound::LogStar) && classifier.lower_bound() <= Some(LowerBound::LogStar) {
+            eh.notify("autologstar", 0, 0);
+            let found = self.clone().autologstar_aux(budget.max_labels, budget.max_steps, String::new(), String::new(), LOGSTAR_MAX_LINES, LOGSTAR_MAX_LINES, false, &mut EventHandler::null(), Some(done.clone()));
+            if let Ok(Some((_, sequence))) = found {
+                classifier.new_upper(UpperBoundCertificate { bound : UpperBound::LogStar, technique : UpperBoundTechnique::Logstar, sequence });
+            }
+        }
+        done.store(true, Ordering::Relaxed);
+
+        classifier.classification()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::{ClassificationBudget, Complexity, UpperBound, UpperBoundTechnique};
+
+    #[test]
+    fn classify() {
+        let eh = &mut EventHandler::null();
+
+        // every node outputs the same label, 0 rounds
+        let p = Problem::from_string("A A A\n\nA A").unwrap();
+        let budget = ClassificationBudget { max_labels : 5, branching : 2, max_steps : 3, seconds : 10 };
+        let classification = p.classify(&budget, None, None, |_|{}, eh);
+        assert_eq!(classification.complexity, Complexity::Constant(0));
+
+        // sinkless orientation requires Ω(log n) rounds, no upper bound is found
+        let p = Problem::from_string("A B B\n\nAB B").unwrap();
+        let mut reported = 0;
+        let budget = ClassificationBudget { max_labels : 5, branching : 2, max_steps : 3, seconds : 0 };
+        let classification = p.classify(&budget, None, None, |_|reported += 1, eh);
+        assert!(matches!(classification.complexity, Complexity::Between { upper : None, .. }));
+        assert!(classification.lower.is_some());
+        assert!(reported > 0);
+    }
+
+    #[test]
+    fn classify_all_strategies() {
+        // maximal independent set, with colorings that have more colors than the labels of the problems that are explored, hence no upper bound is found by round elimination,
+        // and Marks' technique fails since it can be solved by Borel algorithms
+        let p = Problem::from_string("M M M\nP U U\n\nM UP\nU U").unwrap();
+        // the log* upper bound search only stops when the time is over
+        let budget = ClassificationBudget { max_labels : 5, branching : 2, max_steps : 1, seconds : 5 };
+        let mut events = vec![];
+        let classification = {
+            let eh = &mut EventHandler::with(|(s, _, _) : (String, usize, usize)|events.push(s));
+            p.classify(&budget, Some(100), Some(100), |_|{}, eh)
+        };
+        for strategy in ["classify speedups", "autoub", "autolb", "marks", "autologstar"] {
+            assert!(events.iter().any(|s|s == strategy), "{} was not run", strategy);
+        }
+        // maximal independent set is solvable in 0 rounds given itself
+        assert!(matches!(classification.upper.map(|c|c.technique), Some(UpperBoundTechnique::Logstar)));
+        assert!(matches!(classification.complexity, Complexity::Between { upper : Some(UpperBound::LogStar), .. }));
+    }
+}
@@ -15,6 +15,11 @@ use super::{event::EventHandler, max_clique::HyperGraph};
 impl Problem {
     /// Computes the number of independent actions. If that number is x, then given an x coloring it is possible to solve the problem in 0 rounds.
     pub fn compute_coloring_solvability(&mut self, eh: &mut EventHandler) {
+        // independent actions are only computed for regular problems, for the others no coloring is assumed to help
+        if !self.is_regular() {
+            self.coloring_sets = Some(vec![]);
+            return;
+        }
         if self.passive.degree != Degree::Finite(2) {
             return self.compute_hypergraph_coloring_solvability(eh);
             //panic!("cannot compute coloring solvability if the passive side has degree different from 2");
@@ -84,6 +89,11 @@ impl Problem {
         if self.coloring_sets.is_some() {
             panic!("coloring solvability has been computed already");
         }
+        // hyperedges can be arbitrarily large, and no coloring with a finite number of colors is proper
+        if self.passive.degree == Degree::Star {
+            self.coloring_sets = Some(vec![]);
+            return;
+        }
 
         self.passive.maximize(eh);
 
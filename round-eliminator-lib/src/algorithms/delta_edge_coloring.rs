@@ -70,7 +70,9 @@ impl Problem {
             }
         }
         
-        let mapping_oldlabel_labels = mapping_oldlabel_labels.into_iter().sorted().collect();
+        let mapping_oldlabel_labels : Vec<(Label, Vec<Label>)> = mapping_oldlabel_labels.into_iter().sorted().collect();
+
+        let input_allowed_outputs = self.allowed_outputs_after_relabelling(|l|mapping_oldlabel_labels.iter().filter(|(o, _)|*o == l).flat_map(|(_, v)|v.iter().cloned()).collect());
 
         let mut p = Problem {
             active,
@@ -94,7 +96,11 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs,
+            other_degrees : None
         };
         p.assign_chars();
         p
@@ -11,38 +11,141 @@ use super::event::EventHandler;
 
 
 
+/// For constraints that contain a star, the analysis is done on the finite degrees from the largest fixed part of a line up to this many more.
+/// Larger degrees are not checked, hence for these problems the results are only a heuristic.
+const STAR_DEGREES_CHECKED : usize = 3;
+
+/// Up to this many labels, all the subsets of labels are checked by `compute_demisifiable`, for more labels only some of them are checked.
+const FULL_ENUMERATION_MAX_LABELS : usize = 12;
+
+/// The constraint restricted to nodes of the given degree, where stars are replaced by the missing number of labels.
+fn with_degree(constraint : &Constraint, degree : usize) -> Constraint {
+    let lines = constraint.lines.iter().filter_map(|line|{
+        let fixed = line.degree_without_star();
+        match line.degree() {
+            Degree::Finite(d) if d == degree => Some(line.clone()),
+            Degree::Star if fixed <= degree => {
+                let mut line = line.clone();
+                for part in line.parts.iter_mut() {
+                    if part.gtype == GroupType::Star {
+                        part.gtype = GroupType::Many((degree - fixed) as Exponent);
+                    }
+                }
+                line.parts.retain(|part|part.gtype != GroupType::Many(0));
+                line.normalize();
+                Some(line)
+            }
+            _ => None
+        }
+    }).unique().collect();
+    Constraint { lines, is_maximized : false, degree : Degree::Finite(degree) }
+}
+
 impl Problem {
-    
-    pub fn compute_demisifiable<F>(&mut self, mut f : F, old : bool, eh : &mut EventHandler) where F : FnMut(Vec<Label>) {
+
+    /// Finite-degree versions of the problem, one for each pair of degrees considered for the active and the passive side, see `STAR_DEGREES_CHECKED`.
+    fn finite_instances(&self) -> Vec<Problem> {
+        let degrees = |c : &Constraint| match c.degree {
+            Degree::Finite(d) => vec![d],
+            Degree::Star => {
+                let lowest = c.lines.iter().map(|line|line.degree_without_star()).max().unwrap_or(0).max(1);
+                (lowest..=lowest + STAR_DEGREES_CHECKED).collect()
+            }
+        };
+        iproduct!(degrees(&self.active), degrees(&self.passive)).map(|(ad, pd)|{
+            let mut p = self.replace_passive(with_degree(&self.passive, pd));
+            p.active = with_degree(&self.active, ad);
+            p
+        }).collect()
+    }
+
+    /// Calls `f` on the sets of labels whose merge is reversible, see `is_set_reversible_merge`.
+    /// If there are at most `FULL_ENUMERATION_MAX_LABELS` labels, all the subsets of labels are checked, otherwise the search of `demisifiable_by_levels` is used.
+    /// Returns true if the result is exact, that is, if all the subsets have been checked and the problem does not contain stars, see `STAR_DEGREES_CHECKED`.
+    pub fn compute_demisifiable<F>(&mut self, mut f : F, old : bool, eh : &mut EventHandler) -> Result<bool, &'static str> where F : FnMut(Vec<Label>) {
+        let finite = self.active.degree != Degree::Star && self.passive.degree != Degree::Star;
         if old {
-            self.compute_demisifiable_old(eh);
-            return;
+            self.compute_demisifiable_old(eh)?;
+            return Ok(finite);
         }
-        
-        let labels = self.labels();
-        let mut result = vec![];
+        self.require_regular()?;
 
-        if self.is_set_reversible_merge(&labels) {
-            f(labels.clone());
+        let exhaustive = self.labels().len() <= FULL_ENUMERATION_MAX_LABELS;
+        let result = if exhaustive {
+            self.demisifiable_all_subsets(&mut f, eh)?
         } else {
-            println!("full set does not work");
+            self.demisifiable_by_levels(&mut f, eh)?
+        };
+        self.demisifiable = Some(result);
+        Ok(exhaustive && finite)
+    }
+
+    fn demisifiable_all_subsets<F>(&mut self, f : &mut F, eh : &mut EventHandler) -> Result<Vec<(Vec<Label>,Vec<Label>)>, &'static str> where F : FnMut(Vec<Label>) {
+        let labels = self.labels().into_iter().sorted().collect_vec();
+        let total = 1usize << labels.len();
+        let mut result = vec![];
+        for (i, subset) in labels.into_iter().powerset().enumerate() {
+            eh.notify("demisifiable", i, total);
+            if subset.len() >= 2 && self.is_set_reversible_merge(&subset)? {
+                f(subset.clone());
+                result.push((subset,vec![]));
+            }
         }
+        Ok(result)
+    }
 
-        for subset in labels.into_iter().powerset() {
-            if subset.len() >= 2 {
-                if self.is_set_reversible_merge(&subset) {
+    /// A heuristic for problems with many labels: the sets are built level by level, and a set of size s+1 is tried only if all its subsets of size s can be merged reversibly.
+    /// It is not known whether a set can be merged reversibly only if all its subsets can, hence some sets may be missed, but all the returned sets have been checked.
+    fn demisifiable_by_levels<F>(&mut self, f : &mut F, eh : &mut EventHandler) -> Result<Vec<(Vec<Label>,Vec<Label>)>, &'static str> where F : FnMut(Vec<Label>) {
+        let mut result = vec![];
+        let mut level : Vec<Vec<Label>> = self.labels().into_iter().sorted().map(|l|vec![l]).collect();
+
+        while !level.is_empty() {
+            let previous : HashSet<&Vec<Label>> = level.iter().collect();
+            let candidates : Vec<Vec<Label>> = level.iter().tuple_combinations().filter_map(|(a, b) : (&Vec<Label>, &Vec<Label>)|{
+                let s = a.len();
+                if a[..s-1] != b[..s-1] {
+                    return None;
+                }
+                let mut candidate = a.clone();
+                candidate.push(b[s-1]);
+                let closed = (0..candidate.len()).all(|i|{
+                    let mut subset = candidate.clone();
+                    subset.remove(i);
+                    previous.contains(&subset)
+                });
+                if closed { Some(candidate) } else { None }
+            }).collect();
+
+            let mut next = vec![];
+            let total = candidates.len();
+            for (i, subset) in candidates.into_iter().enumerate() {
+                eh.notify("demisifiable", i, total);
+                if self.is_set_reversible_merge(&subset)? {
                     f(subset.clone());
-                    result.push((subset,vec![]));
+                    result.push((subset.clone(),vec![]));
+                    next.push(subset);
                 }
             }
+            level = next;
         }
 
-        self.demisifiable = Some(result);
+        Ok(result)
     }
 
-    pub fn is_set_reversible_merge(&mut self, labels : &Vec<Label>) -> bool {
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("this feature is only implemented for passive degree 2");
+    /// Checks whether the problem can be solved in 0 rounds given a solution of the problem where `labels` are merged, and a coloring of the nodes that use the merged label.
+    /// On graphs the coloring is the one given by repeatedly computing a maximal independent set, so that a node of color i knows, for each j < i, an edge that leads to a node of color j.
+    /// On hypergraphs the nodes in the same hyperedge have different colors, and each node knows one of the colors of the other nodes of the hyperedge.
+    /// Problems with stars are checked on finite degrees, see `STAR_DEGREES_CHECKED`, hence for them a positive answer is only a heuristic.
+    pub fn is_set_reversible_merge(&mut self, labels : &Vec<Label>) -> Result<bool, &'static str> {
+        self.require_regular()?;
+        if self.active.degree == Degree::Star || self.passive.degree == Degree::Star {
+            for mut p in self.finite_instances() {
+                if !p.is_set_reversible_merge(labels)? {
+                    return Ok(false);
+                }
+            }
+            return Ok(true);
         }
         let m = labels[0];
         let merged = self.relax_merge_group(labels, m);
@@ -50,10 +153,13 @@ impl Problem {
 
         let mut fresh_label = merged.labels().into_iter().max().unwrap() +1;
         let d = merged.active.finite_degree();
+        let pd = merged.passive.finite_degree();
+        let on_graph = pd == 2;
+        let colors = d * pd.saturating_sub(1) + 1;
         let mut color_labels = HashMap::new();
 
-        for i in 1..=d+1 {
-            for j in 0..=d+1 {
+        for i in 1..=colors {
+            for j in 0..=colors {
                 if i != j {
                     color_labels.insert((i,j), fresh_label);
                     fresh_label += 1;
@@ -71,15 +177,16 @@ impl Problem {
             if ms == 0 {
                 new_active.lines.push(line.clone());
             } else {
-                for i in 1..=ms+1 {
+                let max_color = if on_graph { ms+1 } else { colors };
+                for i in 1..=max_color {
                     let mut j = 1;
                     let newline = line.edited(|g|{
                         let l = g.first();
                         if l == m {
-                            let g = if j < i {
+                            let g = if on_graph && j < i {
                                 Group::from(vec![color_labels[&(i,j)]])
                             } else {
-                                Group::from((0..=d+1).filter(|&j|j!=i).map(|j|color_labels[&(i,j)]).sorted().collect())
+                                Group::from((0..=colors).filter(|&j|j!=i).map(|j|color_labels[&(i,j)]).sorted().collect())
                             };
                             j += 1;
                             g
@@ -99,41 +206,30 @@ impl Problem {
             degree: merged.passive.degree,
         };
         for line in merged.passive.all_choices(false) {
-            let l1 = line.parts[0].group.first();
-            let l2 = line.parts[1].group.first();
-            if l1 != m && l2 != m {
-                new_passive.lines.push(line);
-            } else if l1 == m && l2 == m {
-                for c1 in 1..=d+1 {
-                    for c2 in 1..=d+1 {
-                        if c1 != c2 {
-                            let newline = Line{
-                                parts : vec![
-                                    Part{ group : Group::from(vec![color_labels[&(c1,c2)]]), gtype : GroupType::Many(1) },
-                                    Part{ group : Group::from(vec![color_labels[&(c2,c1)]]), gtype : GroupType::Many(1) }
-                                ]
-                            };
-                            new_passive.lines.push(newline);
-                        }
-                    }
+            let (ms, rest) : (Vec<Part>, Vec<Part>) = line.parts.into_iter().partition(|p|p.group.first() == m);
+            if ms.is_empty() {
+                new_passive.lines.push(Line{ parts : rest });
+            } else if ms.len() == 1 {
+                for c in 1..=colors {
+                    let mut parts = rest.clone();
+                    parts.push(Part{ group : Group::from(vec![color_labels[&(c,0)]]), gtype : GroupType::ONE });
+                    new_passive.lines.push(Line{ parts });
                 }
             } else {
-                let l = if l1 != m { l1 } else { l2 };
-                for c in 1..=d+1 {
-                    let newline = Line{
-                        parts : vec![
-                            Part{ group : Group::from(vec![color_labels[&(c,0)]]), gtype : GroupType::Many(1) },
-                            Part{ group : Group::from(vec![l]), gtype : GroupType::Many(1) }
-                        ]
-                    };
-                    new_passive.lines.push(newline);
+                for chosen in (1..=colors).combinations(ms.len()) {
+                    let mut parts = rest.clone();
+                    for &c in &chosen {
+                        let group = chosen.iter().filter(|&&o|o != c).map(|&o|color_labels[&(c,o)]).sorted().collect();
+                        parts.push(Part{ group : Group::from(group), gtype : GroupType::ONE });
+                    }
+                    new_passive.lines.push(Line{ parts });
                 }
             }
         }
 
         let mut mapping_label_text = merged.mapping_label_text.clone();
-        for i in 1..=d+1 {
-            for j in 0..=d+1 {
+        for i in 1..=colors {
+            for j in 0..=colors {
                 if i != j {
                     mapping_label_text.push((color_labels[&(i,j)],format!("(c_{}_{})",i,j)));
                 }
@@ -162,40 +258,38 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees : None
         };
 
         input.discard_useless_stuff(false, &mut EventHandler::null());
-        self.compute_triviality_with_input_with_sat(input);
+        self.compute_triviality_with_input_with_sat(input)?;
         let r = self.is_trivial_with_input.unwrap();
         self.is_trivial_with_input = None;
         self.triviality_with_input = None;
-        r
+        Ok(r)
     }
 
 
+    /// The labels that can appear together with `label` in some configuration of the passive constraint.
     pub fn labels_compatible_with_label(&self, label : Label) -> HashSet<Label> {
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("can only be computed when the passive degree is 2");
-        }
-
-        self.passive.lines.iter().flat_map(|line|{
-            let cur_a = &line.parts[0].group;
-            let cur_b = if line.parts.len() == 1 {
-                &line.parts[0].group
-            } else {
-                &line.parts[1].group
-            };
-            std::iter::once((cur_a, cur_b)).chain(std::iter::once((cur_b, cur_a)))
-        })
-        .filter_map(|(g1,g2)|{
-            if g1.contains(&label) {
-                Some(g2.as_set())
-            } else {
-                None
+        let mut result = HashSet::new();
+        for line in &self.passive.lines {
+            for (i, part) in line.parts.iter().enumerate() {
+                if !part.group.contains(&label) {
+                    continue;
+                }
+                for (j, other) in line.parts.iter().enumerate() {
+                    if i != j || part.gtype != GroupType::ONE {
+                        result.extend(other.group.iter().cloned());
+                    }
+                }
             }
-        })
-        .fold(HashSet::new(), |mut h1,h2|{h1.extend(h2.into_iter()); h1})
+        }
+        result
     }
 
 
@@ -203,11 +297,8 @@ impl Problem {
         if self.demisifiable.is_some() {
             panic!("demisifiable sets have been already computed");
         }
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("can only be computed when the passive degree is 2");
-        }
-        if self.active.degree == Degree::Star {
-            panic!("infinite active degree is not implemented");
+        if self.active.degree == Degree::Star || self.passive.degree == Degree::Star {
+            panic!("problems with stars are handled by compute_demisifiable_old on finite instances");
         }
         
         let labels = self.labels();
@@ -322,18 +413,9 @@ impl Problem {
             let mut compatible_with = subset.iter().map(|&x|(x,self.labels_compatible_with_label(x))).collect::<HashMap<_,_>>();
 
 
-            for line in passive.all_choices(true) {
-                let line = line.edited(|g|{
-                    let new_label = subset[g.first() as usize];
-                    Group::from(vec![new_label])
-                });
-                let l1 = line.parts[0].group.first();
-                let l2 = if line.parts[0].gtype.value() > 1 {
-                    l1
-                } else {
-                    line.parts[1].group.first()
-                };
-                if !compatible_with[&l1].contains(&l2) {
+            for line in passive.all_choices(false) {
+                let line_labels = line.parts.iter().map(|p|subset[p.group.first() as usize]).collect_vec();
+                if line_labels.iter().tuple_combinations().any(|(l1,l2)|!compatible_with[l1].contains(l2)) {
                     return None;
                 }
             }
@@ -466,27 +548,45 @@ impl Problem {
         return result2;    
     }
 
-    pub fn compute_demisifiable_old(&mut self, eh : &mut EventHandler) {
+    /// Looks for sets of labels that behave like the ones of some known problems (maximal independent set, maximal matching, 2-coloring, 3-coloring of paths), with hyperedges of the same size as the ones of the problem.
+    /// Problems with stars are checked on finite degrees, and only the sets that work for all of them are kept, hence for them the result is only a heuristic.
+    pub fn compute_demisifiable_old(&mut self, eh : &mut EventHandler) -> Result<(), &'static str> {
+        self.require_regular()?;
+        if self.active.degree == Degree::Star || self.passive.degree == Degree::Star {
+            let mut found : Option<Vec<(Vec<Label>,Vec<Label>)>> = None;
+            for mut p in self.finite_instances() {
+                p.compute_demisifiable_old(eh)?;
+                let r = p.demisifiable.unwrap();
+                found = Some(match found {
+                    None => r,
+                    Some(f) => f.into_iter().filter(|x|r.contains(x)).collect()
+                });
+            }
+            self.demisifiable = Some(found.unwrap_or_default());
+            return Ok(());
+        }
+
+        let k = self.passive.finite_degree();
         let mut r = vec![];
 
-        let p = Problem::from_string("M*\nP U*\n\nM UP\nU U").unwrap();
+        let p = Problem::from_string(format!("M*\nP U*\n\nM UP^{}\nU^{}", k-1, k)).unwrap();
         let labels : HashMap<_,_> = p.mapping_label_text.iter().cloned().map(|(l,s)|(s,l)).collect();
         let external = HashSet::from([labels["M"],labels["U"]]);
         let exclude = HashSet::from([]);
         r.extend(self.generic_demisifiable(eh, &p.active, &p.passive, &external, &exclude).into_iter());
  
-        let p = Problem::from_string("M U*\nP*\n\nM M\nUP U").unwrap();
+        let p = Problem::from_string(format!("M U*\nP*\n\nM^{}\nUP U^{}", k, k-1)).unwrap();
         let labels : HashMap<_,_> = p.mapping_label_text.iter().cloned().map(|(l,s)|(s,l)).collect();
         let external = HashSet::from([labels["U"],labels["P"]]);
         let exclude = HashSet::new();
         r.extend(self.generic_demisifiable(eh, &p.active, &p.passive, &external, &exclude).into_iter());
 
-        let p = Problem::from_string("A\nB\n\nA B").unwrap();
+        let p = Problem::from_string(format!("A\nB\n\nA B^{}", k-1)).unwrap();
         let external = HashSet::new();
         let exclude = HashSet::new();
         r.extend(self.generic_demisifiable(eh, &p.active, &p.passive, &external, &exclude).into_iter());
 
-        let p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        let p = Problem::from_string(format!("A A\nB B\nC C\n\nA BC^{}\nB C^{}", k-1, k-1)).unwrap();
         let labels : HashMap<_,_> = p.mapping_label_text.iter().cloned().map(|(l,s)|(s,l)).collect();
         let external = HashSet::from([labels["A"],labels["B"],labels["C"]]);
         let exclude = HashSet::new();
@@ -495,6 +595,7 @@ impl Problem {
         let r = r.into_iter().map(|(mut v, mut u)|{v.sort(); u.sort(); (v,u)}).unique().collect();
 
         self.demisifiable = Some(r);
+        Ok(())
     }
 
     /* 
@@ -717,4 +818,43 @@ impl Problem {
         //println!("sets: {:?}",sets);
         self.demisifiable = Some(result2);
     }*/
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use std::collections::{HashMap, HashSet};
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn demisifiable_hypergraph() {
+        let mut p = Problem::from_string("A^3\nB^3\nC^3\n\nA B^2\nA C^2").unwrap();
+        let labels : HashMap<_,_> = p.mapping_label_text.iter().cloned().map(|(l,s)|(s,l)).collect();
+        let (a, b, c) = (labels["A"], labels["B"], labels["C"]);
+
+        assert_eq!(p.labels_compatible_with_label(a), HashSet::from([b, c]));
+        assert_eq!(p.labels_compatible_with_label(b), HashSet::from([a, b]));
+
+        let mut found = vec![];
+        let exact = p.compute_demisifiable(|set|found.push(set), false, &mut EventHandler::null()).unwrap();
+        assert!(exact);
+        let mut bc = vec![b, c];
+        bc.sort();
+        assert!(found.contains(&bc));
+        assert!(found.iter().all(|set|set.len() < 3));
+    }
+
+    #[test]
+    fn demisifiable_by_levels() {
+        let eh = &mut EventHandler::null();
+        for s in ["A^3\nB^3\nC^3\n\nA B^2\nA C^2", "M M M\nP U U\n\nM UP\nU U", "A A A\nB B B\n\nA B"] {
+            let mut p = Problem::from_string(s).unwrap();
+            let all = p.demisifiable_all_subsets(&mut |_|{}, eh).unwrap();
+            let by_levels = p.demisifiable_by_levels(&mut |_|{}, eh).unwrap();
+            // the heuristic only reports sets that are found by checking all the subsets
+            assert!(by_levels.iter().all(|set|all.contains(set)));
+            // the two searches check the same pairs
+            assert!(all.iter().filter(|(set,_)|set.len() == 2).all(|set|by_levels.contains(set)));
+        }
+    }
+}
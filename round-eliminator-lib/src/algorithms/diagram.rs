@@ -28,6 +28,12 @@ impl Problem {
         if self.passive.degree != Degree::Finite(2) {
             self.passive.maximize(eh);
         }
+        if let Some(other) = self.other_degrees.as_mut() {
+            for c in other.passive.iter_mut().filter(|c|c.degree != Degree::Finite(2)) {
+                c.maximize(eh);
+            }
+        }
+        let other_passives = self.passive_constraints().split_off(1);
 
         //println!("negative relations:");
         #[cfg(not(target_arch = "wasm32"))]
@@ -40,7 +46,7 @@ impl Problem {
                 .par_bridge()
                 .map(|(l1,l2)|{
                     //if l1 == l2 || self.passive.is_diagram_predecessor(*l1, *l2).is_none() {
-                    if l1 == l2 || self.passive.is_diagram_predecessor(*l1, *l2) {
+                    if l1 == l2 || (self.passive.is_diagram_predecessor(*l1, *l2) && other_passives.iter().all(|c|c.is_diagram_predecessor(*l1, *l2))) {
                         Some((*l1, *l2))
                     } else {
                         //if l1 != l2 {
@@ -64,7 +70,7 @@ impl Problem {
             for (i, l1) in labels.iter().enumerate() {
                 for (j, l2) in labels.iter().enumerate() {
                     eh.notify("diagram", i * labels.len() + j, labels.len() * labels.len());
-                    if l1 == l2 || self.passive.is_diagram_predecessor(*l1, *l2) {
+                    if l1 == l2 || (self.passive.is_diagram_predecessor(*l1, *l2) && other_passives.iter().all(|c|c.is_diagram_predecessor(*l1, *l2))) {
                         diagram.push((*l1, *l2));
                     }
                 }
@@ -82,13 +88,14 @@ impl Problem {
         }
 
         let labels: Vec<_> = self.labels();
+        let other_passives = self.passive_constraints().split_off(1);
 
         let mut diagram = vec![];
 
         for (i, l1) in labels.iter().enumerate() {
             for (j, l2) in labels.iter().enumerate() {
                 eh.notify("diagram", i * labels.len() + j, labels.len() * labels.len());
-                if l1 == l2 || self.passive.is_diagram_predecessor_partial(*l1, *l2) {
+                if l1 == l2 || (self.passive.is_diagram_predecessor_partial(*l1, *l2) && other_passives.iter().all(|c|c.is_diagram_predecessor_partial(*l1, *l2))) {
                     diagram.push((*l1, *l2));
                 }
             }
@@ -207,6 +214,20 @@ mod tests {
 
     use crate::{algorithms::event::EventHandler, problem::Problem};
 
+    #[test]
+    fn diagram_hypergraph() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP UP\nU U U").unwrap();
+        crate::serial::fix_problem(&mut p, true, true, eh);
+        assert_eq!(p.diagram_direct.as_ref().unwrap().1, vec![(2, 1)]);
+        assert!(p.trivial_sets.as_ref().unwrap().is_empty());
+
+        let mut p = Problem::from_string("A*\nB*\n\nA*\nA B*").unwrap();
+        crate::serial::fix_problem(&mut p, true, true, eh);
+        assert!(p.diagram_direct.is_some());
+        assert!(!p.trivial_sets.as_ref().unwrap().is_empty());
+    }
+
     #[test]
     fn diagram() {
         let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
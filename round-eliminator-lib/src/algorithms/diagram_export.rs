@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::{group::Label, problem::{DiagramDirect, Problem}};
+
+use super::fixpoint::FixpointDiagram;
+
+/// How a node of the diagram is highlighted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Highlight {
+    Trivial,
+    Coloring,
+    Both,
+}
+
+impl Highlight {
+    fn svg_color(&self) -> &'static str {
+        match self {
+            Highlight::Trivial => "#b7e4b7",
+            Highlight::Coloring => "#b7d4f0",
+            Highlight::Both => "#e4dab7",
+        }
+    }
+
+    pub(crate) fn tikz_color(&self) -> &'static str {
+        match self {
+            Highlight::Trivial => "green!20",
+            Highlight::Coloring => "blue!20",
+            Highlight::Both => "yellow!30",
+        }
+    }
+}
+
+const NODE_HEIGHT : usize = 30;
+const LAYER_SPACING : usize = 80;
+const NODE_SPACING : usize = 20;
+const MARGIN : usize = 20;
+const CHAR_WIDTH : usize = 9;
+
+/// A diagram ready to be drawn: one node for each group of equivalent labels, and the edges of the transitive reduction.
+pub(crate) struct Drawing {
+    /// For each node, its representative label, the names of its labels, and its highlighting.
+    pub(crate) nodes : Vec<(Label, Vec<String>, Option<Highlight>)>,
+    pub(crate) edges : Vec<(Label, Label)>,
+}
+
+/// Positions of the nodes of a drawing, in pixels.
+pub(crate) struct Layout {
+    /// Center of each node, in the order of `Drawing::nodes`.
+    pub(crate) center : Vec<(usize, usize)>,
+    pub(crate) width : Vec<usize>,
+    /// Edges as pairs of indices of nodes.
+    pub(crate) edges : Vec<(usize, usize)>,
+    pub(crate) total_width : usize,
+    pub(crate) total_height : usize,
+}
+
+impl Drawing {
+    fn new(diagram : &DiagramDirect, mapping : &HashMap<Label, String>, trivial : &HashSet<Label>, coloring : &HashSet<Label>) -> Self {
+        let nodes = diagram.0.iter().map(|(repr, group)|{
+            let names = group.iter().map(|l|mapping.get(l).cloned().unwrap_or_else(||l.to_string())).collect();
+            let is_trivial = group.iter().any(|l|trivial.contains(l));
+            let is_coloring = group.iter().any(|l|coloring.contains(l));
+            let color = match (is_trivial, is_coloring) {
+                (true, true) => Some(Highlight::Both),
+                (true, false) => Some(Highlight::Trivial),
+                (false, true) => Some(Highlight::Coloring),
+                (false, false) => None,
+            };
+            (*repr, names, color)
+        }).collect();
+        Self { nodes, edges : diagram.1.clone() }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "digraph diagram {{").unwrap();
+        writeln!(s, "    node [shape=box, style=\"rounded,filled\", fillcolor=white, fontname=\"monospace\"];").unwrap();
+        for (label, names, highlight) in &self.nodes {
+            let color = highlight.map(|h|h.svg_color()).unwrap_or("white");
+            writeln!(s, "    n{} [label=\"{}\", fillcolor=\"{}\"];", label, dot_escape(&names.join(" ")), color).unwrap();
+        }
+        for (a, b) in &self.edges {
+            writeln!(s, "    n{} -> n{};", a, b).unwrap();
+        }
+        writeln!(s, "}}").unwrap();
+        s
+    }
+
+    /// Draws the diagram in layers: each node is placed one layer below all its predecessors,
+    /// and the order of the nodes inside each layer is improved by a few rounds of the barycenter heuristic, to reduce crossings.
+    pub(crate) fn layout(&self) -> Layout {
+        let index : HashMap<Label, usize> = self.nodes.iter().enumerate().map(|(i, (l, _, _))|(*l, i)).collect();
+        let edges : Vec<(usize, usize)> = self.edges.iter().filter_map(|(a, b)|Some((*index.get(a)?, *index.get(b)?))).collect();
+        let n = self.nodes.len();
+        let mut pred = vec![vec![]; n];
+        let mut succ = vec![vec![]; n];
+        for &(a, b) in &edges {
+            succ[a].push(b);
+            pred[b].push(a);
+        }
+
+        // longest path layering, the diagram is a DAG
+        let mut layer = vec![0usize; n];
+        let mut indegree : Vec<usize> = pred.iter().map(|p|p.len()).collect();
+        let mut queue : Vec<usize> = (0..n).filter(|&i|indegree[i] == 0).collect();
+        while let Some(x) = queue.pop() {
+            for &y in &succ[x] {
+                layer[y] = layer[y].max(layer[x] + 1);
+                indegree[y] -= 1;
+                if indegree[y] == 0 {
+                    queue.push(y);
+                }
+            }
+        }
+        let num_layers = layer.iter().max().map(|m|m + 1).unwrap_or(0);
+        let mut layers : Vec<Vec<usize>> = vec![vec![]; num_layers];
+        for i in 0..n {
+            layers[layer[i]].push(i);
+        }
+
+        let mut position = vec![0f64; n];
+        let update = |layers : &Vec<Vec<usize>>, position : &mut Vec<f64>| {
+            for l in layers {
+                for (p, &x) in l.iter().enumerate() {
+                    position[x] = p as f64;
+                }
+            }
+        };
+        update(&layers, &mut position);
+        for round in 0..8 {
+            let (order, neighbors) : (Vec<usize>, &Vec<Vec<usize>>) = if round % 2 == 0 { ((1..num_layers).collect(), &pred) } else { ((0..num_layers.saturating_sub(1)).rev().collect(), &succ) };
+            for l in order {
+                let barycenter = |x : usize| {
+                    if neighbors[x].is_empty() { position[x] } else { neighbors[x].iter().map(|&y|position[y]).sum::<f64>() / neighbors[x].len() as f64 }
+                };
+                let sorted = layers[l].iter().cloned().sorted_by(|&x, &y|barycenter(x).partial_cmp(&barycenter(y)).unwrap()).collect();
+                layers[l] = sorted;
+                update(&layers, &mut position);
+            }
+        }
+
+        let width : Vec<usize> = self.nodes.iter().map(|(_, names, _)|(names.join(" ").chars().count() * CHAR_WIDTH + 20).max(40)).collect();
+        let width_of = |i : usize| width[i];
+        let layer_width = |l : &Vec<usize>| l.iter().map(|&i|width_of(i)).sum::<usize>() + NODE_SPACING * l.len().saturating_sub(1);
+        let total_width = layers.iter().map(layer_width).max().unwrap_or(0) + 2 * MARGIN;
+        let total_height = num_layers * NODE_HEIGHT + num_layers.saturating_sub(1) * (LAYER_SPACING - NODE_HEIGHT) + 2 * MARGIN;
+
+        // center of each node
+        let mut center = vec![(0usize, 0usize); n];
+        for (l, nodes) in layers.iter().enumerate() {
+            let mut x = (total_width - layer_width(nodes)) / 2;
+            for &i in nodes {
+                center[i] = (x + width_of(i) / 2, MARGIN + l * LAYER_SPACING + NODE_HEIGHT / 2);
+                x += width_of(i) + NODE_SPACING;
+            }
+        }
+
+        Layout { center, width, edges, total_width, total_height }
+    }
+
+    fn to_svg(&self) -> String {
+        let Layout { center, width, edges, total_width, total_height } = self.layout();
+        let mut s = String::new();
+        writeln!(s, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", total_width, total_height, total_width, total_height).unwrap();
+        writeln!(s, "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\"><path d=\"M 0 0 L 10 5 L 0 10 z\"/></marker></defs>").unwrap();
+        for &(a, b) in &edges {
+            let (x1, y1) = center[a];
+            let (x2, y2) = center[b];
+            writeln!(s, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" marker-end=\"url(#arrow)\"/>", x1, y1 + NODE_HEIGHT / 2, x2, y2 - NODE_HEIGHT / 2).unwrap();
+        }
+        for (i, (_, names, highlight)) in self.nodes.iter().enumerate() {
+            let (x, y) = center[i];
+            let w = width[i];
+            let color = highlight.map(|h|h.svg_color()).unwrap_or("white");
+            writeln!(s, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" fill=\"{}\" stroke=\"black\"/>", x - w / 2, y - NODE_HEIGHT / 2, w, NODE_HEIGHT, color).unwrap();
+            writeln!(s, "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"14\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>", x, y, xml_escape(&names.join(" "))).unwrap();
+        }
+        writeln!(s, "</svg>").unwrap();
+        s
+    }
+}
+
+fn dot_escape(s : &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(s : &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl Problem {
+    /// Labels of the trivial sets and of the coloring sets, the latter only if they give a coloring algorithm.
+    fn highlighted_labels(&self) -> (HashSet<Label>, HashSet<Label>) {
+        let trivial = self.trivial_sets.iter().flatten().flatten().cloned().collect();
+        let coloring = self.coloring_sets.iter().filter(|c|c.len() >= 2).flatten().flatten().cloned().collect();
+        (trivial, coloring)
+    }
+
+    pub(crate) fn diagram_drawing(&self) -> Result<Drawing, &'static str> {
+        let diagram = self.diagram_direct.as_ref().ok_or("The diagram has not been computed.")?;
+        let mapping = self.mapping_label_text.iter().cloned().collect();
+        let (trivial, coloring) = self.highlighted_labels();
+        Ok(Drawing::new(diagram, &mapping, &trivial, &coloring))
+    }
+
+    /// The diagram in the DOT format of Graphviz, with equivalent labels in the same node.
+    /// Labels that appear in trivial sets or in coloring sets are highlighted.
+    pub fn diagram_to_dot(&self) -> Result<String, &'static str> {
+        Ok(self.diagram_drawing()?.to_dot())
+    }
+
+    /// The diagram as a standalone SVG image, see `diagram_to_dot`.
+    pub fn diagram_to_svg(&self) -> Result<String, &'static str> {
+        Ok(self.diagram_drawing()?.to_svg())
+    }
+
+    /// The diagram used by the last fixed point computation, in the DOT format of Graphviz.
+    pub fn fixpoint_diagram_to_dot(&self) -> Result<String, &'static str> {
+        let (_, fd) = self.fixpoint_diagram.as_ref().ok_or("The fixed point diagram has not been computed.")?;
+        Ok(fd.to_dot())
+    }
+
+    /// The diagram used by the last fixed point computation, as a standalone SVG image.
+    pub fn fixpoint_diagram_to_svg(&self) -> Result<String, &'static str> {
+        let (_, fd) = self.fixpoint_diagram.as_ref().ok_or("The fixed point diagram has not been computed.")?;
+        Ok(fd.to_svg())
+    }
+}
+
+impl FixpointDiagram {
+    pub(crate) fn drawing(&self) -> Drawing {
+        let (diagram, mapping) = self.direct_diagram_and_text();
+        let mapping = mapping.iter().cloned().collect();
+        Drawing::new(diagram, &mapping, &HashSet::new(), &HashSet::new())
+    }
+
+    pub fn to_dot(&self) -> String {
+        self.drawing().to_dot()
+    }
+
+    pub fn to_svg(&self) -> String {
+        self.drawing().to_svg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn diagram_export() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(p.diagram_to_dot().is_err());
+        p.compute_diagram(eh);
+        p.compute_triviality(eh);
+
+        let dot = p.diagram_to_dot().unwrap();
+        assert!(dot.starts_with("digraph"));
+        // U is stronger than P
+        assert_eq!(dot.matches("->").count(), 1);
+        assert_eq!(dot.matches("[label=").count(), 3);
+
+        let svg = p.diagram_to_svg().unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert_eq!(svg.matches("<line").count(), 1);
+    }
+}
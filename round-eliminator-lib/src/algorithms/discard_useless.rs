@@ -4,8 +4,8 @@ use super::event::EventHandler;
 
 impl Problem {
     pub fn discard_labels_used_on_at_most_one_side_from_configurations(&mut self) {
-        let labels_active = self.active.labels_appearing();
-        let labels_passive = self.passive.labels_appearing();
+        let labels_active = self.active_labels_appearing();
+        let labels_passive = self.passive_labels_appearing();
         let to_keep = labels_active
             .intersection(&labels_passive)
             .cloned()
@@ -13,10 +13,11 @@ impl Problem {
         let newp = self.harden_keep(&to_keep, false);
         self.active = newp.active;
         self.passive = newp.passive;
+        self.other_degrees = newp.other_degrees;
     }
 
     pub fn discard_unused_labels_from_internal_stuff(&mut self) {
-        let to_keep = self.active.labels_appearing();
+        let to_keep = self.active_labels_appearing();
 
         self.mapping_label_text.retain(|(l, _)| to_keep.contains(l));
         if let Some(x) = self.mapping_label_oldlabels.as_mut() {
@@ -61,6 +62,11 @@ impl Problem {
             eh.notify("discard non maximal", 1, 1);
             self.passive.discard_non_maximal_lines();
             self.active.discard_non_maximal_lines();
+            if let Some(other) = self.other_degrees.as_mut() {
+                for c in other.active.iter_mut().chain(other.passive.iter_mut()) {
+                    c.discard_non_maximal_lines();
+                }
+            }
             eh.notify("discard labels at most one side", 1, 1);
             self.discard_labels_used_on_at_most_one_side_from_configurations();
             eh.notify("discard unused internal", 1, 1);
@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::sync::{atomic::{AtomicUsize, Ordering}, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashSet;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{constraint::Constraint, line::{CompressedLine, Line}};
+
+use super::{event::EventHandler, maximize::{combine_lines, without_one}};
+
+/// Number of line pairs sent to a worker at once.
+const BATCH_SIZE : usize = 64;
+
+/// Messages whose payload, compressed or not, is larger than this are rejected, so that a peer cannot make the process allocate an arbitrary amount of memory.
+const MAX_FRAME_SIZE : usize = 1 << 30;
+
+/// If a peer does not send or accept data for this many seconds, it is considered disconnected. It can be changed with the environment variable RE_WORKER_TIMEOUT.
+const DEFAULT_TIMEOUT_SECS : u64 = 600;
+
+#[derive(Serialize, Deserialize)]
+enum ToWorker {
+    /// Starts a new computation of maximize, the lines generated so far are forgotten.
+    Reset,
+    /// The lines of the current step of maximize.
+    Step(Vec<Line>),
+    /// Batch id and pairs of line indices to combine.
+    Pairs(usize, Vec<(usize,usize)>),
+}
+
+#[derive(Serialize, Deserialize)]
+enum FromWorker {
+    /// Batch id and the maximal lines obtained by combining the pairs of the batch.
+    Lines(usize, Vec<Line>),
+}
+
+pub trait Stream : Read + Write + Send {}
+impl<T> Stream for T where T : Read + Write + Send {}
+
+fn timeout_from_env() -> Duration {
+    let secs = std::env::var("RE_WORKER_TIMEOUT").ok().and_then(|s|s.parse().ok()).unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+fn tcp_stream(stream : std::net::TcpStream) -> std::io::Result<Box<dyn Stream>> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(timeout_from_env()))?;
+    stream.set_write_timeout(Some(timeout_from_env()))?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(unix)]
+fn unix_stream(stream : std::os::unix::net::UnixStream) -> std::io::Result<Box<dyn Stream>> {
+    stream.set_read_timeout(Some(timeout_from_env()))?;
+    stream.set_write_timeout(Some(timeout_from_env()))?;
+    Ok(Box::new(stream))
+}
+
+/// Addresses are either `tcp:host:port` or `unix:path`. An address without prefix is treated as tcp.
+pub fn connect(addr : &str) -> Result<Box<dyn Stream>, String> {
+    if let Some(path) = addr.strip_prefix("unix:") {
+        #[cfg(unix)]
+        return std::os::unix::net::UnixStream::connect(path).and_then(unix_stream).map_err(|e|format!("cannot connect to {}: {}", addr, e));
+        #[cfg(not(unix))]
+        return Err(format!("unix sockets are not supported on this platform: {}", path));
+    }
+    let addr = addr.strip_prefix("tcp:").unwrap_or(addr);
+    std::net::TcpStream::connect(addr).and_then(tcp_stream).map_err(|e|format!("cannot connect to {}: {}", addr, e))
+}
+
+fn frame_too_large() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, "message too large")
+}
+
+/// Each message is the length of the payload followed by the payload, that is bincode compressed with lz4.
+fn send<T : Serialize>(stream : &mut dyn Stream, msg : &T) -> std::io::Result<()> {
+    let encoded = bincode::serialize(msg).map_err(|e|std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if encoded.len() > MAX_FRAME_SIZE {
+        return Err(frame_too_large());
+    }
+    let compressed = lz4_flex::compress_prepend_size(&encoded[..]);
+    stream.write_all(&(compressed.len() as u64).to_le_bytes())?;
+    stream.write_all(&compressed)?;
+    stream.flush()
+}
+
+/// Timeouts are reported as errors, and hence they are handled as disconnections.
+fn receive<T : DeserializeOwned>(stream : &mut dyn Stream) -> std::io::Result<T> {
+    let mut len = [0u8; 8];
+    stream.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+    if len > MAX_FRAME_SIZE as u64 || len < 4 {
+        return Err(frame_too_large());
+    }
+    let mut compressed = vec![0u8; len as usize];
+    stream.read_exact(&mut compressed)?;
+    // the size of the decompressed payload is prepended by lz4
+    if u32::from_le_bytes(compressed[..4].try_into().unwrap()) as usize > MAX_FRAME_SIZE {
+        return Err(frame_too_large());
+    }
+    let encoded = lz4_flex::decompress_size_prepended(&compressed).map_err(|e|std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    bincode::deserialize(&encoded).map_err(|e|std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Combines the given pairs of lines, and returns the maximal lines among the results.
+fn combine_pairs(lines : &[Line], without_one : &[Vec<Line>], pairs : &[(usize,usize)], seen : &DashSet<CompressedLine>) -> Vec<Line> {
+    let candidates : Vec<Vec<Line>> = pairs.par_iter().map(|&(i,j)|{
+        combine_lines(&lines[i], &lines[j], &without_one[i], &without_one[j], seen, 100, false)
+    }).collect();
+    let mut result = Constraint { lines : vec![], is_maximized : false, degree : lines[0].degree() };
+    for newline in candidates.into_iter().flatten() {
+        result.add_line_and_discard_non_maximal(newline);
+    }
+    result.lines
+}
+
+fn serve_connection(mut stream : Box<dyn Stream>) {
+    let mut lines = vec![];
+    let mut without = vec![];
+    // `seen` is local to the connection, lines that are generated by other workers may be generated again, but they are discarded by the coordinator
+    let seen = DashSet::new();
+    loop {
+        match receive::<ToWorker>(stream.as_mut()) {
+            Ok(ToWorker::Reset) => {
+                seen.clear();
+            }
+            Ok(ToWorker::Step(newlines)) => {
+                without = without_one(&newlines);
+                lines = newlines;
+            }
+            Ok(ToWorker::Pairs(id, pairs)) => {
+                let result = combine_pairs(&lines, &without, &pairs, &seen);
+                if send(stream.as_mut(), &FromWorker::Lines(id, result)).is_err() {
+                    return;
+                }
+            }
+            Err(_) => {
+                return;
+            }
+        }
+    }
+}
+
+/// Runs a worker that accepts connections from coordinators on the given address, see `connect` for the format of addresses.
+/// It never returns, unless the address cannot be used.
+pub fn run_worker(addr : &str) -> Result<(), String> {
+    if let Some(path) = addr.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            let _ = std::fs::remove_file(path);
+            let listener = std::os::unix::net::UnixListener::bind(path).map_err(|e|format!("cannot listen on {}: {}", addr, e))?;
+            for stream in listener.incoming().flatten() {
+                if let Ok(stream) = unix_stream(stream) {
+                    std::thread::spawn(move ||serve_connection(stream));
+                }
+            }
+            return Ok(());
+        }
+        #[cfg(not(unix))]
+        return Err(format!("unix sockets are not supported on this platform: {}", path));
+    }
+    let addr = addr.strip_prefix("tcp:").unwrap_or(addr);
+    let listener = std::net::TcpListener::bind(addr).map_err(|e|format!("cannot listen on {}: {}", addr, e))?;
+    serve_tcp(listener);
+    Ok(())
+}
+
+pub fn serve_tcp(listener : std::net::TcpListener) {
+    for stream in listener.incoming().flatten() {
+        if let Ok(stream) = tcp_stream(stream) {
+            std::thread::spawn(move ||serve_connection(stream));
+        }
+    }
+}
+
+/// Reads the list of workers from the environment variable RE_WORKERS, a comma separated list of addresses.
+pub fn workers_from_env() -> Option<Vec<String>> {
+    let workers : Vec<String> = std::env::var("RE_WORKERS").ok()?.split(',').map(|s|s.trim().to_owned()).filter(|s|!s.is_empty()).collect();
+    if workers.is_empty() { None } else { Some(workers) }
+}
+
+fn connect_all(workers : &[String]) -> Vec<Box<dyn Stream>> {
+    workers.iter().filter_map(|addr|{
+        match connect(addr) {
+            Ok(stream) => Some(stream),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        }
+    }).collect()
+}
+
+static WORKER_POOL : OnceLock<Option<Mutex<Vec<Box<dyn Stream>>>>> = OnceLock::new();
+
+/// The connections to the workers given by `workers_from_env`, or None if there are none.
+/// The workers are contacted at the first call, and the connections are then shared by all the calls of `maximize`; workers that disconnect are not contacted again.
+pub(crate) fn worker_pool() -> Option<&'static Mutex<Vec<Box<dyn Stream>>>> {
+    WORKER_POOL.get_or_init(||workers_from_env().map(|workers|Mutex::new(connect_all(&workers)))).as_ref()
+}
+
+/// Sends the batches to the workers, and returns the results of all batches.
+/// If a worker disconnects, the batch it was working on is given to another worker, and the worker is not used anymore.
+/// If no worker is left, the remaining batches are computed locally.
+fn run_step(workers : &mut Vec<Box<dyn Stream>>, lines : &[Line], batches : Vec<Vec<(usize,usize)>>, eh : &mut EventHandler) -> Vec<Line> {
+    workers.retain_mut(|w|send(w.as_mut(), &ToWorker::Step(lines.to_vec())).is_ok());
+
+    let total = batches.len();
+    let (batch_tx, batch_rx) = crossbeam_channel::unbounded();
+    for (id, batch) in batches.into_iter().enumerate() {
+        batch_tx.send((id, batch)).unwrap();
+    }
+    let (result_tx, result_rx) = crossbeam_channel::unbounded();
+    let remaining = AtomicUsize::new(total);
+    let alive = AtomicUsize::new(workers.len());
+
+    let mut results = vec![];
+
+    crossbeam::scope(|s| {
+        let handles : Vec<_> = workers.drain(..).map(|mut worker|{
+            let batch_rx = batch_rx.clone();
+            let batch_tx = batch_tx.clone();
+            let result_tx = result_tx.clone();
+            let remaining = &remaining;
+            let alive = &alive;
+            s.spawn(move |_|{
+                while remaining.load(Ordering::SeqCst) > 0 {
+                    let (id, batch) = match batch_rx.recv_timeout(Duration::from_millis(100)) {
+                        Ok(x) => x,
+                        Err(_) => continue
+                    };
+                    let answer = send(worker.as_mut(), &ToWorker::Pairs(id, batch.clone())).and_then(|_|receive::<FromWorker>(worker.as_mut()));
+                    match answer {
+                        Ok(FromWorker::Lines(rid, lines)) if rid == id => {
+                            remaining.fetch_sub(1, Ordering::SeqCst);
+                            result_tx.send(lines).unwrap();
+                        }
+                        _ => {
+                            eprintln!("a worker disconnected or timed out, its work is given to the other workers");
+                            batch_tx.send((id, batch)).unwrap();
+                            alive.fetch_sub(1, Ordering::SeqCst);
+                            return None;
+                        }
+                    }
+                }
+                Some(worker)
+            })
+        }).collect();
+        drop(result_tx);
+
+        let mut received = 0;
+        let mut last_notify = Instant::now();
+        while received < total {
+            if alive.load(Ordering::SeqCst) == 0 {
+                // no worker is left, the remaining batches are computed here
+                let lines = lines.to_vec();
+                let without = without_one(&lines);
+                let seen = DashSet::new();
+                while let Ok((_, batch)) = batch_rx.try_recv() {
+                    results.push(combine_pairs(&lines, &without, &batch, &seen));
+                    remaining.fetch_sub(1, Ordering::SeqCst);
+                    received += 1;
+                }
+                break;
+            }
+            if let Ok(lines) = result_rx.recv_timeout(Duration::from_millis(100)) {
+                results.push(lines);
+                received += 1;
+            }
+            if last_notify.elapsed().as_millis() > 100 {
+                eh.notify("combining line pairs (distributed)", received, total);
+                last_notify = Instant::now();
+            }
+        }
+        remaining.store(0, Ordering::SeqCst);
+        for handle in handles {
+            if let Some(worker) = handle.join().unwrap() {
+                workers.push(worker);
+            }
+        }
+    }).unwrap();
+
+    // results that arrived after the last check
+    while let Ok(lines) = result_rx.try_recv() {
+        results.push(lines);
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+impl Constraint {
+    /// Same as `maximize`, but the pairs of lines are combined by the given workers, see `run_worker`.
+    /// Workers that cannot be reached or that disconnect are ignored.
+    pub fn maximize_distributed(&mut self, workers : &[String], eh : &mut EventHandler) -> Result<(), String> {
+        if self.is_maximized || self.lines.is_empty() {
+            self.is_maximized = true;
+            return Ok(());
+        }
+        self.maximize_with_workers(&mut connect_all(workers), eh)
+    }
+
+    /// Same as `maximize_distributed`, but using connections that are already open, the ones of the workers that disconnect are removed.
+    pub(crate) fn maximize_with_workers(&mut self, streams : &mut Vec<Box<dyn Stream>>, eh : &mut EventHandler) -> Result<(), String> {
+        if self.is_maximized || self.lines.is_empty() {
+            self.is_maximized = true;
+            return Ok(());
+        }
+
+        streams.retain_mut(|w|send(w.as_mut(), &ToWorker::Reset).is_ok());
+        if streams.is_empty() {
+            return Err("Could not connect to any worker.".into());
+        }
+
+        let lines = std::mem::take(&mut self.lines);
+        for mut line in lines {
+            line.normalize();
+            self.add_line_and_discard_non_maximal(line);
+        }
+
+        let mut lines_of_previous_step : HashSet<Line> = HashSet::new();
+        loop {
+            let lines = self.lines.clone();
+            let pairs : Vec<(usize,usize)> = (0..lines.len())
+                .flat_map(|i|(0..=i).map(move |j|(i,j)))
+                .filter(|&(i,j)|!(lines_of_previous_step.contains(&lines[i]) && lines_of_previous_step.contains(&lines[j])))
+                .collect();
+            let batches = pairs.chunks(BATCH_SIZE).map(|c|c.to_vec()).collect();
+
+            let candidates = run_step(streams, &lines, batches, eh);
+
+            let mut newconstraint = self.clone();
+            for newline in candidates {
+                newconstraint.add_line_and_discard_non_maximal(newline);
+            }
+            if &newconstraint == self {
+                break;
+            }
+            *self = newconstraint;
+            lines_of_previous_step = lines.into_iter().collect();
+        }
+
+        self.is_maximized = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn distributed_maximize() {
+        let eh = &mut EventHandler::null();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = format!("tcp:{}", listener.local_addr().unwrap());
+        std::thread::spawn(move ||super::serve_tcp(listener));
+
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut expected = p.passive.clone();
+        expected.maximize(eh);
+
+        // the second worker does not exist, and it is ignored
+        let mut c = p.passive.clone();
+        c.maximize_distributed(&[addr.clone(), "tcp:127.0.0.1:1".into()], eh).unwrap();
+
+        let mut a = c.lines.clone();
+        let mut b = expected.lines.clone();
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+
+        // the same connections are used for more computations, and the lines generated by the previous ones are forgotten
+        let mut streams = super::connect_all(&[addr]);
+        for _ in 0..2 {
+            let mut c = p.passive.clone();
+            c.maximize_with_workers(&mut streams, eh).unwrap();
+            let mut a = c.lines.clone();
+            a.sort();
+            assert_eq!(a, b);
+        }
+        assert_eq!(streams.len(), 1);
+    }
+
+    #[test]
+    fn distributed_frame_size() {
+        // the announced length is rejected before allocating the buffer
+        let mut stream = std::io::Cursor::new(u64::MAX.to_le_bytes().to_vec());
+        let err = super::receive::<super::ToWorker>(&mut stream).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // the length of the decompressed payload is checked as well
+        let mut frame = 4u64.to_le_bytes().to_vec();
+        frame.extend(u32::MAX.to_le_bytes());
+        let mut stream = std::io::Cursor::new(frame);
+        let err = super::receive::<super::ToWorker>(&mut stream).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // valid messages are still received
+        let mut stream = std::io::Cursor::new(vec![]);
+        super::send(&mut stream, &super::ToWorker::Pairs(3, vec![(1,2)])).unwrap();
+        stream.set_position(0);
+        assert!(matches!(super::receive::<super::ToWorker>(&mut stream).unwrap(), super::ToWorker::Pairs(3, _)));
+    }
+}
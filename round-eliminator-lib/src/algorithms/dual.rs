@@ -1,12 +1,15 @@
 use std::collections::{HashMap, HashSet};
 
-use env_logger::init;
 use itertools::Itertools;
-use serde_json::map;
 
-use crate::{algorithms::{choices::left_labels, fixpoint::{parse_diagram, FixpointType}}, constraint::Constraint, group::{Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem};
+use crate::{algorithms::fixpoint::{parse_diagram, FixpointType}, constraint::Constraint, group::{Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem};
 
-use super::{diagram::{compute_direct_diagram, diagram_direct_to_pred_adj, diagram_direct_to_succ_adj, diagram_indirect_to_reachability_adj, diagram_to_indirect}, event::EventHandler};
+use super::{automorphisms::LabelPermutation, diagram::{diagram_indirect_to_reachability_adj, diagram_to_indirect}, event::EventHandler, speedup::symmetry_from_env};
+
+/// Maximum number of configurations visited on each side while computing the dual.
+const MAX_DUAL_CONFIGURATIONS : usize = 1_000_000;
+/// Maximum number of labels of the dual, and of the dual of the dual.
+const MAX_DUAL_LABELS : usize = 5_000;
 
 
 impl Line {
@@ -53,115 +56,192 @@ fn dual_diagram(labels_p : &Vec<Label>, labels_d : &Vec<Vec<Label>>, labels_fp :
     diagram
 }
 
-fn dual_constraint(cp : &Constraint, cf : &Constraint, labels : &Vec<Vec<Label>>, labels_p : &Vec<Label>, all_predecessors : &HashMap<Label, HashSet<Label>>, all_successors : &HashMap<Label, HashSet<Label>>, direct_pred : &HashMap<Label, HashSet<Label>>) -> Result<Constraint, &'static str> {
-    let labels_p_to_positions : HashMap<_,_> = labels_p.iter().copied().enumerate().map(|(i,l)|(l,i)).collect();
-    let d = cp.finite_degree();
-    let labels_d = all_successors.keys().copied().collect_vec();
+/// For the labels of f ordered by `reach`, returns the labels that are not reached from any other label, and for each label the labels that are reached from it in one step.
+fn search_order(labels_f : &[Label], reach : &HashMap<Label, HashSet<Label>>) -> (Vec<Label>, HashMap<Label, Vec<Label>>) {
+    let strictly = |a : Label, b : Label| a != b && reach[&a].contains(&b) && !reach[&b].contains(&a);
+    let start = labels_f.iter().copied().filter(|&l|!labels_f.iter().any(|&x|strictly(x, l))).collect();
+    let next = labels_f.iter().map(|&l|{
+        let after = labels_f.iter().copied().filter(|&x|strictly(l, x)).collect_vec();
+        let direct = after.iter().copied().filter(|&x|!after.iter().any(|&y|strictly(y, x))).collect_vec();
+        (l, direct)
+    }).collect();
+    (start, next)
+}
 
+/// Number of configurations the search of `dual_configurations` starts from, that is, the number of multisets of size `d` of the dual labels made of `start` labels of f, or None if it does not fit in a usize.
+fn initial_configurations(start : usize, labels_p : usize, d : usize) -> Option<usize> {
+    let m = start.checked_pow(labels_p as u32)?;
+    // after step i, r is the binomial coefficient (m + i choose i + 1), hence the division is exact
+    (0..d).try_fold(1usize, |r, i|Some(r.checked_mul(m.checked_add(i)?)? / (i + 1)))
+}
 
-    let to_vector = |line : &Line|{
-        line.iter_labels().collect_vec()
-    };
+/// Converts label permutations of the problem into permutations of the positions of the labels in `labels_p`.
+fn position_permutations(labels_p : &[Label], generators : &[LabelPermutation]) -> Vec<Vec<usize>> {
+    let labels_p_to_positions : HashMap<_,_> = labels_p.iter().copied().enumerate().map(|(i,l)|(l,i)).collect();
+    generators.iter().map(|perm|{
+        labels_p.iter().map(|l|labels_p_to_positions[perm.get(l).unwrap_or(l)]).collect_vec()
+    }).collect()
+}
 
-    let to_configuration = |line : &Vec<Label>|{
-        let parts = line.into_iter().map(|l|{
-            Part{
-                gtype: GroupType::Many(1),
-                group: Group::from(vec![*l]),
+/// All the configurations obtained by permuting the coordinates of the dual labels of `configuration` according to the generators.
+fn configuration_orbit(configuration : &Vec<Vec<Label>>, generators : &[Vec<usize>]) -> HashSet<Vec<Vec<Label>>> {
+    let mut orbit = HashSet::new();
+    let mut queue = vec![configuration.clone()];
+    orbit.insert(configuration.clone());
+    while let Some(configuration) = queue.pop() {
+        for perm in generators {
+            let mut image = configuration.iter().map(|v|{
+                let mut w = v.clone();
+                for (i, &j) in perm.iter().enumerate() {
+                    w[j] = v[i];
+                }
+                w
+            }).collect_vec();
+            image.sort();
+            if orbit.insert(image.clone()) {
+                queue.push(image);
             }
-        }).collect_vec();
-        Line{ parts }
-    };
+        }
+    }
+    orbit
+}
 
-    let is_linep_bad_for_lined = |line_d : &Vec<Label>, line_p : &Vec<Label>|{
-        let parts_f = line_p.iter().enumerate().map(|(i,&l)|{
-            Part{
-                gtype : GroupType::Many(1),
-                group : Group::from(vec![labels[line_d[i] as usize][labels_p_to_positions[&l]] as Label])
-            }
-        }).collect_vec();
-        let line_f = Line{ parts : parts_f };
-        !cf.includes(&line_f)
-    };
+/// The smallest configuration of the orbit, used to visit only one configuration for each orbit.
+fn canonical_configuration(configuration : Vec<Vec<Label>>, generators : &[Vec<usize>]) -> Vec<Vec<Label>> {
+    if generators.is_empty() {
+        return configuration;
+    }
+    configuration_orbit(&configuration, generators).into_iter().min().unwrap()
+}
 
-    let find_bad_linep_for_lined = |line_d : &Vec<Label>|{
-        cp.all_choices(false).into_iter().flat_map(|line_p|to_vector(&line_p).into_iter().permutations(d))
-                .find(|line_p|{
-                    is_linep_bad_for_lined(line_d,line_p)
-                })
-    };
+/// Computes the good configurations of one side of the dual, as sorted vectors of dual labels, where a dual label is a vector containing a label of f for each label of the problem.
+/// A configuration is good if, for each (ordered) configuration of `cp`, replacing each label by the label of f that the corresponding dual label assigns to it gives a configuration allowed by `cf`.
+/// Dual labels are created only when they are reached: the search starts from the dual labels made of `start` labels of f, and when a configuration is bad,
+/// it tries to move each of its dual labels one step along `next`, on the label of the problem that makes it bad.
+/// Configurations are handled as multisets, so permutations of the same configuration are visited only once.
+/// If `generators` is not empty, it must contain label permutations that preserve `cp`: they map good configurations to good configurations,
+/// hence only the smallest configuration of each orbit is visited, and the orbits of the good ones are added at the end.
+fn dual_configurations(cp : &Constraint, cf : &Constraint, labels_p : &[Label], start : &[Label], next : &HashMap<Label, Vec<Label>>, generators : &[LabelPermutation], msg : &str, eh : &mut EventHandler) -> Result<Vec<Vec<Vec<Label>>>, &'static str> {
+    let labels_p_to_positions : HashMap<_,_> = labels_p.iter().copied().enumerate().map(|(i,l)|(l,i)).collect();
+    let d = cp.finite_degree();
 
-    let is_right = |l1:Label,l2:Label|{
-        all_successors[&l2].contains(&l1)
+    // the configurations of cp are computed once, with labels replaced by their positions
+    let lines_p = cp.all_choices(false).into_iter()
+        .flat_map(|line|line.iter_labels().permutations(d))
+        .map(|line|line.into_iter().map(|l|labels_p_to_positions[&l]).collect_vec())
+        .unique()
+        .collect_vec();
+
+    let mut allowed_f : HashMap<Vec<Label>, bool> = HashMap::new();
+    let mut find_bad_linep = |configuration : &Vec<Vec<Label>>|{
+        lines_p.iter().find(|line_p|{
+            let mut line_f = line_p.iter().enumerate().map(|(i,&j)|configuration[i][j]).collect_vec();
+            line_f.sort();
+            let allowed = allowed_f.entry(line_f).or_insert_with_key(|line_f|{
+                let parts = line_f.iter().map(|&l|Part{ gtype : GroupType::Many(1), group : Group::from(vec![l]) }).collect();
+                cf.includes(&Line{ parts })
+            });
+            !*allowed
+        }).cloned()
     };
-    
-    let sources = labels_d.iter().filter(|l|direct_pred[l].is_empty()).cloned().collect_vec();
-    let initial_configurations = Line{ parts : vec![Part{
-            group : Group::from(sources),
-            gtype: GroupType::Many(d as u8)
-        }]}
-        .all_choices(false)
-        .into_iter()
-        .map(|line|line.parts.into_iter().map(|part|part.group.first()).collect_vec())
-        .map(|mut line|{ line.sort(); line })
-        .unique();
 
+    let generators = position_permutations(labels_p, generators);
 
+    // the initial configurations are created only if they fit in the budget
+    if initial_configurations(start.len(), labels_p.len(), d).map_or(true, |x|x > MAX_DUAL_CONFIGURATIONS) {
+        return Err("The dual problem has too many configurations");
+    }
+    let start_labels = (0..labels_p.len()).map(|_|start.iter().copied()).multi_cartesian_product().collect_vec();
+    let mut tofix_configurations : HashSet<_> = start_labels.into_iter().combinations_with_replacement(d)
+        .map(|mut configuration|{ configuration.sort(); canonical_configuration(configuration, &generators) })
+        .collect();
+    let mut seen = tofix_configurations.clone();
     let mut good_configurations = HashSet::new();
-    let mut tofix_configurations : HashSet<_> = HashSet::from_iter(initial_configurations);
-    let mut seen = HashSet::new();
 
     while !tofix_configurations.is_empty() {
-        println!("size: {}, good: {}",tofix_configurations.len(),good_configurations.len());
+        eh.notify(msg, good_configurations.len(), good_configurations.len() + tofix_configurations.len());
         let mut new_tofix_configurations = HashSet::new();
-        for mut configuration in tofix_configurations {
-            if let Some(bad) = find_bad_linep_for_lined(&configuration) {
+        for configuration in tofix_configurations {
+            if let Some(bad) = find_bad_linep(&configuration) {
                 for i in 0..d {
-                    let mut set = vec![];
-                    for &succ in &all_successors[&configuration[i]] {
-                        let old_label_f = labels[configuration[i] as usize][labels_p_to_positions[&bad[i]]];
-                        let new_label_f = labels[succ as usize][labels_p_to_positions[&bad[i]]];
-                        if old_label_f != new_label_f {
-                        //let mut new_configuration = configuration.clone();
-                        //new_configuration[i] = succ;
-                        //if !is_linep_bad_for_lined(&new_configuration,&bad) {
-                            set.push(succ);
-                        }
-                    }
-                    let left = left_labels(&set, is_right);
-                    for &succ in &left {
+                    let j = bad[i];
+                    for &x in &next[&configuration[i][j]] {
                         let mut new_configuration = configuration.clone();
-                        new_configuration[i] = succ;
+                        new_configuration[i][j] = x;
                         new_configuration.sort();
-                        if !seen.contains(&new_configuration) {
-                            seen.insert(new_configuration.clone());
+                        let new_configuration = canonical_configuration(new_configuration, &generators);
+                        if seen.insert(new_configuration.clone()) {
+                            if seen.len() > MAX_DUAL_CONFIGURATIONS {
+                                return Err("The dual problem has too many configurations");
+                            }
                             new_tofix_configurations.insert(new_configuration);
                         }
                     }
                 }
             } else {
-                configuration.sort();
                 good_configurations.insert(configuration);
             }
         }
         tofix_configurations = new_tofix_configurations;
     }
-    println!("done");
-
-    //let lines = (0..d).map(|_|0..labels.len() as Label).multi_cartesian_product()
-    //    .filter(|line_d|line_d.is_sorted())
-    //    .filter(|line_d|find_bad_linep_for_lined(line_d).is_none())
-    let lines = good_configurations.into_iter()
-        .map(|line|{to_configuration(&line)}).collect_vec();
-    let c = Constraint { lines, is_maximized: false, degree: crate::line::Degree::Finite(d)  };
-    let c = c.edited(|g|{
-        let g = g.iter().fold(HashSet::new(),|mut a,l|{
-            a.extend(all_successors[l].iter().cloned());
-            a
-        });
-        Group::from_set(&g)
-    });
-    Ok(c)
+
+    if !generators.is_empty() {
+        good_configurations = good_configurations.iter().flat_map(|configuration|configuration_orbit(configuration, &generators)).collect();
+    }
+    Ok(good_configurations.into_iter().collect())
+}
+
+/// The constraint of one side of the dual, where each dual label of a good configuration is replaced by the group of dual labels that are `within` it.
+fn dual_constraint(configurations : &[Vec<Vec<Label>>], d : usize, labels : &[Vec<Label>], within : impl Fn(&[Label], &[Label]) -> bool) -> Constraint {
+    let lines = configurations.iter().map(|configuration|{
+        let parts = configuration.iter().map(|v|{
+            let group = (0..labels.len()).filter(|&l|within(&labels[l], v)).map(|l|l as Label).collect();
+            Part{ gtype : GroupType::Many(1), group : Group::from(group) }
+        }).collect();
+        Line{ parts }
+    }).filter(|line|line.parts.iter().all(|part|!part.group.is_empty())).collect();
+    Constraint { lines, is_maximized: false, degree: Degree::Finite(d) }
+}
+
+/// The vectors obtained from `generators` by repeatedly taking pointwise joins and meets in the diagram of f, that is, the smallest sublattice of the labels of the dual of the dual that contains them.
+/// When the fixed point procedure is given a diagram containing the generators, it only uses labels of this sublattice.
+fn generated_sublattice(generators : &[Vec<Label>], labels_f : &[Label], diagram_f : &Vec<(Label,Label)>) -> Result<Vec<Vec<Label>>, &'static str> {
+    let diagram_f = diagram_to_indirect(labels_f, diagram_f);
+    let succ = diagram_indirect_to_reachability_adj(labels_f, &diagram_f);
+    let le = |a : Label, b : Label| succ[&a].contains(&b);
+
+    let mut join = HashMap::new();
+    let mut meet = HashMap::new();
+    for &a in labels_f {
+        for &b in labels_f {
+            let up = labels_f.iter().copied().filter(|&x|le(a, x) && le(b, x)).collect_vec();
+            let down = labels_f.iter().copied().filter(|&x|le(x, a) && le(x, b)).collect_vec();
+            join.insert((a, b), up.iter().copied().filter(|&x|up.iter().all(|&y|le(x, y))).exactly_one().ok());
+            meet.insert((a, b), down.iter().copied().filter(|&x|down.iter().all(|&y|le(y, x))).exactly_one().ok());
+        }
+    }
+
+    let mut labels = generators.iter().cloned().unique().collect_vec();
+    let mut seen : HashSet<_> = labels.iter().cloned().collect();
+    let mut i = 0;
+    while i < labels.len() {
+        for j in 0..i {
+            for table in [&join, &meet] {
+                let v = labels[i].iter().zip(labels[j].iter())
+                    .map(|(&a,&b)|table[&(a, b)])
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("The diagram of f is not a lattice")?;
+                if seen.insert(v.clone()) {
+                    labels.push(v);
+                }
+            }
+        }
+        if labels.len() > MAX_DUAL_LABELS {
+            return Err("The dual of the dual has too many labels");
+        }
+        i += 1;
+    }
+    Ok(labels)
 }
 
 fn labels_for_dual(f_mapping_label_text : &Vec<(Label,String)>, dual_labels : &Vec<Label>, dual_labels_v : &Vec<Vec<Label>>) -> Vec<(Label, String)> {
@@ -175,62 +255,104 @@ fn labels_for_dual(f_mapping_label_text : &Vec<(Label,String)>, dual_labels : &V
 }
 
 
+/// The custom diagram used by the double dual: each label of `p` is mapped to the corresponding vector of `generators`, and the diagram only contains the sublattice generated by them.
+fn doubledual_text(p : &Problem, generators : &[Vec<Label>], coordinates : usize, mapping_label_text_f : &Vec<(Label,String)>, diagram_f : &Vec<(Label,Label)>) -> Result<String, &'static str> {
+    let labels_f = mapping_label_text_f.iter().map(|(l,_)|*l).collect_vec();
+    let dualdual_labels_v = generated_sublattice(generators, &labels_f, diagram_f)?;
+    let dualdual_labels = (0..dualdual_labels_v.len() as Label).collect_vec();
+    let coordinates = (0..coordinates as Label).collect_vec();
+    let dualdual_diagram = dual_diagram(&coordinates, &dualdual_labels_v, &labels_f, diagram_f);
+
+    let dualdual_text : HashMap<_,_> = labels_for_dual(mapping_label_text_f,&dualdual_labels,&dualdual_labels_v).into_iter().collect();
+    let p_text : HashMap<_,_> = p.mapping_label_text.iter().cloned().collect();
+    let mut s = String::new();
+    for (l, g) in p.labels().into_iter().zip(generators) {
+        let dd = dualdual_labels_v.iter().position(|v|v == g).unwrap() as Label;
+        s += &format!("{} = {}\n",p_text[&l],dualdual_text[&dd]);
+    }
+    for (a,b) in dualdual_diagram {
+        s += &format!("{} -> {}\n",dualdual_text[&a],dualdual_text[&b]);
+    }
+    Ok(s)
+}
+
+
 impl Problem {
 
 
+    /// Computes the dual of this problem with respect to `f`, together with the vector of labels of f of each dual label and the diagram of the dual labels.
+    /// Dual labels are not enumerated: the good configurations of the two sides are searched starting from the dual labels made of maximal (minimal) labels of f,
+    /// and only the dual labels that are between a dual label of an active configuration and one of a passive configuration are kept, since the other ones would appear on only one side.
+    /// The number of configurations the search starts from is reported, and checked against the budget, before the search starts, and the estimated number of dual labels is reported before they are created.
+    /// If the environment variable RE_SYMMETRY is set to 1, the search only visits one configuration for each orbit under the automorphisms of the constraints of the problem.
     pub fn dual_problem(&self, f : &Problem, eh : &mut EventHandler) -> Result<(Problem,Vec<Vec<Label>>,Vec<(Label,Label)>), &'static str> {
-        println!("add active pred");
+        self.require_regular()?;
+        f.require_regular()?;
         let mut f = f.clone();
         f.add_active_predecessors();
         f.active.is_maximized = true;
 
         let labels_f = f.labels();
         let labels_p = self.labels();
+        let n = labels_p.len();
+
+        let succ_f = f.diagram_indirect_to_reachability_adj();
+        let pred_f = f.diagram_indirect_to_inverse_reachability_adj();
+        let le = |a : Label, b : Label| a == b || succ_f[&a].contains(&b);
+
+        let (start_active, next_active) = search_order(&labels_f, &pred_f);
+        let (start_passive, next_passive) = search_order(&labels_f, &succ_f);
+        let initial = initial_configurations(start_active.len(), n, self.active.finite_degree())
+            .zip(initial_configurations(start_passive.len(), n, self.passive.finite_degree()))
+            .and_then(|(a,b)|a.checked_add(b))
+            .unwrap_or(usize::MAX);
+        eh.notify("dual configurations (estimate)", initial, MAX_DUAL_CONFIGURATIONS);
+        if initial > MAX_DUAL_CONFIGURATIONS {
+            return Err("The dual problem has too many configurations");
+        }
 
-        println!("dual labels");
-        let dual_labels_v = k_partitions(&labels_f, labels_p.len()).collect_vec();
-
-        println!("dual diagram");
-        let d_diag = dual_diagram(&self.labels(), &dual_labels_v, &f.labels(), &f.diagram_indirect.as_ref().unwrap());
-        let d_labels = (0..dual_labels_v.len()).map(|x|x as Label).collect_vec();
-        let all_succ = diagram_indirect_to_reachability_adj(&d_labels, &d_diag);
-        let inv = d_diag.iter().map(|&(a,b)|(b,a)).collect_vec();
-        let all_pred = diagram_indirect_to_reachability_adj(&d_labels, &inv);
-
-        let direct = compute_direct_diagram(&d_labels, &d_diag).1;
-        let direct_succ = diagram_direct_to_succ_adj(&direct, &d_labels);
-        let direct_pred = diagram_direct_to_pred_adj(&direct, &d_labels);
-
-        println!("dual constraints");
-        let dual_active = dual_constraint(&self.active, &f.active, &dual_labels_v, &labels_p, &all_succ, &all_pred, &direct_succ)?;
-        let dual_passive = dual_constraint(&self.passive, &f.passive, &dual_labels_v, &labels_p, &all_pred, &all_succ, &direct_pred)?;
-
-        println!("computed constraints, computing label names");
-
-        let active_labels = dual_active.labels_appearing();
-        let passive_labels = dual_passive.labels_appearing();
-        let dual_labels = active_labels.union(&passive_labels).copied().collect_vec();
-
-        /*let mapping_label_text : HashMap<_,_> = self.mapping_label_text.iter().cloned().collect();
-
-        let mapping_label_text = dual_labels.into_iter().map(|&l|{
-            let v = &dual_labels_v[l as usize];
-            let mapped_to_x = |x : Label| {
-                labels_p.iter().zip(v.iter())
-                    .filter(|(_,&j)|j as Label == x)
-                    .map(|(&l,_)|l)
-                    .collect_vec()
-            };
-            let v_to_string = |v : &Vec<Label>|{
-                v.iter().map(|l|mapping_label_text[l].replace("(", "[").replace(")","]")).join("")
-            };
-            let s = labels_f.iter().map(|&x|v_to_string(&mapped_to_x(x))).join("_");
-            (l,format!("({})",s))
-        }).collect_vec();*/
+        let (generators_active, generators_passive) = if symmetry_from_env() {
+            (self.active.automorphism_generators(), self.passive.automorphism_generators())
+        } else {
+            (vec![], vec![])
+        };
+        let active = dual_configurations(&self.active, &f.active, &labels_p, &start_active, &next_active, &generators_active, "dual active configurations", eh)?;
+        let passive = dual_configurations(&self.passive, &f.passive, &labels_p, &start_passive, &next_passive, &generators_passive, "dual passive configurations", eh)?;
+
+        // a dual label is useful only if it is below a dual label of an active configuration and above one of a passive configuration
+        let tops = active.iter().flatten().unique().collect_vec();
+        let bottoms = passive.iter().flatten().unique().collect_vec();
+        let intervals = tops.iter().cartesian_product(bottoms.iter())
+            .filter(|(a,b)|(0..n).all(|k|le(b[k], a[k])))
+            .map(|(a,b)|(0..n).map(|k|labels_f.iter().copied().filter(|&x|le(b[k], x) && le(x, a[k])).collect_vec()).collect_vec())
+            .collect_vec();
+        let estimate = intervals.iter()
+            .map(|interval|interval.iter().fold(1usize, |r,x|r.saturating_mul(x.len())))
+            .fold(0usize, |r,x|r.saturating_add(x));
+        eh.notify("dual labels (estimate)", estimate, MAX_DUAL_LABELS);
+
+        let mut dual_labels_v = vec![];
+        let mut seen = HashSet::new();
+        for interval in &intervals {
+            for v in interval.iter().map(|x|x.iter().copied()).multi_cartesian_product() {
+                if seen.insert(v.clone()) {
+                    dual_labels_v.push(v);
+                    if dual_labels_v.len() > MAX_DUAL_LABELS {
+                        return Err("The dual problem has too many labels");
+                    }
+                }
+            }
+        }
 
-        let mapping_label_text = labels_for_dual(&f.mapping_label_text,&dual_labels, &dual_labels_v);
+        eh.notify("dual diagram", 0, 1);
+        let d_diag = dual_diagram(&labels_p, &dual_labels_v, &labels_f, f.diagram_indirect.as_ref().unwrap());
 
-        println!("done");
+        let below = |v : &[Label], a : &[Label]| (0..n).all(|k|le(v[k], a[k]));
+        let dual_active = dual_constraint(&active, self.active.finite_degree(), &dual_labels_v, below);
+        let dual_passive = dual_constraint(&passive, self.passive.finite_degree(), &dual_labels_v, |v, b|below(b, v));
+
+        let dual_labels = dual_active.labels_appearing().union(&dual_passive.labels_appearing()).copied().sorted().collect_vec();
+        let mapping_label_text = labels_for_dual(&f.mapping_label_text,&dual_labels, &dual_labels_v);
 
         Ok((Problem {
             active : dual_active,
@@ -254,7 +376,11 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees : None
         },dual_labels_v,d_diag))
     }
 
@@ -273,56 +399,18 @@ impl Problem {
             v.iter().copied().sorted_by_key(|x|orig_dual_diagram[x].len()).last().unwrap()
         }).collect_vec();
 
-        let labels_p = self.labels();
-
-        let dual_labels = equiv.iter().map(|(l,_)|*l).collect_vec();
-        let labels_f = mapping_label_text_fp.iter().map(|(l,_)|*l).collect_vec();
-
-        println!("computing dual dual labels");
-
-        let dualdual_labels_v = k_partitions(&labels_f, dual_labels.len()).collect_vec();
-        let dualdual_labels = (0..dualdual_labels_v.len() as Label).collect_vec();
-
-        println!("computing dual dual diagram");
-
-        let dualdual_diagram = dual_diagram(&dual_labels, &dualdual_labels_v, &labels_f, &diagram_fp);
-
-        println!("computing mapping");
-
-
-        let mut mapping = vec![];
-
-        for &l in &labels_p {
-            for &dd in &dualdual_labels {
-                if dualdual_labels_v[dd as usize].iter().enumerate().all(|(i,&r)|{
-                    dual_labels_v[equiv_choice[i] as usize][l as usize] == r
-                }) {
-                    mapping.push((l,dd));
-                    //break;
-                }
-            }
-        }
-
-        println!("computing text dualdual");
-
+        // the dual of the dual has a coordinate for each class of equivalent labels of the dual
+        let generators = (0..self.labels().len()).map(|k|{
+            equiv_choice.iter().map(|&c|dual_labels_v[c as usize][k]).collect_vec()
+        }).collect_vec();
+        let s = doubledual_text(self, &generators, equiv_choice.len(), &mapping_label_text_fp, &diagram_fp)?;
 
-        let dualdual_text : HashMap<_,_> = labels_for_dual(&mapping_label_text_fp,&dualdual_labels,&dualdual_labels_v).into_iter().collect();
-        let p_text : HashMap<_,_> = self.mapping_label_text.iter().cloned().collect();
-        let mut s = String::new();
-        for (a,b) in mapping {
-            s += &format!("{} = {}\n",p_text[&a],dualdual_text[&b]);
-        }
-        //println!("{}",s);
-        for (a,b) in dualdual_diagram {
-            s += &format!("{} -> {}\n",dualdual_text[&a],dualdual_text[&b]);
-        }
-        
-        
         let (p,_,_) = self.fixpoint_generic(None, FixpointType::Custom(s),false, eh)?;
         Ok(p)
     }
 
     pub fn doubledual_diagram(&self, f_active : &str, f_passive : &str, f_diagram : &str, input_active : &str, input_passive : &str, eh : &mut EventHandler) -> Result<String, &'static str> {
+        self.require_regular()?;
         let (mapping_label_text_fp,diagram_fp) = if f_diagram.is_empty() {
             let mut fp = Problem::from_string_active_passive(f_active, f_passive)?.0;
             fp.passive.maximize(eh);
@@ -331,55 +419,18 @@ impl Problem {
         } else {
             parse_diagram(f_diagram)
         };
-        println!("computed diagram fp");
         if input_active.is_empty() {
             let labels_p = self.labels();
             let labels_f = mapping_label_text_fp.iter().map(|(l,_)|*l).collect_vec();
-    
-            println!("computing dual labels");
-
-            let dual_labels_v = k_partitions(&labels_f, labels_p.len()).collect_vec();
-            let dual_labels = (0..dual_labels_v.len() as Label).collect_vec();
-
-            println!("computing dual dual labels");
-
-            let dualdual_labels_v = k_partitions(&labels_f, dual_labels.len()).collect_vec();
-            let dualdual_labels = (0..dualdual_labels_v.len() as Label).collect_vec();
-
-            println!("computing dual dual diagram");
-
-            let dualdual_diagram = dual_diagram(&dual_labels, &dualdual_labels_v, &labels_f, &diagram_fp);
-
-            println!("computing mapping");
-
-
-            let mut mapping = vec![];
 
-            for &l in &labels_p {
-                for &dd in &dualdual_labels {
-                    if dualdual_labels_v[dd as usize].iter().enumerate().all(|(i,&r)|{
-                        dual_labels_v[i][l as usize] == r
-                    }) {
-                        mapping.push((l,dd));
-                        break;
-                    }
-                }
+            if labels_f.len().checked_pow(labels_p.len() as u32).map_or(true, |x|x > MAX_DUAL_LABELS) {
+                return Err("The dual problem has too many labels");
             }
-
-            println!("computing text dualdual");
-
-
-            let dualdual_text : HashMap<_,_> = labels_for_dual(&mapping_label_text_fp,&dualdual_labels,&dualdual_labels_v).into_iter().collect();
-            let p_text : HashMap<_,_> = self.mapping_label_text.iter().cloned().collect();
-            let mut s = String::new();
-            for (a,b) in mapping {
-                s += &format!("{} = {}\n",p_text[&a],dualdual_text[&b]);
-            }
-            for (a,b) in dualdual_diagram {
-                s += &format!("{} -> {}\n",dualdual_text[&a],dualdual_text[&b]);
-            }
-            println!("{}",s);
-            Ok(s)
+            let dual_labels_v = k_partitions(&labels_f, labels_p.len()).collect_vec();
+            let generators = (0..labels_p.len()).map(|k|{
+                dual_labels_v.iter().map(|v|v[k]).collect_vec()
+            }).collect_vec();
+            doubledual_text(self, &generators, dual_labels_v.len(), &mapping_label_text_fp, &diagram_fp)
         } else {
             let input = Problem::from_string_active_passive(input_active, input_passive)?.0;
             let labels_p = input.labels();
@@ -400,7 +451,132 @@ impl Problem {
 
 #[cfg(test)]
 mod tests {
-    use crate::{algorithms::{dual::dual_diagram, event::EventHandler}, problem::Problem, serial::fix_problem};
+    use std::collections::{HashMap, HashSet};
+
+    use itertools::Itertools;
+
+    use crate::{algorithms::{dual::{dual_configurations, dual_constraint, dual_diagram, k_partitions, search_order}, event::EventHandler}, constraint::Constraint, group::{Group, GroupType, Label}, line::Line, part::Part, problem::Problem, serial::fix_problem};
+
+    /// The good configurations of one side of the dual, obtained by enumerating all the dual labels, as the dual was computed before the search.
+    fn enumerated_configurations(cp : &Constraint, cf : &Constraint, labels_p : &Vec<Label>, labels_f : &Vec<Label>) -> HashSet<Vec<Vec<Label>>> {
+        let d = cp.finite_degree();
+        let position : HashMap<_,_> = labels_p.iter().enumerate().map(|(i,&l)|(l,i)).collect();
+        let lines_p = cp.all_choices(false).iter().flat_map(|line|line.iter_labels().permutations(d)).collect_vec();
+        k_partitions(labels_f, labels_p.len()).combinations_with_replacement(d).filter(|configuration|{
+            lines_p.iter().all(|line_p|{
+                let parts = line_p.iter().zip(configuration.iter()).map(|(l,v)|Part{
+                    gtype : GroupType::Many(1),
+                    group : Group::from(vec![v[position[l]]]),
+                }).collect();
+                cf.includes(&Line{ parts })
+            })
+        }).map(|mut configuration|{ configuration.sort(); configuration }).collect()
+    }
+
+    fn sorted_choices(c : &Constraint, labels : &[Vec<Label>]) -> HashSet<Vec<Vec<Label>>> {
+        c.all_choices(false).iter()
+            .map(|line|line.iter_labels().map(|l|labels[l as usize].clone()).sorted().collect_vec())
+            .collect()
+    }
+
+    #[test]
+    fn dual_matches_enumeration() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C\n").unwrap();
+        let mut f = Problem::from_string("A B B\n\nB AB").unwrap();
+        p.passive.maximize(eh);
+        p.compute_diagram(eh);
+        f.passive.maximize(eh);
+        f.compute_diagram(eh);
+
+        let (dual, dual_labels_v, _) = p.dual_problem(&f, eh).unwrap();
+        let kept : HashSet<_> = dual.labels().iter().map(|&l|dual_labels_v[l as usize].clone()).collect();
+
+        let mut f = f.clone();
+        f.add_active_predecessors();
+        f.active.maximize(eh);
+        let labels_p = p.labels();
+        let labels_f = f.labels();
+        let enumerated_active = enumerated_configurations(&p.active, &f.active, &labels_p, &labels_f);
+        let enumerated_passive = enumerated_configurations(&p.passive, &f.passive, &labels_p, &labels_f);
+
+        // the labels that are dropped never appear on both sides
+        let active_labels : HashSet<_> = enumerated_active.iter().flatten().cloned().collect();
+        let passive_labels : HashSet<_> = enumerated_passive.iter().flatten().cloned().collect();
+        assert!(active_labels.intersection(&passive_labels).all(|v|kept.contains(v)));
+
+        // on the labels that are kept, the configurations are the same
+        for (cd, enumerated) in [(&dual.active, enumerated_active), (&dual.passive, enumerated_passive)] {
+            let expected : HashSet<_> = enumerated.into_iter().filter(|configuration|configuration.iter().all(|v|kept.contains(v))).collect();
+            assert_eq!(sorted_choices(cd, &dual_labels_v), expected);
+        }
+    }
+
+    #[test]
+    fn dual_configurations_symmetric() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C\n").unwrap();
+        let mut f = Problem::from_string("A B B\n\nB AB").unwrap();
+        p.passive.maximize(eh);
+        p.compute_diagram(eh);
+        f.passive.maximize(eh);
+        f.compute_diagram(eh);
+        f.add_active_predecessors();
+        f.active.is_maximized = true;
+
+        let generators = p.active.automorphism_generators();
+        assert!(!generators.is_empty());
+
+        let labels_p = p.labels();
+        let labels_f = f.labels();
+        let (start, next) = search_order(&labels_f, &f.diagram_indirect_to_inverse_reachability_adj());
+        let plain = dual_configurations(&p.active, &f.active, &labels_p, &start, &next, &[], "", eh).unwrap();
+        let symmetric = dual_configurations(&p.active, &f.active, &labels_p, &start, &next, &generators, "", eh).unwrap();
+
+        // the configurations may differ, but they allow the same configurations of dual labels
+        let succ_f = f.diagram_indirect_to_reachability_adj();
+        let below = |v : &[Label], a : &[Label]| v.iter().zip(a.iter()).all(|(&x,&y)|x == y || succ_f[&x].contains(&y));
+        let all_labels = k_partitions(&labels_f, labels_p.len()).collect_vec();
+        let plain = dual_constraint(&plain, 3, &all_labels, &below);
+        let symmetric = dual_constraint(&symmetric, 3, &all_labels, &below);
+        assert_eq!(sorted_choices(&plain, &all_labels), sorted_choices(&symmetric, &all_labels));
+    }
+
+    #[test]
+    fn dual_configurations_are_good() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C\n").unwrap();
+        let mut f = Problem::from_string("A B B\n\nB AB").unwrap();
+        p.passive.maximize(eh);
+        p.compute_diagram(eh);
+        f.passive.maximize(eh);
+        f.compute_diagram(eh);
+
+        let (dual, dual_labels_v, _) = p.dual_problem(&f, eh).unwrap();
+        assert!(!dual.active.lines.is_empty() && !dual.passive.lines.is_empty());
+        assert!(dual.labels().iter().all(|&l|(l as usize) < dual_labels_v.len()));
+
+        let mut f = f.clone();
+        f.add_active_predecessors();
+        f.active.maximize(eh);
+        let labels_p = p.labels();
+        let position : HashMap<_,_> = labels_p.iter().enumerate().map(|(i,&l)|(l,i)).collect();
+        // each configuration of the dual, combined with each configuration of p, gives a configuration of f
+        let good = |cd : &Constraint, cp : &Constraint, cf : &Constraint|{
+            cd.all_choices(false).iter().all(|line_d|{
+                let line_d = line_d.iter_labels().collect_vec();
+                cp.all_choices(false).iter().flat_map(|line_p|line_p.iter_labels().permutations(line_d.len())).all(|line_p|{
+                    let parts = line_p.iter().zip(line_d.iter()).map(|(lp,&ld)|Part{
+                        gtype : GroupType::Many(1),
+                        group : Group::from(vec![dual_labels_v[ld as usize][position[lp]]]),
+                    }).collect();
+                    cf.includes(&Line{ parts })
+                })
+            })
+        };
+        assert!(good(&dual.active, &p.active, &f.active));
+        assert!(good(&dual.passive, &p.passive, &f.passive));
+    }
 
 
     #[test]
@@ -482,7 +658,7 @@ let mut f = Problem::from_string("A B B\n\nB AB").unwrap();
 
         let dual = dual.speedup(eh);
         let dual = dual.speedup(eh);
-        f.compute_triviality_with_input(dual,true);
+        f.compute_triviality_with_input(dual,true).unwrap();
         if f.triviality_with_input.is_some() {
             println!(":( solves fp");
             return;
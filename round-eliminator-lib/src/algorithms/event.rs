@@ -29,6 +29,11 @@ impl<'a> EventHandler<'a> {
         }
     }
 
+    /// Returns true if notifications are discarded.
+    pub fn is_null(&self) -> bool {
+        self.tx.is_none()
+    }
+
     pub fn notify<S: AsRef<str>>(&mut self, s: S, x: usize, t: usize) {
         let s = s.as_ref();
         if let Some(tx) = self.tx.as_mut() {
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::{group::{GroupType, Label}, part::Part, problem::Problem};
+
+/// Maximum depth of nested includes, to stop include cycles.
+const MAX_INCLUDE_DEPTH : usize = 16;
+
+/// A problem parsed from the extended text format, together with the descriptions given to its labels.
+/// The descriptions are not stored in `Problem`, hence the operations on the problem, the web interface and the command line tool do not see them,
+/// they are only kept here and in the interchange format.
+#[derive(Clone, Debug)]
+pub struct AnnotatedProblem {
+    pub problem : Problem,
+    pub descriptions : Vec<(Label, String)>,
+}
+
+/// Position of a line in the input, `origin` is the name of the file, or `<input>` for the main text.
+#[derive(Clone, Debug)]
+struct Position {
+    origin : String,
+    line : usize,
+}
+
+impl Position {
+    fn error(&self, column : usize, msg : &str) -> String {
+        format!("{}:{}:{}: {}", self.origin, self.line, column, msg)
+    }
+}
+
+enum Item {
+    Blank,
+    /// The parts of a line of a constraint, after expanding macros, each with the column where it starts in the original line.
+    Line(Position, Vec<(usize, String)>),
+}
+
+struct Preprocessor<'a> {
+    resolve : &'a mut dyn FnMut(&str, &str) -> Result<(String, String), String>,
+    macros : HashMap<String, String>,
+    /// Label text and description.
+    descriptions : Vec<(String, String)>,
+    items : Vec<Item>,
+}
+
+fn is_macro_char(c : char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The part of the line before the comment, a `#` preceded by `\` does not start a comment.
+fn strip_comment(line : &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '#' if !escaped => { return &line[..i]; }
+            '\\' => { escaped = !escaped; }
+            _ => { escaped = false; }
+        }
+    }
+    line
+}
+
+/// Escapes the characters of a description that would otherwise end it: `#`, `\` and new lines.
+fn escape_description(description : &str) -> String {
+    description.replace('\\', "\\\\").replace('#', "\\#").replace('\n', "\\n")
+}
+
+fn unescape_description(description : &str) -> String {
+    let mut result = String::new();
+    let mut chars = description.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+impl<'a> Preprocessor<'a> {
+    fn expand(&self, text : &str, pos : &Position, column : usize) -> Result<String, String> {
+        let mut result = String::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != '$' {
+                result.push(c);
+                continue;
+            }
+            let mut name = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !is_macro_char(c) {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            let body = self.macros.get(&name).ok_or_else(||pos.error(column + text[..i].chars().count(), &format!("undefined macro ${}", name)))?;
+            result.push_str(body);
+        }
+        Ok(result)
+    }
+
+    fn process(&mut self, text : &str, origin : &str, depth : usize) -> Result<(), String> {
+        for (i, original) in text.lines().enumerate() {
+            let pos = Position { origin : origin.to_owned(), line : i + 1 };
+            if original.trim().is_empty() {
+                // only the main text separates the constraints, so that included files can contain empty lines
+                if depth == 0 {
+                    self.items.push(Item::Blank);
+                }
+                continue;
+            }
+            let line = strip_comment(original);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let indent = line.chars().take_while(|c|c.is_whitespace()).count();
+            let column = indent + 1;
+            let trimmed = line.trim();
+            let (keyword, rest) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+            // column where `rest` starts
+            let rest_column = column + trimmed.chars().count() - rest.chars().count();
+            match keyword {
+                "include" => {
+                    let path = rest.trim().trim_matches('"');
+                    if path.is_empty() {
+                        return Err(pos.error(column, "missing file name"));
+                    }
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(pos.error(column, "includes are nested too deeply"));
+                    }
+                    let (included_origin, included) = (self.resolve)(origin, path).map_err(|e|pos.error(rest_column, &e))?;
+                    self.process(&included, &included_origin, depth + 1)?;
+                }
+                "let" => {
+                    let (name, body) = rest.split_once('=').ok_or_else(||pos.error(rest_column, "expected `let NAME = labels`"))?;
+                    let name = name.trim();
+                    if name.is_empty() || !name.chars().all(is_macro_char) {
+                        return Err(pos.error(rest_column, "the name of a macro can only contain letters, digits and `_`"));
+                    }
+                    let body_column = rest_column + rest.chars().count() - body.chars().count();
+                    let body = self.expand(body.trim(), &pos, body_column)?;
+                    self.macros.insert(name.to_owned(), body);
+                }
+                "label" => {
+                    let (label, description) = rest.split_once(':').ok_or_else(||pos.error(rest_column, "expected `label NAME : description`"))?;
+                    let label = label.trim();
+                    let part = Part::parse(label, &mut HashMap::new()).map_err(|e|pos.error(rest_column, e))?;
+                    if part.group.len() != 1 || part.gtype != GroupType::ONE {
+                        return Err(pos.error(rest_column, "expected a single label"));
+                    }
+                    self.descriptions.push((label.to_owned(), unescape_description(description.trim())));
+                }
+                _ => {
+                    let mut parts = vec![];
+                    let mut column = 1;
+                    for token in line.split_inclusive(char::is_whitespace) {
+                        let len = token.chars().count();
+                        let token = token.trim();
+                        if !token.is_empty() {
+                            let expanded = self.expand(token, &pos, column)?;
+                            for part in expanded.split_whitespace() {
+                                parts.push((column, part.to_owned()));
+                            }
+                        }
+                        column += len;
+                    }
+                    self.items.push(Item::Line(pos, parts));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Problem {
+    /// Parses the extended text format, see `from_extended_string_with_includes`. Includes are not allowed.
+    pub fn from_extended_string(text : &str) -> Result<AnnotatedProblem, String> {
+        Self::from_extended_string_with_includes(text, |_, _|Err("includes are not supported here".into()))
+    }
+
+    /// Parses a problem written in the extended text format, that is the usual format with the following additions:
+    /// - `#` starts a comment, that extends until the end of the line;
+    /// - `label NAME : description` gives a description to a label, where `\#`, `\\` and `\n` stand for `#`, `\` and a new line;
+    /// - `let NAME = labels` defines a macro, and `$NAME` is then replaced by `labels`, for example `let C = (0a)(1a)` allows to write `$C^2`;
+    /// - `include "file"` is replaced by the content of the file.
+    ///
+    /// Lines that only contain comments or directives do not separate the active constraint from the passive one, only empty lines do.
+    /// The function `resolve` is called with the name of the including file and the included path, and it returns the name and the content of the included file.
+    /// Errors are of the form `file:line:column: message`.
+    pub fn from_extended_string_with_includes<F>(text : &str, mut resolve : F) -> Result<AnnotatedProblem, String> where F : FnMut(&str, &str) -> Result<(String, String), String> {
+        let mut pre = Preprocessor { resolve : &mut resolve, macros : HashMap::new(), descriptions : vec![], items : vec![] };
+        pre.process(text, "<input>", 0)?;
+
+        let mut sections : Vec<Vec<(Position, Vec<(usize, String)>)>> = vec![vec![]];
+        for item in pre.items {
+            match item {
+                Item::Blank => {
+                    if !sections.last().unwrap().is_empty() {
+                        sections.push(vec![]);
+                    }
+                }
+                Item::Line(pos, parts) => {
+                    if sections.len() > 2 {
+                        return Err(pos.error(1, "unexpected line after the passive constraint"));
+                    }
+                    sections.last_mut().unwrap().push((pos, parts));
+                }
+            }
+        }
+        sections.retain(|s|!s.is_empty());
+        if sections.len() < 2 {
+            return Err("the problem must contain an active and a passive constraint, separated by an empty line".into());
+        }
+
+        // the parts are checked one by one to report the position of errors
+        let mut scratch = HashMap::new();
+        for (pos, parts) in sections.iter().flatten() {
+            for (column, part) in parts {
+                Part::parse(part, &mut scratch).map_err(|e|pos.error(*column, e))?;
+            }
+        }
+
+        let text = |section : &Vec<(Position, Vec<(usize, String)>)>| section.iter().map(|(_, parts)|parts.iter().map(|(_, p)|p).join(" ")).join("\n");
+        let (problem, _) = Problem::from_string_active_passive(text(&sections[0]), text(&sections[1]))?;
+
+        let label_of : HashMap<&String, Label> = problem.mapping_label_text.iter().map(|(l, s)|(s, *l)).collect();
+        let mut descriptions = vec![];
+        for (label, description) in pre.descriptions {
+            // labels that do not appear in the problem, for example in files included by many problems, are ignored
+            if let Some(&l) = label_of.get(&label) {
+                descriptions.retain(|(x, _)|*x != l);
+                descriptions.push((l, description));
+            }
+        }
+        descriptions.sort();
+
+        Ok(AnnotatedProblem { problem, descriptions })
+    }
+
+    /// Reads a problem in the extended text format from a file, included files are relative to the file that includes them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_extended_file(path : &str) -> Result<AnnotatedProblem, String> {
+        let text = std::fs::read_to_string(path).map_err(|e|format!("{}: {}", path, e))?;
+        let main = path.to_owned();
+        Self::from_extended_string_with_includes(&text, |origin, included| {
+            let origin = if origin == "<input>" { &main } else { origin };
+            let path = std::path::Path::new(origin).parent().unwrap_or(std::path::Path::new(".")).join(included);
+            let name = path.to_string_lossy().into_owned();
+            let text = std::fs::read_to_string(&path).map_err(|e|format!("{}: {}", name, e))?;
+            Ok((name, text))
+        }).map_err(|e|e.replacen("<input>", path, 1))
+    }
+}
+
+/// Writes the label declarations followed by the problem, the result can be parsed again with `from_extended_string`.
+impl Display for AnnotatedProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mapping : HashMap<Label, String> = self.problem.mapping_label_text.iter().cloned().collect();
+        for (label, description) in &self.descriptions {
+            writeln!(f, "label {} : {}", mapping[label], escape_description(description))?;
+        }
+        write!(f, "{}", self.problem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use itertools::Itertools;
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn extended_format() {
+        let text = "# maximal independent set\nlabel M : in the set\nlet UP = UP\n\nM U U # a node in the set\nP P P\n\n# passive side\nM $UP\nU U\n";
+        let p = Problem::from_extended_string(text).unwrap();
+        // the labels are numbered in the same way, but the order of `mapping_label_text` depends on a HashMap
+        let expected = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert_eq!((&p.problem.active, &p.problem.passive), (&expected.active, &expected.passive));
+        assert_eq!(p.problem.mapping_label_text.iter().sorted().collect_vec(), expected.mapping_label_text.iter().sorted().collect_vec());
+        assert_eq!(p.descriptions.len(), 1);
+
+        let again = Problem::from_extended_string(&p.to_string()).unwrap();
+        assert_eq!(again.to_string(), p.to_string());
+        assert_eq!(again.descriptions, p.descriptions);
+
+        // descriptions can contain the characters used by comments
+        let mut escaped = p.clone();
+        escaped.descriptions[0].1 = "in the set # not a comment \\ and\na new line".into();
+        let again = Problem::from_extended_string(&escaped.to_string()).unwrap();
+        assert_eq!(again.descriptions, escaped.descriptions);
+
+        let macros = Problem::from_extended_string("let C = (0a)(1a)\nlet D = $C(2a)\n$D^3\n\n$C $D").unwrap();
+        assert_eq!(macros.problem.labels().len(), 3);
+    }
+
+    #[test]
+    fn extended_format_includes() {
+        let gadget = "let C = AB\n\nlabel A : first color";
+        let p = Problem::from_extended_string_with_includes("include \"gadget\"\n$C^3\n\n$C C", |_, path|{
+            assert_eq!(path, "gadget");
+            Ok(("gadget".into(), gadget.into()))
+        }).unwrap();
+        assert_eq!(p.problem.labels().len(), 3);
+        assert_eq!(p.descriptions.len(), 1);
+
+        let e = Problem::from_extended_string("include loop\nA\n\nA").unwrap_err();
+        assert!(e.contains("not supported"));
+    }
+
+    #[test]
+    fn extended_format_errors() {
+        assert_eq!(Problem::from_extended_string("A B\n\nA $X").unwrap_err(), "<input>:3:3: undefined macro $X");
+        assert_eq!(Problem::from_extended_string("# c\nA  (B\n\nA B").unwrap_err(), "<input>:2:4: Missing ')'");
+        let e = Problem::from_extended_string_with_includes("include a\nA\n\nA", |_, _|Ok(("a".into(), "include a".into()))).unwrap_err();
+        assert!(e.starts_with("a:1:1: includes are nested too deeply"));
+    }
+}
@@ -6,7 +6,7 @@ use itertools::Itertools;
 
 use crate::{algorithms::diagram::compute_direct_diagram, constraint::Constraint, group::{Exponent, Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::{DiagramDirect, Problem}};
 use serde::{Deserialize, Serialize};
-use super::{event::EventHandler, maximize::{Operation}, diagram::{diagram_indirect_to_reachability_adj, diagram_to_indirect}};
+use super::{event::EventHandler, maximize::{Operation}, diagram::{diagram_indirect_to_reachability_adj, diagram_to_indirect}, checkpoint::checkpoint_key, fixpoint_report::{FixpointFailure, FixpointObstruction}};
 
 
 #[derive(Clone,Debug,Serialize,Deserialize,Eq,PartialEq, Hash)]
@@ -25,6 +25,20 @@ pub struct FixpointDiagram {
 }
 
 impl FixpointDiagram {
+    /// The transitive reduction of the diagram and the names of its labels.
+    pub(crate) fn direct_diagram_and_text(&self) -> (&DiagramDirect, &Vec<(Label, String)>) {
+        (&self.diagram_direct, &self.mapping_newlabel_text)
+    }
+
+    /// A description of the diagram that does not depend on the order of its labels and edges, two diagrams with the same canonical form give the same fixed point.
+    pub(crate) fn canonical_form(&self) -> String {
+        let text : HashMap<_,_> = self.mapping_newlabel_text.iter().cloned().collect();
+        let old_text : HashMap<_,_> = self.mapping_oldlabel_text.iter().cloned().collect();
+        let mapping = self.mapping_label_newlabel.iter().map(|(l,n)|format!("{} = {}",old_text[l],text[n])).sorted();
+        let edges = self.diagram.iter().map(|(a,b)|format!("{} -> {}",text[a],text[b])).sorted();
+        mapping.chain(edges).join("\n")
+    }
+
     fn new(p : &Problem, larger : bool, addarrows : Vec<(Label,Label)> ) -> Self {
         let mut p = p.clone();
         let mut diag = p.diagram_indirect.take().unwrap();
@@ -412,7 +426,8 @@ impl Problem {
     }
 
 
-    pub fn fixpoint_generic(&self, sublabels : Option<Vec<Label>>, fptype : FixpointType, only_compute_triviality : bool, eh: &mut EventHandler ) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), String> {
+    pub fn fixpoint_generic(&self, sublabels : Option<Vec<Label>>, fptype : FixpointType, only_compute_triviality : bool, eh: &mut EventHandler ) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), FixpointFailure> {
+        self.require_regular()?;
         if let Some(sublabels) = sublabels {
             if only_compute_triviality {
                 return Err("The option 'triviality only' is not allowed for partial fixpointing".into());
@@ -421,7 +436,7 @@ impl Problem {
             let mut subproblem = self.harden_keep(&sublabels.iter().cloned().collect(), false);
             subproblem.discard_useless_stuff(false, eh);
             subproblem.fixpoint_diagram = self.fixpoint_diagram.clone();
-            let (fixpoint, diagram, mapping_label_newlabel) = subproblem.fixpoint_generic(None, fptype, false,eh).unwrap();
+            let (fixpoint, diagram, mapping_label_newlabel) = subproblem.fixpoint_generic(None, fptype, false,eh)?;
             let mut newlabel_to_label : HashMap<Label,Label> = mapping_label_newlabel.into_iter().filter(|(l,_)|sublabels.contains(l)).map(|(l,n)|(n,l)).collect();
             let orig_newlabels : HashSet<_> = newlabel_to_label.keys().cloned().collect();
             let mut next_fresh = *self.labels().iter().max().unwrap_or(&0) + 1;
@@ -520,7 +535,11 @@ impl Problem {
                 demisifiable : None,
                 is_trivial_with_input : None,
                 triviality_with_input : None,
-                expressions : None
+                trivial_given_input : None,
+                expressions : None,
+                input_given : None,
+                input_allowed_outputs : None,
+                other_degrees : None
             };
             p.compute_diagram(eh);
             p.discard_useless_stuff(true, eh);
@@ -538,15 +557,30 @@ impl Problem {
             return Ok((p,diagram,self.labels().into_iter().map(|x|(x,x)).collect()));
         } else {
             match fptype {
-                FixpointType::Basic => { self.fixpoint_dup(None, only_compute_triviality,false,eh).map_err(|e|e.into()) },
-                FixpointType::Dup(dups, track) => { self.fixpoint_dup(Some(dups),only_compute_triviality,track,eh).map_err(|e|e.into()) }
+                FixpointType::Basic => {
+                    self.fixpoint_dup(None, only_compute_triviality,false,eh).map_err(|e|{
+                        let fd = self.fixpoint_diagram_with_dup(None);
+                        diagram_failure(e, &fd.mapping_newlabel_text, &fd.diagram)
+                    })
+                },
+                FixpointType::Dup(dups, track) => {
+                    self.fixpoint_dup(Some(dups.clone()),only_compute_triviality,track,eh).map_err(|e|{
+                        let fd = self.fixpoint_diagram_with_dup(Some(&dups));
+                        diagram_failure(e, &fd.mapping_newlabel_text, &fd.diagram)
+                    })
+                }
                 FixpointType::Loop => {
                     if only_compute_triviality {
                         return Err("The option 'triviality only' is not allowed for 'loop' mode".into());
                     }
                     self.fixpoint_loop(eh)
                 },
-                FixpointType::Custom(s) => { self.fixpoint_custom(s,only_compute_triviality,eh).map_err(|e|e.into()) },
+                FixpointType::Custom(s) => {
+                    self.fixpoint_custom(s.clone(),only_compute_triviality,eh).map_err(|e|{
+                        let (mapping_newlabel_text, diagram) = parse_diagram(&s);
+                        diagram_failure(e, &mapping_newlabel_text, &diagram)
+                    })
+                },
 
             }
         }
@@ -556,18 +590,23 @@ impl Problem {
         self.fixpoint_dup(None, only_compute_triviality,false,eh)
     }
 
-    pub fn fixpoint_dup(&self, dup : Option<Vec<Vec<Label>>>, only_compute_triviality:bool,track:bool,eh: &mut EventHandler) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), &'static str> {
-        //println!("called dup");
+    /// The diagram used by the fixed point procedure: the one stored in the problem, or the default one, with the given groups of labels duplicated.
+    pub(crate) fn fixpoint_diagram_with_dup(&self, dup : Option<&Vec<Vec<Label>>>) -> FixpointDiagram {
         let mut fd = if let Some((_,fd)) = self.fixpoint_diagram.clone() {
             fd
         } else {
             FixpointDiagram::new(self, false,vec![])
         };
-        //println!("generated diagram1");
         if let Some(dup) = dup {
-            fd.duplicate_labels(&dup);
+            fd.duplicate_labels(dup);
         }
-        //println!("generated diagram2");
+        fd
+    }
+
+    pub fn fixpoint_dup(&self, dup : Option<Vec<Vec<Label>>>, only_compute_triviality:bool,track:bool,eh: &mut EventHandler) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), &'static str> {
+        self.require_regular()?;
+        //println!("called dup");
+        let fd = self.fixpoint_diagram_with_dup(dup.as_ref());
         let mapping_label_newlabel = fd.mapping_label_newlabel.clone();
         let mapping_newlabel_text = fd.mapping_newlabel_text.clone();
         let diagram = fd.diagram.clone();
@@ -631,6 +670,7 @@ impl Problem {
 
 
     pub fn fixpoint_custom(&self, text_diag : String, only_compute_triviality:bool, eh: &mut EventHandler) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), &'static str> {
+        self.require_regular()?;
         let text_mapping = text_diag.lines().filter(|line|!line.starts_with("#") && line.contains("=")).join("\n");
         let text_diagram = text_diag.lines().filter(|line|!line.starts_with("#") && (line.contains("->") || line.contains("<-"))).join("\n");
 
@@ -669,6 +709,7 @@ impl Problem {
     }
 
     pub fn fixpoint_onestep_only_determine_triviality(&self, mapping_label_newlabel : &Vec<(Label, Label)>, mapping_newlabel_text : &Vec<(Label, String)>, diagram : &Vec<(Label,Label)>, tracking : Option<&CHashMap<Line,Tracking>>, tracking_passive : Option<&CHashMap<Line,Tracking>>, eh: &mut EventHandler) -> Result<(Self,Constraint), &'static str> {
+        self.require_regular()?;
         //if self.passive.degree != crate::line::Degree::Finite(2) {
         //    panic!("This option only works when the passive degree is 2");
         //}
@@ -700,13 +741,10 @@ impl Problem {
         let old_labels : HashMap<Label,String> = mapping_label_newlabel.iter().map(|(l,&n)|{
             (n,old_to_str[l].clone())
         }).collect();
-        //let avoidance = Problem::avoidance_sets(&old_labels.keys().cloned().collect(),&passive_successors);
-        //println!("----Tree----");
-        //Problem::print_tree_for_label(&tree_for_labels,&tostr,&avoidance,&old_labels,tostr_rev["(X_Y)"]);
-        //println!("----End Tree----");
+        let avoidance = Problem::avoidance_sets(&old_labels.keys().cloned().collect(),&passive_successors);
 
         //println!("Computing passive");
-        let passive = procedure(&passive, &newlabels, &diagram_indirect_rev, &mapping_newlabel_text, tracking_passive, eh)?;
+        let passive = procedure(&passive, &newlabels, &diagram_indirect_rev, tracking_passive, eh)?;
         let passive = passive.edited(|g| Group::from(passive_successors[&g.first()].iter().cloned().sorted().collect()));
         //for line in &passive.lines {
         //    println!("{}",line.to_string(&tostr));
@@ -796,6 +834,12 @@ impl Problem {
                 if Problem::fp_is_obtainable(&mut active, &mut not_obtainable,&target_line, &passive_successors,&tostr,&tree_for_labels) {
                     let mut p = self.clone();
                     p.fixpoint_procedure_works = Some(false);
+                    // the trees explain how the labels of the configuration are obtained, they are shown together with the problem
+                    let mut trees = format!("The configuration {} can be obtained. Trees of the labels (dot format), each node shows the avoided labels:\n", target_line.to_string(&tostr));
+                    for &label in target_line.line_set().iter() {
+                        trees += &Problem::tree_for_label(&tree_for_labels,&tostr,&avoidance,&old_labels,label);
+                    }
+                    p.expressions = Some(trees);
                     //println!("obtainable lines:");
                     //for line in &active.lines {
                     //    println!("{}",line.to_string(&tostr));
@@ -812,38 +856,35 @@ impl Problem {
         return Ok((p,Constraint{lines:vec![],is_maximized:false,degree:passive.degree}));
     }
 
-    fn print_tree_for_label(tree_for_labels: &HashMap<Label,Vec<(Label,Label)>>,tostr:&HashMap<Label,String>, avoidance: &HashMap<Label, Vec<Label>>, old_labels : &HashMap<Label,String>,label : Label ){
+    /// The tree describing how `label` can be obtained, in the dot format.
+    fn tree_for_label(tree_for_labels: &HashMap<Label,Vec<(Label,Label)>>,tostr:&HashMap<Label,String>, avoidance: &HashMap<Label, Vec<Label>>, old_labels : &HashMap<Label,String>,label : Label ) -> String {
         let mut id = 1;
-        println!("digraph {{");
-        Problem::print_tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,label, 0, &mut id);
-        println!("}}");
+        let mut s = String::from("digraph {\n");
+        Problem::tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,label, 0, &mut id, &mut s);
+        s += "}\n";
+        s
     }
 
-    fn print_tree_for_label_aux(tree_for_labels: &HashMap<Label,Vec<(Label,Label)>>,tostr:&HashMap<Label,String>, avoidance: &HashMap<Label, Vec<Label>>, old_labels : &HashMap<Label,String>,label : Label,parent_id : usize, id : &mut usize ){
+    fn tree_for_label_aux(tree_for_labels: &HashMap<Label,Vec<(Label,Label)>>,tostr:&HashMap<Label,String>, avoidance: &HashMap<Label, Vec<Label>>, old_labels : &HashMap<Label,String>,label : Label,parent_id : usize, id : &mut usize, s : &mut String ){
         let v = &tree_for_labels[&label];
         if v.len() != 1 || parent_id == 0 {
             let avoided_labels = avoidance[&label].iter().map(|l|&old_labels[&l]).sorted().join("");
             *id += 1;
             let node_id = *id;
-            println!("  {}[label=\"{} {}\"]",node_id,avoided_labels,tostr[&label]);
-            if parent_id != 0 { println!("  {} -> {}",parent_id,node_id); }
-            //println!("{} ({})",tostr[&label],avoided_labels);
-            //for (l1,l2) in v {
-            //    println!("    {} -> {}",tostr[l1],tostr[l2]);
-            //}
-            println!();
+            *s += &format!("  {}[label=\"{} {}\"]\n",node_id,avoided_labels,tostr[&label]);
+            if parent_id != 0 { *s += &format!("  {} -> {}\n",parent_id,node_id); }
             for (l1,l2) in v {
                 *id += 1;
                 let branch_id = *id;
-                println!("  {}[label=\"\" shape=point]",branch_id);
-                println!("  {} -> {}",node_id,branch_id);
-                Problem::print_tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l1,branch_id,id);
-                Problem::print_tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l2,branch_id,id);
+                *s += &format!("  {}[label=\"\" shape=point]\n",branch_id);
+                *s += &format!("  {} -> {}\n",node_id,branch_id);
+                Problem::tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l1,branch_id,id,s);
+                Problem::tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l2,branch_id,id,s);
             }
         } else {
             for (l1,l2) in v {
-                Problem::print_tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l1,parent_id,id);
-                Problem::print_tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l2,parent_id,id);
+                Problem::tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l1,parent_id,id,s);
+                Problem::tree_for_label_aux(tree_for_labels,tostr,avoidance,old_labels,*l2,parent_id,id,s);
             }
         }
     }
@@ -983,6 +1024,7 @@ impl Problem {
     }
 
     pub fn fixpoint_onestep(&self, only_compute_triviality:bool, mapping_label_newlabel : &Vec<(Label, Label)>, mapping_newlabel_text : &Vec<(Label, String)>, diagram : &Vec<(Label,Label)>, tracking : Option<&CHashMap<Line,Tracking>>, tracking_passive : Option<&CHashMap<Line,Tracking>>, eh: &mut EventHandler) -> Result<(Self,Constraint), &'static str> {
+        self.require_regular()?;
         if only_compute_triviality {
             return self.fixpoint_onestep_only_determine_triviality(mapping_label_newlabel,mapping_newlabel_text,diagram,tracking,tracking_passive,eh);
         }
@@ -1000,8 +1042,8 @@ impl Problem {
         let diagram_indirect = diagram_to_indirect(&newlabels,&diagram);
         let diagram_indirect_rev = diagram_indirect.iter().map(|&(a,b)|(b,a)).collect();
 
-        let active = procedure(&active, &newlabels, &diagram_indirect, &mapping_newlabel_text, tracking, eh)?;
-        let passive = procedure(&passive, &newlabels, &diagram_indirect_rev, &mapping_newlabel_text, tracking_passive, eh)?;
+        let active = procedure(&active, &newlabels, &diagram_indirect, tracking, eh)?;
+        let passive = procedure(&passive, &newlabels, &diagram_indirect_rev, tracking_passive, eh)?;
 
         let passive_successors = diagram_indirect_to_reachability_adj(&newlabels,&diagram_indirect);
         let passive_before_edit = passive.clone();
@@ -1030,7 +1072,11 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs : self.allowed_outputs_after_relabelling(|l|mapping_label_newlabel.get(&l).map(|n|passive_successors[n].iter().cloned().collect()).unwrap_or_default()),
+            other_degrees : None
         };
         p.mapping_label_text = mapping_newlabel_text.clone();
         Ok((p,passive_before_edit))
@@ -1109,7 +1155,8 @@ impl Problem {
     }*/
 
 
-    pub fn fixpoint_addarrow<F>(&self, mut f: F) where F: FnMut(Vec<(Label, Label)>, usize, bool) {
+    pub fn fixpoint_addarrow<F>(&self, mut f: F) -> Result<(), &'static str> where F: FnMut(Vec<(Label, Label)>, usize, bool) {
+        self.require_regular()?;
         let labels = self.labels();
         let succ = self.diagram_indirect_to_reachability_adj();
         let missing_arrows = labels.iter().cloned().cartesian_product(labels.iter().cloned()).filter(|(l1,l2)|{
@@ -1155,10 +1202,11 @@ impl Problem {
                 let is_trivial = r.trivial_sets.as_ref().unwrap().len() > 0;
                 f(subset_of_arrows_to_add,r.active.lines.len(), is_trivial);
                 if !is_trivial {
-                    return;
+                    return Ok(());
                 }
             }
         }
+        Ok(())
     }
 
 }
@@ -1363,11 +1411,57 @@ pub fn right_closed_subsets(labels : &[Label], successors : &HashMap<Label, Hash
 }
 
 
-fn procedure(constraint : &Constraint, labels : &[Label], diagram_indirect : &Vec<(Label, Label)>, mapping : &Vec<(Label, String)>, tracking : Option<&CHashMap<Line,Tracking>>, eh: &mut EventHandler) -> Result<Constraint, &'static str> {
-    let becomes_star = 100;
+/// The minimal labels among the ones that are reachable from both `l1` and `l2`.
+fn minimal_common(reachable : &HashMap<Label, HashSet<Label>>, l1 : Label, l2 : Label) -> Vec<Label> {
+    let mut common : HashSet<Label> = reachable[&l1].intersection(&reachable[&l2]).cloned().collect();
+    for l in common.clone().into_iter() {
+        for r in reachable[&l].iter().filter(|&&x|x != l) {
+            common.remove(r);
+        }
+    }
+    common.into_iter().sorted().collect()
+}
+
+/// The pairs of labels that do not have a unique minimal common successor or a unique maximal common predecessor, for which the procedure fails.
+/// If the labels of a pair reach the labels of another pair that fails with the same candidates, only the latter is reported,
+/// since the former fails for the same reason: for example, if A and B have two minimal common successors, the same holds for their predecessors.
+pub(crate) fn lattice_obstructions(labels : &[Label], diagram : &Vec<(Label, Label)>) -> Vec<FixpointObstruction> {
+    let diagram_indirect = diagram_to_indirect(labels, diagram);
+    let successors = diagram_indirect_to_reachability_adj(labels,&diagram_indirect);
+    let predecessors = diagram_indirect_to_reachability_adj(labels,&diagram_indirect.iter().cloned().map(|(a,b)|(b,a)).collect());
+    let mut failures = vec![];
+    for (&a, &b) in labels.iter().tuple_combinations() {
+        for (reachable, is_successors) in [(&successors, true), (&predecessors, false)] {
+            let candidates = minimal_common(reachable, a, b);
+            if candidates.len() != 1 {
+                failures.push((a, b, is_successors, candidates));
+            }
+        }
+    }
+
+    // whether the pair (c,d) is reachable from the pair (a,b)
+    let reaches = |reachable : &HashMap<Label, HashSet<Label>>, (a, b) : (Label, Label), (c, d) : (Label, Label)| {
+        (reachable[&a].contains(&c) && reachable[&b].contains(&d)) || (reachable[&a].contains(&d) && reachable[&b].contains(&c))
+    };
+    failures.iter().filter(|(a, b, is_successors, candidates)|{
+        let reachable = if *is_successors { &successors } else { &predecessors };
+        !failures.iter().any(|(c, d, other_successors, other_candidates)|{
+            other_successors == is_successors && other_candidates == candidates && (c, d) != (a, b)
+                && reaches(reachable, (*a, *b), (*c, *d)) && !reaches(reachable, (*c, *d), (*a, *b))
+        })
+    }).map(|(a, b, is_successors, candidates)|{
+        FixpointObstruction::NotALattice { a : *a, b : *b, successors : *is_successors, candidates : candidates.clone() }
+    }).collect()
+}
 
+/// Describes why the procedure failed with the diagram given by `mapping_newlabel_text` and `diagram`.
+fn diagram_failure(message : &str, mapping_newlabel_text : &Vec<(Label, String)>, diagram : &Vec<(Label, Label)>) -> FixpointFailure {
+    let labels = mapping_newlabel_text.iter().map(|&(l,_)|l).collect_vec();
+    FixpointFailure::new(message, lattice_obstructions(&labels, diagram), mapping_newlabel_text.clone(), false)
+}
 
-    let mapping : HashMap<_,_> = mapping.iter().cloned().collect();
+fn procedure(constraint : &Constraint, labels : &[Label], diagram_indirect : &Vec<(Label, Label)>, tracking : Option<&CHashMap<Line,Tracking>>, eh: &mut EventHandler) -> Result<Constraint, &'static str> {
+    let becomes_star = 100;
 
     let successors = diagram_indirect_to_reachability_adj(&labels,&diagram_indirect);
     let predecessors = diagram_indirect_to_reachability_adj(&labels,&diagram_indirect.iter().cloned().map(|(a,b)|(b,a)).collect());
@@ -1378,33 +1472,20 @@ fn procedure(constraint : &Constraint, labels : &[Label], diagram_indirect : &Ve
 
     eh.notify("checking if the diagram is valid", 0, 0);
 
+    // see lattice_obstructions for a description of the failures
     for &l1 in labels {
         for &l2 in labels {
-            let mut common : HashSet<Label> = successors[&l1].intersection(&successors[&l2]).cloned().collect();
-            for l in common.clone().into_iter() {
-                for r in successors[&l].iter().filter(|&&x|x != l) {
-                    common.remove(r);
-                }
-            }
+            let common = minimal_common(&successors, l1, l2);
             if common.len() != 1 {
-                println!("case 1 bad labels {} {}",mapping[&l1],mapping[&l2]);
                 return Err("The diagram does not satisfy the requirements");
             }
-            //assert!(common.len() == 1);
-            unions.insert((l1,l2),common.into_iter().next().unwrap());
+            unions.insert((l1,l2),common[0]);
 
-            let mut common : HashSet<Label> = predecessors[&l1].intersection(&predecessors[&l2]).cloned().collect();
-            for l in common.clone().into_iter() {
-                for r in predecessors[&l].iter().filter(|&&x|x != l) {
-                    common.remove(r);
-                }
-            }
+            let common = minimal_common(&predecessors, l1, l2);
             if common.len() != 1 {
-                println!("case 2 bad labels {} {}",mapping[&l1],mapping[&l2]);
                 return Err("The diagram does not satisfy the requirements");
             }
-            //assert!(common.len() == 1);
-            intersections.insert((l1,l2),common.into_iter().next().unwrap());
+            intersections.insert((l1,l2),common[0]);
         }
     }
 
@@ -1423,7 +1504,12 @@ fn procedure(constraint : &Constraint, labels : &[Label], diagram_indirect : &Ve
     let mut newconstraint = constraint.clone();
     newconstraint.is_maximized = false;
 
-    newconstraint.maximize_custom(eh,true,false,tracking,f_is_superset, f_union, f_intersection);
+    // the operations depend on the diagram, so it is part of the name of the checkpoint
+    let checkpoint_tag = format!("fixpoint-{}", checkpoint_key(&(labels, diagram_indirect)));
+    newconstraint.maximize_custom(eh,true,false,tracking,Some(&checkpoint_tag),f_is_superset, f_union, f_intersection).map_err(|e|{
+        eprintln!("{}", e);
+        "The lines could not be combined, the details have been printed on the standard error"
+    })?;
     /*println!("obtained constraint");
     for line in &newconstraint.lines {
         println!("{}",line.to_string(&mapping));
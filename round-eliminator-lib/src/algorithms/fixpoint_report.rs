@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{group::{Group, Label}, line::Line, problem::Problem};
+
+/// Something that prevents the fixed point procedure from giving a lower bound.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FixpointObstruction {
+    /// The labels `a` and `b` of the diagram do not have a unique minimal common successor (if `successors` is true) or a unique maximal common predecessor.
+    /// `candidates` contains the minimal common successors (maximal common predecessors), it is empty if there are none.
+    NotALattice { a : Label, b : Label, successors : bool, candidates : Vec<Label> },
+    /// The obtained problem is 0-round solvable: all the labels of `set` are allowed together on the passive side, and `configuration` is an active configuration that only uses labels of `set`.
+    Trivial { set : Vec<Label>, configuration : Line },
+    /// In loop mode, the `compatible` expressions are pairwise compatible with any diagram, and they are obtained from the `original` ones.
+    Expressions { compatible : Vec<String>, original : Vec<String>, algorithm : Option<String> },
+}
+
+/// A change that may remove an obstruction.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FixpointRepair {
+    /// Add the arrow `a -> b` to the diagram, that can then be given as a custom diagram.
+    AddArrow(Label, Label),
+    /// Duplicate these labels of the default diagram, as done by `FixpointType::Dup`.
+    Duplicate(Vec<Label>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FixpointFailure {
+    pub message : String,
+    pub obstructions : Vec<FixpointObstruction>,
+    pub repairs : Vec<FixpointRepair>,
+    /// Names of the labels used by the obstructions and by the repairs.
+    pub mapping_label_text : Vec<(Label, String)>,
+    /// The whole report, rendered with `mapping_label_text`.
+    pub text : String,
+}
+
+impl FixpointFailure {
+    /// Suggests repairs for the given obstructions and renders the report.
+    /// Duplications are suggested only if `duplicable` is true, that is, if the labels are the ones of the default diagram.
+    pub fn new(message : &str, obstructions : Vec<FixpointObstruction>, mapping_label_text : Vec<(Label, String)>, duplicable : bool) -> Self {
+        let mut repairs = vec![];
+        for obstruction in &obstructions {
+            match obstruction {
+                FixpointObstruction::NotALattice { a, b, candidates, .. } => {
+                    // making one of the candidates (or one of the two labels) reachable from the other leaves a single candidate
+                    let labels = if candidates.is_empty() { vec![*a, *b] } else { candidates.clone() };
+                    for (&x, &y) in labels.iter().tuple_combinations() {
+                        repairs.push(FixpointRepair::AddArrow(x, y));
+                        repairs.push(FixpointRepair::AddArrow(y, x));
+                    }
+                }
+                FixpointObstruction::Trivial { set, .. } => {
+                    if duplicable {
+                        repairs.push(FixpointRepair::Duplicate(set.clone()));
+                    }
+                }
+                FixpointObstruction::Expressions { .. } => {}
+            }
+        }
+        let repairs = repairs.into_iter().unique().collect();
+
+        let mut failure = FixpointFailure { message : message.to_owned(), obstructions, repairs, mapping_label_text, text : String::new() };
+        failure.text = failure.render();
+        failure
+    }
+
+    fn render(&self) -> String {
+        let mapping : HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        let names = |labels : &[Label]| labels.iter().map(|l|&mapping[l]).join(", ");
+
+        let mut s = self.message.clone();
+        for obstruction in &self.obstructions {
+            s += "\n";
+            match obstruction {
+                FixpointObstruction::NotALattice { a, b, successors, candidates } => {
+                    let what = if *successors { "successor" } else { "predecessor" };
+                    if candidates.is_empty() {
+                        s += &format!("{} and {} have no common {}.", mapping[a], mapping[b], what);
+                    } else {
+                        let extremal = if *successors { "minimal" } else { "maximal" };
+                        s += &format!("{} and {} have more than one {} common {}: {}.", mapping[a], mapping[b], extremal, what, names(candidates));
+                    }
+                }
+                FixpointObstruction::Trivial { set, configuration } => {
+                    s += &format!("The labels {} are allowed together on the passive side, and the active configuration {} only uses them.", names(set), configuration.to_string(&mapping));
+                }
+                FixpointObstruction::Expressions { compatible, original, algorithm } => {
+                    s += &format!("These expressions will be pairwise compatible with any diagram: {}\nOriginal expressions:\n", compatible.join(", "));
+                    for e in original {
+                        s += &format!("{}\n", e);
+                    }
+                    match algorithm {
+                        Some(algo) => s += &format!("Obtained algorithm:\n{}", algo),
+                        None => s += "Cannot convert it into an algorithm",
+                    }
+                }
+            }
+        }
+        if !self.repairs.is_empty() {
+            s += "\nPossible repairs:";
+            for repair in &self.repairs {
+                match repair {
+                    FixpointRepair::AddArrow(a, b) => s += &format!("\n  add the arrow {} -> {}", mapping[a], mapping[b]),
+                    FixpointRepair::Duplicate(labels) => s += &format!("\n  duplicate {}", names(labels)),
+                }
+            }
+        }
+        s
+    }
+}
+
+impl Display for FixpointFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl From<&str> for FixpointFailure {
+    fn from(message : &str) -> Self {
+        FixpointFailure::new(message, vec![], vec![], false)
+    }
+}
+
+impl From<String> for FixpointFailure {
+    fn from(message : String) -> Self {
+        FixpointFailure::new(&message, vec![], vec![], false)
+    }
+}
+
+impl From<FixpointFailure> for String {
+    fn from(failure : FixpointFailure) -> Self {
+        failure.text
+    }
+}
+
+impl Problem {
+    /// If this problem is a fixed point and it is 0-round solvable, explains why, using the trivial sets that have been computed.
+    /// `duplicable` tells whether the labels of this problem are the ones of the default diagram of the original problem.
+    pub fn fixpoint_triviality_report(&self, duplicable : bool) -> Option<FixpointFailure> {
+        let trivial_sets = self.trivial_sets.as_ref()?;
+        if trivial_sets.is_empty() {
+            return None;
+        }
+        let obstructions = trivial_sets.iter().filter_map(|set|{
+            let restricted = |g : &Group| Group::from(g.iter().filter(|l|set.contains(l)).cloned().collect());
+            let line = self.active.lines.iter().find(|line|line.parts.iter().all(|part|!restricted(&part.group).is_empty()))?;
+            Some(FixpointObstruction::Trivial { set : set.clone(), configuration : line.edited(restricted) })
+        }).collect();
+        Some(FixpointFailure::new("The obtained problem is 0-round solvable.", obstructions, self.mapping_label_text.clone(), duplicable))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::{event::EventHandler, fixpoint::{lattice_obstructions, FixpointType}}, problem::Problem};
+
+    use super::{FixpointObstruction, FixpointRepair};
+
+    #[test]
+    fn fixpoint_failure_report() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A A\nB B\nC C\n\nA B\nA C\nB C").unwrap();
+        let failure = p.fixpoint_generic(None, FixpointType::Custom("A -> B\nA -> C".into()), false, eh).unwrap_err();
+        let label = |name : &str| failure.mapping_label_text.iter().find(|(_, s)|s == name).unwrap().0;
+        let (b, c) = (label("B"), label("C"));
+        assert_eq!(failure.obstructions, vec![FixpointObstruction::NotALattice { a : b.min(c), b : b.max(c), successors : true, candidates : vec![] }]);
+        assert!(failure.repairs.contains(&FixpointRepair::AddArrow(b, c)));
+        assert!(failure.text.contains("add the arrow B -> C"));
+
+        let mut p = Problem::from_string("A A\nB B\n\nA A\nA B").unwrap();
+        p.compute_triviality(eh);
+        let report = p.fixpoint_triviality_report(true).unwrap();
+        assert_eq!(report.repairs.len(), 1);
+        assert!(matches!(&report.repairs[0], FixpointRepair::Duplicate(set) if set.len() == 1));
+
+        let mut p = Problem::from_string("A B\n\nA A\nB B").unwrap();
+        p.compute_triviality(eh);
+        assert!(p.fixpoint_triviality_report(true).is_none());
+    }
+
+    #[test]
+    fn minimal_lattice_obstructions() {
+        let (a, b, c, d, a1, b1) = (0, 1, 2, 3, 4, 5);
+        let diagram = vec![(a1, a), (b1, b), (a, c), (a, d), (b, c), (b, d)];
+        // A and B have the minimal common successors C and D, and so do all their predecessors, but only A and B are reported
+        let obstructions = lattice_obstructions(&[a, b, c, d, a1, b1], &diagram);
+        assert_eq!(obstructions, vec![
+            FixpointObstruction::NotALattice { a, b, successors : true, candidates : vec![c, d] },
+            FixpointObstruction::NotALattice { a : c, b : d, successors : true, candidates : vec![] },
+            FixpointObstruction::NotALattice { a : c, b : d, successors : false, candidates : vec![a, b] },
+            FixpointObstruction::NotALattice { a : a1, b : b1, successors : false, candidates : vec![] },
+        ]);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, problem::Problem, serial::fix_problem};
+
+use super::{event::EventHandler, fixpoint::{FixpointDiagram, FixpointType}};
+#[cfg(not(target_arch = "wasm32"))]
+use super::parallel::CollectWithProgress;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+/// A modification of the default diagram used by the fixed point procedure.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DiagramEdit {
+    /// Add the arrow between these labels of the problem before computing the default diagram.
+    AddArrow(Label, Label),
+    /// Duplicate this label of the default diagram, as done by `FixpointType::Dup`.
+    Duplicate(Label),
+    /// Compute the fixed point only on these labels of the problem, as done with `sublabels`.
+    Sublabels(Vec<Label>),
+}
+
+/// A non-trivial fixed point found by `fixpoint_search`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FixpointCandidate {
+    pub edits : Vec<DiagramEdit>,
+    pub fixpoint : Problem,
+    pub diagram : FixpointDiagram,
+}
+
+/// A combination of edits to try, together with the problem that stores the default diagram obtained after the arrows and the sublabels are applied.
+struct Attempt {
+    edits : Vec<DiagramEdit>,
+    base : Problem,
+    sublabels : Option<Vec<Label>>,
+    dups : Vec<Vec<Label>>,
+    diagram : FixpointDiagram,
+}
+
+impl Attempt {
+    fn run(&self) -> Option<FixpointCandidate> {
+        let eh = &mut EventHandler::null();
+        let fptype = if self.dups.is_empty() { FixpointType::Basic } else { FixpointType::Dup(self.dups.clone(), false) };
+        let (mut fixpoint, _, _) = self.base.fixpoint_generic(self.sublabels.clone(), fptype, false, eh).ok()?;
+        fix_problem(&mut fixpoint, true, true, eh);
+        if !fixpoint.trivial_sets.as_ref().unwrap().is_empty() || fixpoint.is_trivial_given_input() {
+            return None;
+        }
+        Some(FixpointCandidate { edits : self.edits.clone(), fixpoint, diagram : self.diagram.clone() })
+    }
+}
+
+impl Problem {
+    /// Searches for non-trivial fixed points obtained by modifying the default diagram: adding arrows, duplicating labels of the diagram, and computing the fixed point on all labels but one.
+    /// Combinations of up to `max_edits` edits are tried, fewer edits first, and at most `budget` of them are given to the fixed point procedure.
+    /// Combinations that give the same diagram are tried only once, and equal fixed points are reported only once.
+    /// The result is sorted by number of edits, then by number of labels and active lines of the fixed point.
+    pub fn fixpoint_search(&self, max_edits : usize, budget : usize, eh : &mut EventHandler) -> Vec<FixpointCandidate> {
+        let mut problem = self.clone();
+        if problem.diagram_indirect.is_none() {
+            problem.compute_partial_diagram(eh);
+        }
+        // the fixed point procedure uses the stored diagram if there is one
+        problem.fixpoint_diagram = None;
+
+        let labels = problem.labels();
+        let succ = problem.diagram_indirect_to_reachability_adj();
+        let missing_arrows = labels.iter().cloned().cartesian_product(labels.iter().cloned()).filter(|(l1,l2)|{
+            l1 != l2 && !succ[l1].contains(l2)
+        }).collect_vec();
+
+        let mut sublabel_choices = vec![None];
+        if labels.len() > 1 {
+            for &l in &labels {
+                sublabel_choices.push(Some(labels.iter().cloned().filter(|&x|x != l).collect_vec()));
+            }
+        }
+
+        let mut seen_diagrams = HashSet::new();
+        let mut seen_fixpoints = HashSet::new();
+        let mut tried = 0;
+        let mut result = vec![];
+
+        'search: for edits in 0..=max_edits {
+            let mut attempts = vec![];
+            'level: for sublabels in &sublabel_choices {
+                let sublabel_edits = sublabels.iter().map(|s|DiagramEdit::Sublabels(s.clone())).collect_vec();
+                if sublabel_edits.len() > edits {
+                    continue;
+                }
+                let arrows = missing_arrows.iter().filter(|(l1,l2)|{
+                    sublabels.as_ref().map_or(true, |s|s.contains(l1) && s.contains(l2))
+                }).cloned().collect_vec();
+                for num_arrows in 0..=edits - sublabel_edits.len() {
+                    for arrows in arrows.iter().cloned().combinations(num_arrows) {
+                        let mut base = problem.clone();
+                        base.compute_default_fixpoint_diagram(sublabels.clone(), false, arrows.clone(), eh);
+                        let newlabels = base.fixpoint_diagram.as_ref().unwrap().1.direct_diagram_and_text().1.iter().map(|&(l,_)|l).collect_vec();
+                        for dups in newlabels.into_iter().combinations(edits - sublabel_edits.len() - num_arrows) {
+                            let dups = dups.into_iter().map(|l|vec![l]).collect_vec();
+                            let diagram = base.fixpoint_diagram_with_dup(if dups.is_empty() { None } else { Some(&dups) });
+                            if !seen_diagrams.insert((sublabels.clone(), diagram.canonical_form())) {
+                                continue;
+                            }
+                            if tried + attempts.len() >= budget {
+                                break 'level;
+                            }
+                            let edits = sublabel_edits.iter().cloned()
+                                .chain(arrows.iter().map(|&(a,b)|DiagramEdit::AddArrow(a, b)))
+                                .chain(dups.iter().map(|d|DiagramEdit::Duplicate(d[0])))
+                                .collect();
+                            attempts.push(Attempt { edits, base : base.clone(), sublabels : sublabels.clone(), dups, diagram });
+                        }
+                    }
+                }
+            }
+
+            tried += attempts.len();
+            let total = attempts.len();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let found = attempts.into_par_iter().map(|attempt|attempt.run()).collect_with_progress(eh, "fixed point search", total);
+
+            #[cfg(target_arch = "wasm32")]
+            let found : Vec<_> = attempts.into_iter().enumerate().filter_map(|(i, attempt)|{
+                eh.notify("fixed point search", i, total);
+                attempt.run()
+            }).collect();
+
+            for candidate in found {
+                if seen_fixpoints.insert(candidate.fixpoint.to_string()) {
+                    result.push(candidate);
+                }
+            }
+            if tried >= budget {
+                break 'search;
+            }
+        }
+
+        // the candidates of each level are found in parallel, in any order
+        result.sort_by_key(|c|(c.edits.len(), c.fixpoint.labels().len(), c.fixpoint.active.lines.len()));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::DiagramEdit;
+
+    #[test]
+    fn fixpoint_search() {
+        let eh = &mut EventHandler::null();
+        // the default diagram gives a trivial fixed point, duplicating the label (XY) of the diagram gives a non-trivial one
+        let p = Problem::from_string("A A X\nB B Y\n\nAX BY\nXY XY").unwrap();
+        let found = p.fixpoint_search(1, 100, eh);
+        assert!(!found.is_empty());
+        for candidate in &found {
+            assert_eq!(candidate.edits.len(), 1);
+            assert!(candidate.fixpoint.trivial_sets.as_ref().unwrap().is_empty());
+        }
+        assert!(found.iter().any(|c|matches!(c.edits[0], DiagramEdit::Duplicate(_))));
+        assert!(found.windows(2).all(|w|w[0].edits.len() <= w[1].edits.len()));
+
+        assert!(p.fixpoint_search(3, 0, eh).is_empty());
+    }
+}
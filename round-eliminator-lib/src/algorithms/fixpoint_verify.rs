@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::Constraint, group::{Group, Label}, line::Line, problem::Problem};
+
+use super::event::EventHandler;
+
+/// Maximum number of partial label mappings visited while searching for a relaxation.
+const SEARCH_BUDGET : usize = 10_000_000;
+
+/// A certificate that a problem is a non-trivial fixed point that is a relaxation of another problem.
+/// Problems are stored as text and labels by name, so that the certificate does not depend on how labels are numbered, and `check` can verify it from scratch.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FixpointCertificate {
+    /// The problem for which the lower bound is claimed.
+    pub original : String,
+    /// The claimed fixed point.
+    pub fixpoint : String,
+    /// The problem obtained by applying round elimination to the fixed point, with maximized passive side.
+    pub speedup : String,
+    /// Each label of `original` and the label of `fixpoint` that replaces it.
+    pub relaxation : Vec<(String, String)>,
+    /// Each label of `speedup` and the label of `fixpoint` that replaces it.
+    pub fixpoint_relaxation : Vec<(String, String)>,
+}
+
+/// The configurations allowed by the constraint, each with a single label in each part.
+fn configurations(constraint : &Constraint) -> Vec<Line> {
+    constraint.lines.iter().flat_map(|line|line.all_choices(true)).unique().collect()
+}
+
+fn apply(line : &Line, f : &HashMap<Label, Label>) -> Line {
+    let mut line = line.edited(|g|Group::from(vec![f[&g.first()]]));
+    line.normalize();
+    line
+}
+
+/// Whether replacing labels of `from` according to `f` maps every allowed configuration of `from` to an allowed configuration of `to`.
+fn is_relaxation(from : &Problem, to : &Problem, f : &HashMap<Label, Label>) -> bool {
+    configurations(&from.active).iter().all(|line|to.active.includes_single_line(&apply(line, f)))
+        && configurations(&from.passive).iter().all(|line|to.passive.includes_single_line(&apply(line, f)))
+}
+
+/// Searches for a mapping from the labels of `from` to the labels of `to` that shows that `to` is a relaxation of `from`.
+/// For each label, the labels in `preferred` are tried first.
+fn find_relaxation(from : &Problem, to : &Problem, preferred : &HashMap<Label, Vec<Label>>) -> Result<Option<HashMap<Label, Label>>, String> {
+    let labels = from.labels();
+    let targets = to.labels();
+    let position : HashMap<_,_> = labels.iter().enumerate().map(|(i, &l)|(l, i)).collect();
+
+    // each configuration is checked as soon as all its labels have been assigned
+    let mut to_check = vec![vec![]; labels.len()];
+    for (constraint, target) in [(&from.active, &to.active), (&from.passive, &to.passive)] {
+        for line in configurations(constraint) {
+            let last = line.parts.iter().map(|part|position[&part.group.first()]).max().unwrap();
+            to_check[last].push((line, target));
+        }
+    }
+
+    let candidates = labels.iter().map(|l|{
+        let first = preferred.get(l).cloned().unwrap_or_default().into_iter().filter(|t|targets.contains(t));
+        first.chain(targets.iter().cloned()).unique().collect_vec()
+    }).collect_vec();
+
+    fn search(i : usize, labels : &[Label], candidates : &[Vec<Label>], to_check : &[Vec<(Line, &Constraint)>], f : &mut HashMap<Label, Label>, visited : &mut usize) -> Result<bool, String> {
+        if i == labels.len() {
+            return Ok(true);
+        }
+        for &t in &candidates[i] {
+            *visited += 1;
+            if *visited > SEARCH_BUDGET {
+                return Err("The search for a label mapping exceeded its budget".into());
+            }
+            f.insert(labels[i], t);
+            if to_check[i].iter().all(|(line, target)|target.includes_single_line(&apply(line, f))) && search(i + 1, labels, candidates, to_check, f, visited)? {
+                return Ok(true);
+            }
+        }
+        f.remove(&labels[i]);
+        Ok(false)
+    }
+
+    let mut f = HashMap::new();
+    let mut visited = 0;
+    if search(0, &labels, &candidates, &to_check, &mut f, &mut visited)? {
+        Ok(Some(f))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parses the problem again from its text, so that nothing computed before (diagrams, triviality, ...) is used.
+fn fresh(p : &Problem) -> Result<Problem, String> {
+    if !p.is_regular() {
+        return Err("Only problems where all nodes have the same degree can be verified".into());
+    }
+    let p = Problem::from_string(p.to_string())?;
+    if p.active.lines.iter().chain(p.passive.lines.iter()).any(|line|line.has_star()) {
+        return Err("Problems with arbitrary degree cannot be verified".into());
+    }
+    Ok(p)
+}
+
+fn by_name(f : &HashMap<Label, Label>, from : &Problem, to : &Problem) -> Vec<(String, String)> {
+    let from_text : HashMap<_,_> = from.mapping_label_text.iter().cloned().collect();
+    let to_text : HashMap<_,_> = to.mapping_label_text.iter().cloned().collect();
+    f.iter().map(|(a, b)|(from_text[a].clone(), to_text[b].clone())).sorted().collect()
+}
+
+fn from_names(mapping : &[(String, String)], from : &Problem, to : &Problem) -> Result<HashMap<Label, Label>, String> {
+    let from_label : HashMap<_,_> = from.mapping_label_text.iter().map(|(l, s)|(s.clone(), *l)).collect();
+    let to_label : HashMap<_,_> = to.mapping_label_text.iter().map(|(l, s)|(s.clone(), *l)).collect();
+    let f : HashMap<_,_> = mapping.iter().map(|(a, b)|{
+        match (from_label.get(a), to_label.get(b)) {
+            (Some(&x), Some(&y)) => Ok((x, y)),
+            _ => Err(format!("The mapping {} -> {} uses unknown labels", a, b)),
+        }
+    }).collect::<Result<_, String>>()?;
+    if let Some(l) = from.labels().into_iter().find(|l|!f.contains_key(l)) {
+        return Err(format!("The mapping does not say how to replace the label {}", from_label.iter().find(|(_, &x)|x == l).unwrap().0));
+    }
+    Ok(f)
+}
+
+/// Round elimination applied to the problem, that is, two speedup steps, with maximized passive side.
+fn speedup_maximized(p : &Problem, eh : &mut EventHandler) -> Problem {
+    let mut r = p.speedup(eh).speedup(eh);
+    r.passive.maximize(eh);
+    r
+}
+
+/// For each label of `r = speedup_maximized(p)`, the labels of `p` that it represents.
+fn represented_labels(p : &Problem, r : &Problem, eh : &mut EventHandler) -> HashMap<Label, Vec<Label>> {
+    let half : HashMap<_,_> = p.speedup(eh).mapping_label_oldlabels.unwrap().into_iter().collect();
+    r.mapping_label_oldlabels.as_ref().unwrap().iter().map(|(l, sets)|{
+        (*l, sets.iter().flat_map(|s|half[s].iter().cloned()).unique().sorted().collect())
+    }).collect()
+}
+
+/// Checks that the problem is not 0-round solvable.
+fn check_nontrivial(p : &Problem, eh : &mut EventHandler) -> Result<(), String> {
+    let mut p = p.clone();
+    p.compute_triviality(eh);
+    if !p.trivial_sets.as_ref().unwrap().is_empty() {
+        return Err("The fixed point is 0-round solvable".into());
+    }
+    Ok(())
+}
+
+impl Problem {
+    /// Verifies that `claimed` is a non-trivial fixed point that is a relaxation of this problem, without relying on the fixed point procedure or on anything stored in the problems.
+    /// It applies round elimination to `claimed`, searches for label mappings showing that the result can be relaxed to `claimed` and that `claimed` is a relaxation of this problem, and checks that `claimed` is not 0-round solvable.
+    pub fn verify_fixpoint(&self, claimed : &Problem, eh : &mut EventHandler) -> Result<FixpointCertificate, String> {
+        let original = fresh(self)?;
+        let fixpoint = fresh(claimed)?;
+        if original.active.degree != fixpoint.active.degree || original.passive.degree != fixpoint.passive.degree {
+            return Err("The fixed point has different degrees than the problem".into());
+        }
+        check_nontrivial(&fixpoint, eh)?;
+
+        // labels with the same name are tried first
+        let fixpoint_label : HashMap<_,_> = fixpoint.mapping_label_text.iter().map(|(l, s)|(s.clone(), *l)).collect();
+        let preferred = original.mapping_label_text.iter().filter_map(|(l, s)|fixpoint_label.get(s).map(|&t|(*l, vec![t]))).collect();
+        eh.notify("searching relaxation", 0, 2);
+        let relaxation = find_relaxation(&original, &fixpoint, &preferred)?
+            .ok_or("The claimed fixed point is not a relaxation of the problem")?;
+
+        let speedup = speedup_maximized(&fixpoint, eh);
+        // a label of the speedup represents labels of the fixed point, and it is often replaced by one of them
+        let preferred = represented_labels(&fixpoint, &speedup, eh);
+        eh.notify("searching relaxation", 1, 2);
+        let fixpoint_relaxation = find_relaxation(&speedup, &fixpoint, &preferred)?
+            .ok_or("Round elimination applied to the claimed fixed point does not give a problem that can be relaxed to it")?;
+
+        Ok(FixpointCertificate {
+            relaxation : by_name(&relaxation, &original, &fixpoint),
+            fixpoint_relaxation : by_name(&fixpoint_relaxation, &speedup, &fixpoint),
+            original : original.to_string(),
+            fixpoint : fixpoint.to_string(),
+            speedup : speedup.to_string(),
+        })
+    }
+}
+
+impl FixpointCertificate {
+    /// Checks the certificate from scratch: round elimination is applied again to the fixed point, and the two mappings and the non-triviality are checked.
+    pub fn check(&self, eh : &mut EventHandler) -> Result<(), String> {
+        let original = Problem::from_string(&self.original)?;
+        let fixpoint = Problem::from_string(&self.fixpoint)?;
+        let fixpoint = fresh(&fixpoint)?;
+        check_nontrivial(&fixpoint, eh)?;
+
+        let relaxation = from_names(&self.relaxation, &original, &fixpoint)?;
+        if !is_relaxation(&original, &fixpoint, &relaxation) {
+            return Err("The fixed point is not a relaxation of the problem".into());
+        }
+
+        let speedup = speedup_maximized(&fixpoint, eh);
+        let fixpoint_relaxation = from_names(&self.fixpoint_relaxation, &speedup, &fixpoint)?;
+        if !is_relaxation(&speedup, &fixpoint, &fixpoint_relaxation) {
+            return Err("Round elimination applied to the fixed point does not give a problem that can be relaxed to it".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn verify_fixpoint() {
+        let eh = &mut EventHandler::null();
+        // every node has exactly one outgoing edge, a fixed point
+        let p = Problem::from_string("O I I\n\nI O").unwrap();
+        let certificate = p.verify_fixpoint(&p, eh).unwrap();
+        assert!(certificate.check(eh).is_ok());
+
+        let json = serde_json::to_string(&certificate).unwrap();
+        let mut again : super::FixpointCertificate = serde_json::from_str(&json).unwrap();
+        assert_eq!(again, certificate);
+        let first = again.fixpoint_relaxation[0].0.clone();
+        again.fixpoint_relaxation.retain(|(a, _)|a != &first);
+        assert!(again.check(eh).is_err());
+
+        let trivial = Problem::from_string("A A A\n\nA A").unwrap();
+        assert!(trivial.verify_fixpoint(&trivial, eh).is_err());
+
+        // maximal independent set is not a fixed point
+        let mis = Problem::from_string("M M M\nP U U\n\nM UP\nU U").unwrap();
+        assert!(mis.verify_fixpoint(&mis, eh).is_err());
+    }
+}
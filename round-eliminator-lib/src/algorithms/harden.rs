@@ -19,16 +19,18 @@ impl Problem {
         let mut keep = keep.clone();
 
         let mut newpassive = self.passive.clone();
+        let mut newother = self.other_degrees.clone();
         let mut newactive = if add_predecessors {
             let predecessors = self.diagram_indirect_to_inverse_reachability_adj();
-
-            self.active.edited(|g| {
+            let add = |c : &Constraint| c.edited(|g| {
                 let mut h = HashSet::new();
                 for label in g.iter() {
                     h.extend(predecessors[label].iter().cloned());
                 }
                 Group::from_set(&h)
-            })
+            });
+            newother = newother.map(|other|other.map_active(add));
+            add(&self.active)
         } else {
             self.active.clone()
         };
@@ -36,9 +38,10 @@ impl Problem {
         loop {
             newactive = newactive.harden(&keep);
             newpassive = newpassive.harden(&keep);
+            newother = newother.map(|other|other.map(|c|c.harden(&keep)));
 
-            let appearing_active = newactive.labels_appearing();
-            let appearing_passive = newpassive.labels_appearing();
+            let appearing_active : HashSet<Label> = newactive.labels_appearing().into_iter().chain(newother.iter().flat_map(|other|other.active.iter().flat_map(|c|c.labels_appearing()))).collect();
+            let appearing_passive : HashSet<Label> = newpassive.labels_appearing().into_iter().chain(newother.iter().flat_map(|other|other.passive.iter().flat_map(|c|c.labels_appearing()))).collect();
 
             let newkeep: HashSet<Label> = appearing_active
                 .intersection(&appearing_passive)
@@ -73,7 +76,11 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs : self.allowed_outputs_after_relabelling(|l|if keep.contains(&l) { vec![l] } else { vec![] }),
+            other_degrees : newother
         }
     }
 }
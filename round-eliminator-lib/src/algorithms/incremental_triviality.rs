@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use rustsat::{instances::SatInstance, types::{constraints::CardConstraint, Lit}};
+
+use crate::{group::Label, problem::Problem};
+
+use super::{event::EventHandler, problem_triviality::choice_lines, sat::{report_error, IncrementalSat}};
+
+/// Checks whether a fixed problem is zero-round solvable given many different inputs, all of them obtained by restricting a common universe input.
+/// The sat instance is built only once, for the universe.
+/// Each active and passive line of the universe is guarded by a literal, and a query assumes true exactly the literals of the lines of the given input,
+/// so that consecutive queries reuse the same solver.
+/// It is used by `compute_subinput_that_gives_nontriviality`, where the problem is fixed and the inputs change.
+/// The automatic bounds do not use it: they only check zero-round solvability with respect to the input attached to the problem, that is fixed while the problem changes,
+/// and each speedup introduces new labels, hence an instance built for one problem cannot be reused for the next ones.
+/// The critical sets do not use a sat solver.
+pub struct IncrementalTriviality {
+    active_index : HashMap<Vec<Label>, usize>,
+    passive_index : HashMap<Vec<Label>, usize>,
+    active_present : Vec<Lit>,
+    passive_present : Vec<Lit>,
+    /// For each active line of the universe, for each position, the label of the input, and the literals that say to which label of the problem it is mapped.
+    copies : Vec<Vec<(Label, Vec<Lit>)>>,
+    problem_labels : Vec<Label>,
+    sat : IncrementalSat,
+}
+
+impl IncrementalTriviality {
+    /// The universe contains the union of the lines of all the given inputs.
+    pub fn new(problem : &Problem, universe : &[Problem]) -> Result<Self, &'static str> {
+        let problem_labels = problem.labels();
+        let squish_labels : HashMap<_,_> = problem_labels.iter().enumerate().map(|(i,&l)|(l,i as Label)).collect();
+        let problem_active_lines = choice_lines(&problem.active, true, Some(&squish_labels));
+        let problem_passive_lines = choice_lines(&problem.passive, true, Some(&squish_labels));
+
+        let sorted_lines = |lines : Vec<Vec<Label>>| lines.into_iter().map(|line|line.into_iter().sorted().collect_vec());
+        let active_lines = universe.iter().flat_map(|input|sorted_lines(choice_lines(&input.active, false, None))).unique().collect_vec();
+        let passive_lines = universe.iter().flat_map(|input|sorted_lines(choice_lines(&input.passive, false, None))).unique().collect_vec();
+
+        let mut instance: SatInstance = SatInstance::new();
+
+        let active_present = active_lines.iter().map(|_|instance.new_lit()).collect_vec();
+        let passive_present = passive_lines.iter().map(|_|instance.new_lit()).collect_vec();
+
+        // each occurrence of a label in an active line is a different copy of the label, as in `make_all_labels_different`
+        let copies : Vec<Vec<(Label, Vec<Lit>)>> = active_lines.iter().map(|line|{
+            line.iter().map(|&l|{
+                let lits = (0..problem_labels.len()).map(|_|instance.new_lit()).collect_vec();
+                instance.add_card_constr(CardConstraint::new_eq(lits.iter().cloned(),1));
+                (l, lits)
+            }).collect()
+        }).collect();
+
+        for (i,line) in active_lines.iter().enumerate() {
+            let mut clause = vec![!active_present[i]];
+            for problem_line in problem_active_lines.iter().filter(|pl|pl.len() == line.len()) {
+                let mapped = instance.new_lit();
+                let cube = (0..line.len()).map(|k|copies[i][k].1[problem_line[k] as usize]).collect_vec();
+                instance.add_lit_impl_cube(mapped, &cube);
+                clause.push(mapped);
+            }
+            instance.add_clause(clause.into_iter().collect());
+        }
+
+        let mut copies_of_label : HashMap<Label, Vec<(usize,usize)>> = HashMap::new();
+        for (i,line) in active_lines.iter().enumerate() {
+            for (k,&l) in line.iter().enumerate() {
+                copies_of_label.entry(l).or_default().push((i,k));
+            }
+        }
+
+        // a passive line over copies needs to be mapped only if the passive line and all the active lines containing the copies are present
+        for (q,line) in passive_lines.iter().enumerate() {
+            if line.iter().any(|l|!copies_of_label.contains_key(l)) {
+                continue;
+            }
+            let combinations = line.iter().map(|l|copies_of_label[l].iter().cloned()).multi_cartesian_product()
+                .map(|combination|combination.into_iter().sorted().collect_vec())
+                .unique();
+            for combination in combinations {
+                let mut clause = vec![!passive_present[q]];
+                clause.extend(combination.iter().map(|&(i,_)|!active_present[i]).unique());
+                for problem_line in problem_passive_lines.iter().filter(|pl|pl.len() == line.len()) {
+                    let mapped = instance.new_lit();
+                    let cube = combination.iter().zip(problem_line.iter()).map(|(&(i,k),&o)|copies[i][k].1[o as usize]).collect_vec();
+                    instance.add_lit_impl_cube(mapped, &cube);
+                    clause.push(mapped);
+                }
+                instance.add_clause(clause.into_iter().collect());
+            }
+        }
+
+        let active_index = active_lines.into_iter().enumerate().map(|(i,line)|(line,i)).collect();
+        let passive_index = passive_lines.into_iter().enumerate().map(|(i,line)|(line,i)).collect();
+
+        Ok(Self {
+            active_index,
+            passive_index,
+            active_present,
+            passive_present,
+            copies,
+            problem_labels,
+            sat : IncrementalSat::new(instance.sanitize()).map_err(report_error)?,
+        })
+    }
+
+    /// Returns None if the input is not contained in the universe.
+    /// Otherwise, it returns whether the problem is zero-round solvable given the input, and if so, a mapping from input labels to problem labels.
+    /// An error is returned if the sat solver fails.
+    pub fn query(&mut self, input : &Problem) -> Result<Option<Option<Vec<(Label, Vec<Label>)>>>, &'static str> {
+        let active_labels = input.active.labels_appearing();
+        if input.passive.labels_appearing().iter().any(|l|!active_labels.contains(l)) {
+            return Ok(None);
+        }
+
+        let mut active = HashSet::new();
+        for line in choice_lines(&input.active, false, None) {
+            match self.active_index.get(&line.into_iter().sorted().collect_vec()) {
+                Some(&i) => { active.insert(i); },
+                None => { return Ok(None); }
+            }
+        }
+        let mut passive = HashSet::new();
+        for line in choice_lines(&input.passive, false, None) {
+            match self.passive_index.get(&line.into_iter().sorted().collect_vec()) {
+                Some(&q) => { passive.insert(q); },
+                None => { return Ok(None); }
+            }
+        }
+
+        let assumptions = self.active_present.iter().enumerate().map(|(i,&lit)|if active.contains(&i) { lit } else { !lit })
+            .chain(self.passive_present.iter().enumerate().map(|(q,&lit)|if passive.contains(&q) { lit } else { !lit }))
+            .collect_vec();
+        let lits = active.iter().flat_map(|&i|self.copies[i].iter().flat_map(|(_,lits)|lits.iter().cloned())).collect_vec();
+
+        let solution = match self.sat.solve(&assumptions, &lits, &mut EventHandler::null()).map_err(report_error)? {
+            Some(solution) => solution,
+            None => { return Ok(Some(None)); }
+        };
+        let true_lits : HashSet<_> = solution.into_iter().collect();
+
+        let mut mapping : HashMap<Label, HashSet<Label>> = HashMap::new();
+        for &i in &active {
+            for (l,lits) in &self.copies[i] {
+                for (j,lit) in lits.iter().enumerate() {
+                    if true_lits.contains(lit) {
+                        mapping.entry(*l).or_default().insert(self.problem_labels[j]);
+                    }
+                }
+            }
+        }
+        Ok(Some(Some(mapping.into_iter().map(|(l,h)|(l,h.into_iter().sorted().collect())).collect())))
+    }
+}
+
+impl Problem {
+    /// Same as `compute_triviality_with_input` with a sat solver, but it first tries to answer by using `incremental`, which must have been created for this problem.
+    pub fn compute_triviality_with_input_incremental(&mut self, input : Problem, incremental : &mut IncrementalTriviality) -> Result<(), &'static str> {
+        if input.labels().is_empty() {
+            return self.compute_triviality_with_input(input, true);
+        }
+        match incremental.query(&input)? {
+            Some(Some(mapping)) => {
+                self.is_trivial_with_input = Some(true);
+                self.triviality_with_input = Some((input.mapping_label_text.clone(), mapping));
+            }
+            Some(None) => {
+                self.is_trivial_with_input = Some(false);
+            }
+            None => {
+                self.compute_triviality_with_input_with_sat(input)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::IncrementalTriviality;
+
+    #[test]
+    fn incremental_triviality() {
+        let p = Problem::from_string("M M M\nP U U\n\nM UP\nU U").unwrap();
+        let input = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        let mut incremental = IncrementalTriviality::new(&p, &[input.clone()]).unwrap();
+
+        let mut inputs = vec![input.clone()];
+        let labels = input.labels();
+        for &l in &labels {
+            let keep : HashSet<_> = labels.iter().cloned().filter(|&x|x != l).collect();
+            let mut sub = input.harden_keep(&keep, false);
+            sub.discard_useless_stuff(false, &mut EventHandler::null());
+            inputs.push(sub);
+        }
+
+        for input in inputs {
+            let mut fresh = p.clone();
+            fresh.compute_triviality_with_input_with_sat(input.clone()).unwrap();
+            let mut cached = p.clone();
+            cached.compute_triviality_with_input_incremental(input.clone(), &mut incremental).unwrap();
+            assert_eq!(fresh.is_trivial_with_input, cached.is_trivial_with_input);
+        }
+    }
+}
@@ -8,7 +8,8 @@ use crate::{
 };
 
 impl Problem {
-    pub fn inverse_speedup(&self) -> Self {
+    pub fn inverse_speedup(&self) -> Result<Self, &'static str> {
+        self.require_regular()?;
         let mut passive = Constraint {
             lines: vec![],
             is_maximized: false,
@@ -57,7 +58,9 @@ impl Problem {
             )
         });
 
-        let mapping_oldlabel_labels = mapping_oldlabel_labels.into_iter().sorted().collect();
+        let mapping_oldlabel_labels : Vec<(Label, Vec<Label>)> = mapping_oldlabel_labels.into_iter().sorted().collect();
+
+        let input_allowed_outputs = self.allowed_outputs_after_relabelling(|l|mapping_oldlabel_labels.iter().filter(|(o, _)|*o == l).flat_map(|(_, v)|v.iter().cloned()).collect());
 
         let mut p = Problem {
             active,
@@ -81,9 +84,13 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.as_ref().map(|input|Box::new(input.swap_sides())),
+            input_allowed_outputs,
+            other_degrees : None
         };
         p.assign_chars();
-        p
+        Ok(p)
     }
 }
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::{group::{GroupType, Label}, line::Line, part::Part, problem::Problem, serial::AutoOperation};
+
+use super::diagram_export::Layout;
+
+/// Pixels of the layout of diagrams for each centimeter of the TikZ picture.
+const PIXELS_PER_CM : f64 = 40.0;
+
+/// A label name as LaTeX: labels written as `(...)` lose the parentheses, and characters that are special in LaTeX are escaped.
+pub fn label_to_latex(name : &str) -> String {
+    let name = name.strip_prefix('(').and_then(|n|n.strip_suffix(')')).unwrap_or(name);
+    let escaped : String = name.chars().map(|c|match c {
+        '_' | '&' | '%' | '$' | '#' | '{' | '}' => format!("\\{}", c),
+        '^' => "\\string^".into(),
+        '~' => "\\string~".into(),
+        '\\' => "\\backslash{}".into(),
+        c => c.to_string(),
+    }).collect();
+    format!("\\mathsf{{{}}}", escaped)
+}
+
+fn labels_to_latex(labels : &[Label], mapping : &HashMap<Label, String>) -> String {
+    labels.iter().map(|l|label_to_latex(&mapping[l])).join(", ")
+}
+
+/// Groups with a single label are written as the label, larger groups in set notation.
+fn part_to_latex(part : &Part, mapping : &HashMap<Label, String>) -> String {
+    let group = if part.group.len() == 1 {
+        label_to_latex(&mapping[&part.group.first()])
+    } else {
+        format!("\\{{{}\\}}", part.group.iter().map(|l|label_to_latex(&mapping[l])).join(", "))
+    };
+    match part.gtype {
+        GroupType::ONE => group,
+        GroupType::Many(n) => format!("{}^{{{}}}", group, n),
+        GroupType::Star => format!("{}^{{*}}", group),
+    }
+}
+
+pub fn line_to_latex(line : &Line, mapping : &HashMap<Label, String>) -> String {
+    line.parts.iter().map(|p|part_to_latex(p, mapping)).join("\\ ")
+}
+
+impl Problem {
+    /// The active and the passive constraints (of all degrees) side by side, as a tabular environment.
+    pub fn to_latex(&self) -> String {
+        let mapping : HashMap<Label, String> = self.mapping_label_text.iter().cloned().collect();
+        let active = self.active_constraints().into_iter().flat_map(|c|c.lines.iter()).map(|l|line_to_latex(l, &mapping)).collect_vec();
+        let passive = self.passive_constraints().into_iter().flat_map(|c|c.lines.iter()).map(|l|line_to_latex(l, &mapping)).collect_vec();
+
+        let mut s = String::new();
+        writeln!(s, "\\begin{{tabular}}{{l|l}}").unwrap();
+        writeln!(s, "Active & Passive \\\\ \\hline").unwrap();
+        for i in 0..active.len().max(passive.len()) {
+            let cell = |v : &Vec<String>| v.get(i).map(|l|format!("${}$", l)).unwrap_or_default();
+            writeln!(s, "{} & {} \\\\", cell(&active), cell(&passive)).unwrap();
+        }
+        writeln!(s, "\\end{{tabular}}").unwrap();
+        s
+    }
+
+    /// The diagram as a TikZ picture, with the same layout and highlighting used by `diagram_to_svg`.
+    pub fn diagram_to_tikz(&self) -> Result<String, &'static str> {
+        let drawing = self.diagram_drawing()?;
+        let Layout { center, edges, .. } = drawing.layout();
+        let coord = |(x, y) : (usize, usize)| (x as f64 / PIXELS_PER_CM, -(y as f64) / PIXELS_PER_CM);
+
+        let mut s = String::new();
+        writeln!(s, "\\begin{{tikzpicture}}[diagram node/.style={{draw, rounded corners}}]").unwrap();
+        for (i, (label, names, highlight)) in drawing.nodes.iter().enumerate() {
+            let (x, y) = coord(center[i]);
+            let fill = highlight.map(|h|format!(", fill={}", h.tikz_color())).unwrap_or_default();
+            let text = names.iter().map(|n|label_to_latex(n)).join("\\ ");
+            writeln!(s, "\\node[diagram node{}] (n{}) at ({:.2}, {:.2}) {{${}$}};", fill, label, x, y, text).unwrap();
+        }
+        for (a, b) in edges {
+            writeln!(s, "\\draw[->] (n{}) -- (n{});", drawing.nodes[a].0, drawing.nodes[b].0).unwrap();
+        }
+        writeln!(s, "\\end{{tikzpicture}}").unwrap();
+        Ok(s)
+    }
+}
+
+/// A sequence of problems obtained by `autoautolb`, `autoautoub` or the logstar search, as a numbered derivation.
+/// Each step describes the operation in terms of the labels of the problem it is applied to, and then shows the obtained problem.
+pub fn sequence_to_latex(sequence : &[(AutoOperation, Problem)]) -> String {
+    let mut s = String::new();
+    writeln!(s, "\\begin{{enumerate}}").unwrap();
+    let mut previous : Option<&Problem> = None;
+    for (operation, problem) in sequence {
+        let text = |p : &Problem| p.mapping_label_text.iter().cloned().collect::<HashMap<_,_>>();
+        let description = match operation {
+            AutoOperation::Initial => "The initial problem is the following.".to_owned(),
+            AutoOperation::Speedup => "Applying round elimination gives the following problem.".to_owned(),
+            AutoOperation::Harden(kept) => {
+                match previous {
+                    Some(p) => format!("Hardening by keeping only the labels ${}$ gives the following problem.", labels_to_latex(kept, &text(p))),
+                    None => "Hardening gives the following problem.".to_owned(),
+                }
+            }
+            AutoOperation::Merge(merges, before) => {
+                let mapping = text(before);
+                let merges = merges.iter().map(|(from, to)|format!("${}$ with ${}$", label_to_latex(&mapping[from]), label_to_latex(&mapping[to]))).join(", ");
+                format!("Relaxing by replacing {} gives the following problem.", merges)
+            }
+            AutoOperation::LogstarMIS(labels, before) => {
+                format!("Computing a maximal independent set among the nodes that use ${}$ gives the following problem.", labels_to_latex(labels, &text(before)))
+            }
+            AutoOperation::LogstarDup(labels, before) => {
+                format!("Splitting ${}$ into copies and merging the copies into two groups gives the following problem.", labels_to_latex(labels, &text(before)))
+            }
+            AutoOperation::LogstarSee(labels, before) => {
+                format!("Letting nodes see which neighbors use ${}$ gives the following problem.", labels_to_latex(labels, &text(before)))
+            }
+        };
+        writeln!(s, "\\item {}\n\n{}", description, problem.to_latex()).unwrap();
+        previous = Some(problem);
+    }
+    writeln!(s, "\\end{{enumerate}}").unwrap();
+    s
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::AutoOperation};
+
+    #[test]
+    fn latex() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2\n").unwrap();
+        let latex = p.to_latex();
+        assert!(latex.contains("Active & Passive"));
+        assert!(latex.contains("\\mathsf{U}^{2}"));
+        assert!(latex.contains("\\mathsf{P}^{3}"));
+        assert_eq!(super::label_to_latex("(0_a)"), "\\mathsf{0\\_a}");
+
+        assert!(p.diagram_to_tikz().is_err());
+        p.compute_diagram(eh);
+        let tikz = p.diagram_to_tikz().unwrap();
+        assert_eq!(tikz.matches("\\node").count(), 3);
+        assert_eq!(tikz.matches("\\draw").count(), 1);
+
+        let sp = p.speedup(eh);
+        let sequence = vec![(AutoOperation::Initial, p.clone()), (AutoOperation::Speedup, sp)];
+        let latex = super::sequence_to_latex(&sequence);
+        assert_eq!(latex.matches("\\item").count(), 2);
+        assert_eq!(latex.matches("\\begin{tabular}").count(), 2);
+    }
+}
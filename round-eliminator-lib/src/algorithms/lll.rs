@@ -1,32 +1,110 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
-use crate::{constraint::Constraint, group::{Group, GroupType, Label}, line::Line, part::Part, problem::Problem};
+use crate::{constraint::Constraint, group::{Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use super::event::EventHandler;
 
 
+/// Randomized upper bounds that can be obtained from a zero-round randomized algorithm with small local failure probability.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum RandomizedBound {
+    /// p·2^d < 1, the LLL instance can be solved in O(log* n) rounds (Brandt, Grunau, Rozhoň).
+    LogStar,
+    /// e·p·(d+1) < 1, the LLL instance can be solved with the distributed LLL, conjectured to require O(log log n) rounds.
+    LogLog,
+}
+
+impl RandomizedBound {
+    pub fn from_criteria(failure_probability : f64, dependency_degree : usize) -> Option<Self> {
+        let p = failure_probability;
+        let d = dependency_degree as f64;
+        if p * 2f64.powf(d) < 1.0 {
+            Some(RandomizedBound::LogStar)
+        } else if std::f64::consts::E * p * (d + 1.0) < 1.0 {
+            Some(RandomizedBound::LogLog)
+        } else {
+            None
+        }
+    }
+}
 
-
+impl Display for RandomizedBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RandomizedBound::LogStar => write!(f, "O(log* n)"),
+            RandomizedBound::LogLog => write!(f, "O(log log n) (conjectured)"),
+        }
+    }
+}
+
+/// Certifies that a problem can be solved with randomization:
+/// after `steps` speedup steps, a zero-round randomized algorithm that, on each active node, picks uniformly at random a line of `algorithm` and a uniformly random assignment of it to the ports,
+/// fails on each passive node with probability `failure_probability`, and this satisfies an LLL criterion.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RandomizedCertificate {
+    pub steps : usize,
+    pub failure_probability : f64,
+    pub dependency_degree : usize,
+    pub algorithm : Problem,
+    pub bound : RandomizedBound,
+}
+
+impl Display for RandomizedCertificate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let p = self.failure_probability;
+        let d = self.dependency_degree;
+        match self.bound {
+            RandomizedBound::LogStar => writeln!(f, "The problem is solvable in {} + {} rounds randomized.", self.steps, self.bound)?,
+            // the criterion only gives an LLL instance, the O(log log n) bound for it is a conjecture
+            RandomizedBound::LogLog => writeln!(f, "The problem is solvable in {} rounds plus the rounds of the distributed LLL randomized, conjectured to be O(log log n).", self.steps)?,
+        }
+        writeln!(f, "After {} speedup steps, picking uniformly at random one of the following configurations fails on a passive node with probability {}.", self.steps, p)?;
+        let mapping = self.algorithm.mapping_label_text.iter().cloned().collect();
+        for line in self.algorithm.active.all_choices(true) {
+            writeln!(f, "{}", line.to_string(&mapping))?;
+        }
+        match self.bound {
+            RandomizedBound::LogStar => writeln!(f, "The dependency degree is {}, and p·2^d = {} < 1.", d, p * 2f64.powf(d as f64)),
+            RandomizedBound::LogLog => writeln!(f, "The dependency degree is {}, and p·e·(d+1) = {} < 1.", d, p * std::f64::consts::E * (d as f64 + 1.0)),
+        }
+    }
+}
 
 impl Problem {
 
-    pub fn randomized_success_prob(&mut self, eh : &mut EventHandler) {
-        self.passive.maximize(eh);
+    /// Each bad event is a passive node, and it depends on the choices of its active neighbors, each of which has other active_degree-1 passive neighbors.
+    pub fn dependency_degree(&self) -> Option<usize> {
+        match (self.active.degree, self.passive.degree) {
+            (Degree::Finite(d), Degree::Finite(p)) => Some(p * (d - 1)),
+            _ => None
+        }
+    }
 
-        let outputs = self.active.all_choices(true);
+    /// Computes the smallest local failure probability of zero-round randomized algorithms that pick uniformly at random a line among the ones using only the labels of at most `max_lines` active lines.
+    /// Returns the failure probability and the corresponding restriction of the problem.
+    pub fn best_zero_round_randomized(&self, max_lines : usize, eh : &mut EventHandler) -> Result<Option<(f64,Problem)>, &'static str> {
+        self.require_regular()?;
+        if self.active.degree == Degree::Star || self.passive.degree == Degree::Star {
+            return Ok(None);
+        }
+        let mut problem = self.clone();
+        if !problem.passive.is_maximized {
+            problem.passive.maximize(eh);
+        }
+
+        let outputs = problem.active.all_choices(true);
 
-        let mut best_prob = 0f32;
-        let mut best_subproblem = self.clone();
+        let mut best : Option<(f64,Problem)> = None;
 
-        //let sets = outputs.into_iter().powerset();
-        let sets = (1..=4).flat_map(|size|outputs.iter().cloned().combinations(size));
+        let sets = (1..=max_lines).flat_map(|size|outputs.iter().cloned().combinations(size));
 
         for lines in sets {
-            let c = Constraint{ lines, is_maximized: false, degree: self.active.degree  };
+            let c = Constraint{ lines, is_maximized: false, degree: problem.active.degree  };
             let labels = c.labels_appearing();
-            let problem = self.harden_keep(&labels, false);
-            let lines = problem.active.all_choices(true);
+            let subproblem = problem.harden_keep(&labels, false);
+            let lines = subproblem.active.all_choices(true);
 
             if lines.is_empty() {
                 continue;
@@ -41,45 +119,86 @@ impl Problem {
                 }
             }
 
-            let mut good = 0;
-            let mut bad = 0;
+            let mut good = 0u128;
+            let mut bad = 0u128;
 
-            for passive in (0..self.passive.finite_degree()).map(|_|freq.iter()).multi_cartesian_product() {
+            for passive in (0..problem.passive.finite_degree()).map(|_|freq.iter()).multi_cartesian_product() {
                 let parts : Vec<_> = passive.iter().map(|lc|
                     Part {
                         group : Group::from(vec![*lc.0]),
                         gtype : GroupType::Many(1)
                     }).collect();
                 let line = Line{ parts };
-                let count = passive.iter().fold(1,|a,lc|a * lc.1);
-                if self.passive.includes(&line) {
+                let count = passive.iter().fold(1u128,|a,lc|a * *lc.1 as u128);
+                if problem.passive.includes(&line) {
                     good += count;
                 } else {
                     bad += count;
                 }
-                
             }
 
-            let prob = good as f32 / (good + bad) as f32;
-            if prob > best_prob {
-                best_prob = prob;
-                best_subproblem = problem;
-
-                let e = std::f64::consts::E;
-                let p = 1f32 - best_prob;
-                let d = (self.active.finite_degree() -1) * self.passive.finite_degree();
-                println!("epd {}",  e as f32 * p * d as f32);
-                //println!("best prob {}\n{}\n",best_prob,best_subproblem);    
+            let failure = bad as f64 / (good + bad) as f64;
+            if best.as_ref().map_or(true, |(p,_)| failure < *p) {
+                best = Some((failure, subproblem));
             }
-
         }
 
-        let e = std::f64::consts::E;
-        let p = 1f32 - best_prob;
-        let d = (self.active.finite_degree() -1) * self.passive.finite_degree();
-        println!("epd {}",  e as f32 * p * d as f32);
-        //println!("best prob {}\n{}\n",best_prob,best_subproblem);
+        Ok(best)
+    }
+
+    /// Applies speedup up to `max_steps` times, and for each obtained problem checks whether the best zero-round randomized algorithm satisfies an LLL criterion.
+    /// Returns the certificate with the smallest number of steps, preferring O(log* n) over O(log log n) for the same number of steps.
+    pub fn randomized_upper_bound(&self, max_steps : usize, max_lines : usize, eh : &mut EventHandler) -> Result<Option<RandomizedCertificate>, &'static str> {
+        self.require_regular()?;
+        let dependency_degree = match self.dependency_degree() {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+        let mut p = self.clone();
+
+        for steps in 0..=max_steps {
+            eh.notify("randomized zero round", steps, max_steps);
+            if let Some((failure_probability, algorithm)) = p.best_zero_round_randomized(max_lines, eh)? {
+                if let Some(bound) = RandomizedBound::from_criteria(failure_probability, dependency_degree) {
+                    return Ok(Some(RandomizedCertificate { steps, failure_probability, dependency_degree, algorithm, bound }));
+                }
+            }
+            if steps == max_steps {
+                break;
+            }
+            p = p.speedup(eh);
+            p.discard_useless_stuff(false, eh);
+            p.sort_active_by_strength();
+        }
 
+        Ok(None)
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::{event::EventHandler, lll::{RandomizedBound, RandomizedCertificate}}, problem::Problem};
+
+    #[test]
+    fn randomized() {
+        let eh = &mut EventHandler::null();
+
+        // every configuration is allowed, so picking randomly never fails
+        let p = Problem::from_string("A A A\n\nA A").unwrap();
+        let (failure, _) = p.best_zero_round_randomized(4, eh).unwrap().unwrap();
+        assert_eq!(failure, 0.0);
+
+        let p = Problem::from_string("A A A\nB B B\n\nA B").unwrap();
+        let (failure, _) = p.best_zero_round_randomized(4, eh).unwrap().unwrap();
+        assert!(failure > 0.0);
+
+        assert_eq!(RandomizedBound::from_criteria(0.0, 4), Some(RandomizedBound::LogStar));
+        assert_eq!(RandomizedBound::from_criteria(0.01, 10), Some(RandomizedBound::LogLog));
+        assert_eq!(RandomizedBound::from_criteria(0.5, 4), None);
+
+        let certificate = RandomizedCertificate { steps : 1, failure_probability : 0.01, dependency_degree : 10, algorithm : p, bound : RandomizedBound::LogLog };
+        assert!(certificate.to_string().starts_with("The problem is solvable in 1 rounds plus the rounds of the distributed LLL randomized, conjectured to be O(log log n).\n"));
+    }
+}
@@ -10,23 +10,36 @@ use crate::{algorithms::event::EventHandler, constraint::Constraint, group::{Gro
 
 
 impl Problem {
-    pub fn logstar_dup(&self, labels : &Vec<Label>) -> (Self,HashMap<Label, Vec<Label>>) {
+    pub fn logstar_dup(&self, labels : &Vec<Label>) -> Result<(Self,HashMap<Label, Vec<Label>>), &'static str> {
+        self.require_regular()?;
         let (mut p,map) = self.make_some_labels_different(labels, true);
         p.discard_useless_stuff(false, &mut EventHandler::null());
-        (p,map)
+        Ok((p,map))
     }
 
-    pub fn logstar_see_one(&self, label : Label) -> Self {
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("only passive side 2 supported");
+    /// Splits `label` into one copy for each configuration of labels that the other nodes of a passive node may have,
+    /// so that a node using `label` also tells which labels it sees on the other side.
+    pub fn logstar_see_one(&self, label : Label) -> Result<Self, &'static str> {
+        self.require_regular()?;
+        if self.passive.degree == Degree::Star {
+            return Err("only finite passive degree supported");
         }
-        let compatible_with_l = self.labels_compatible_with_label(label);
-        if compatible_with_l.is_empty() {
+        let choices = self.passive.all_choices(false);
+        // the labels of the other nodes, for passive configurations that contain `label`
+        let rest_of = |line : &Line| -> Option<Vec<Label>> {
+            let mut labels = line.groups().map(|g|g.first()).collect_vec();
+            let pos = labels.iter().position(|&x|x == label)?;
+            labels.remove(pos);
+            labels.sort_unstable();
+            Some(labels)
+        };
+        let rests = choices.iter().filter_map(rest_of).unique().sorted().collect_vec();
+        if rests.is_empty() {
             return self.clone();
         }
         let fresh_label = self.labels().into_iter().max().unwrap_or(0) + 1;
-        let new_ls = (fresh_label..(fresh_label+compatible_with_l.len() as Label)).collect_vec();
-        let other_to_newl : HashMap<_,_> = compatible_with_l.iter().cloned().zip(new_ls.iter().cloned()).collect();
+        let new_ls = (fresh_label..(fresh_label+rests.len() as Label)).collect_vec();
+        let rest_to_newl : HashMap<_,_> = rests.iter().cloned().zip(new_ls.iter().cloned()).collect();
 
         let active = self.active.edited(|g|{
             if !g.contains(&label) {
@@ -45,38 +58,27 @@ impl Problem {
             degree: self.passive.degree,
         };
 
-        for line in self.passive.all_choices(false) {
-            let l1 = line.parts[0].group.first();
-            let l2 = line.parts[1].group.first();
-            if l1 != label && l2 != label {
-                passive.lines.push(line);
-            } else if l1 == label && l2 == label {
-                let newline = Line {
-                    parts : vec![
-                        Part{ group : Group::from(vec![other_to_newl[&label]]), gtype : GroupType::Many(1) },
-                        Part{ group : Group::from(vec![other_to_newl[&label]]), gtype : GroupType::Many(1) }
-                    ]
-                };
-                passive.lines.push(newline);
-            } else {
-                let other = if l1 != label { l1 } else { l2 };
-                let newline = Line {
-                    parts : vec![
-                        Part{ group : Group::from(vec![other_to_newl[&other]]), gtype : GroupType::Many(1) },
-                        Part{ group : Group::from(vec![other]), gtype : GroupType::Many(1) }
-                    ]
-                };
-                passive.lines.push(newline);
+        for line in &choices {
+            match rest_of(line) {
+                None => {
+                    passive.lines.push(line.clone());
+                }
+                Some(rest) => {
+                    let newl = rest_to_newl[&rest];
+                    let mut newline = line.edited(|g|if g.first() == label { Group::from(vec![newl]) } else { g.clone() });
+                    newline.normalize();
+                    passive.lines.push(newline);
+                }
             }
-        } 
-        
+        }
+
         let old_mapping : HashMap<_,_> = self.mapping_label_text.iter().cloned().collect();
         let mut mapping_label_text : Vec<_> = self.mapping_label_text.iter().cloned().filter(|(l,_)|*l != label).collect();
         let old_name = old_mapping[&label].replace("(", "").replace(")", "");
-        for other in compatible_with_l.iter() {
-            let newother = other_to_newl[other];
-            let other_name = old_mapping[&other].replace("(", "").replace(")", "");
-            mapping_label_text.push((newother,format!("({}_{})",old_name,other_name)));
+        for rest in rests.iter() {
+            let newl = rest_to_newl[rest];
+            let rest_name = rest.iter().map(|other|old_mapping[other].replace("(", "").replace(")", "")).join("");
+            mapping_label_text.push((newl,format!("({}_{})",old_name,rest_name)));
         }
 
         let mut p = Problem {             
@@ -101,23 +103,31 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees : None
         };
         p.discard_useless_stuff(false, &mut EventHandler::null());
-        p
+        Ok(p)
     }
 
-    pub fn logstar_see(&self, labels : &Vec<Label>) -> Self {
+    pub fn logstar_see(&self, labels : &Vec<Label>) -> Result<Self, &'static str> {
         let mut p = self.clone();
         for &l in labels {
-            p = p.logstar_see_one(l);
+            p = p.logstar_see_one(l)?;
         }
-        p
+        Ok(p)
     }
 
-    pub fn logstar_mis(&self, labels : &Vec<Label>) -> Self {
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("only passive side 2 supported");
+    /// Computes a maximal independent set among the nodes that use the given labels, where two nodes are neighbors if they are connected through a passive node that they reach with those labels.
+    /// The labels are merged into one label, that is split into M (in the set), P (points to a passive node with a node in the set), and U (unmatched).
+    /// A passive node may contain at most one M, and P only if it contains M.
+    pub fn logstar_mis(&self, labels : &Vec<Label>) -> Result<Self, &'static str> {
+        self.require_regular()?;
+        if self.passive.degree == Degree::Star {
+            return Err("only finite passive degree supported");
         }
 
         let l = labels[0];
@@ -159,34 +169,26 @@ impl Problem {
         };
 
         for line in p.passive.all_choices(false) {
-            let l1 = line.parts[0].group.first();
-            let l2 = line.parts[1].group.first();
-            if l1 != l && l2 != l {
+            let count = line.groups().filter(|g|g.first() == l).count();
+            if count == 0 {
                 passive.lines.push(line);
-            } else if l1 == l && l2 == l {
-                let newline = Line {
-                    parts : vec![
-                        Part{ group : Group::from(vec![label_m]), gtype : GroupType::Many(1) },
-                        Part{ group : Group::from(vec![label_u,label_p]), gtype : GroupType::Many(1) }
-                    ]
-                };
-                passive.lines.push(newline);
-                let newline = Line {
-                    parts : vec![
-                        Part{ group : Group::from(vec![label_u]), gtype : GroupType::Many(1) },
-                        Part{ group : Group::from(vec![label_u]), gtype : GroupType::Many(1) }
-                    ]
+                continue;
+            }
+            let rest = line.parts.iter().filter(|part|part.group.first() != l).cloned().collect_vec();
+            let with = |groups : Vec<Vec<Label>>| {
+                let mut newline = Line {
+                    parts : groups.into_iter().map(|g|Part{ group : Group::from(g), gtype : GroupType::Many(1) }).chain(rest.iter().cloned()).collect()
                 };
-                passive.lines.push(newline);
+                newline.normalize();
+                newline
+            };
+            if count == 1 {
+                passive.lines.push(with(vec![vec![label_m,label_u]]));
             } else {
-                let other = if l1 != l { l1 } else { l2 };
-                let newline = Line {
-                    parts : vec![
-                        Part{ group : Group::from(vec![label_m,label_u]), gtype : GroupType::Many(1) },
-                        Part{ group : Group::from(vec![other]), gtype : GroupType::Many(1) }
-                    ]
-                };
-                passive.lines.push(newline);
+                let mut one_m = vec![vec![label_m]];
+                one_m.extend(std::iter::repeat(vec![label_u,label_p]).take(count-1));
+                passive.lines.push(with(one_m));
+                passive.lines.push(with(vec![vec![label_u]; count]));
             }
         } 
 
@@ -222,13 +224,18 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees : None
         };
         p.discard_useless_stuff(false, &mut EventHandler::null());
-        p
+        Ok(p)
     }
 
-    pub fn autologstar(&mut self, max_labels : usize, mut max_depth : usize, initial_active : String, initial_passive : String, max_active : usize, max_passive : usize, onlybool : bool, eh: &mut EventHandler) -> Option<(usize, Vec<(AutoOperation,Problem)>)> {
+    pub fn autologstar(&mut self, max_labels : usize, mut max_depth : usize, initial_active : String, initial_passive : String, max_active : usize, max_passive : usize, onlybool : bool, eh: &mut EventHandler) -> Result<Option<(usize, Vec<(AutoOperation,Problem)>)>, &'static str> {
+        self.require_regular()?;
 
         #[cfg(target_arch = "wasm32")]
         {
@@ -241,25 +248,28 @@ impl Problem {
             use rayon::iter::{IntoParallelIterator, ParallelIterator};
             let done = Arc::new(AtomicBool::new(false));
             let threads = rayon::current_num_threads();
-            let result : Vec<_> = (0..threads).into_par_iter().map(|_|{
+            let result = (0..threads).into_par_iter().map(|_|{
                 let done = done.clone();
                 let r = self.clone().autologstar_aux(max_labels,max_depth,initial_active.clone(),initial_passive.clone(),max_active,max_passive,onlybool,&mut EventHandler::null(), Some(done));
                 r
             })
-                .filter_map(std::convert::identity)
-                .collect::<_>();
-            result.into_iter().next()
+                .filter_map(|r|r.transpose())
+                .collect::<Result<Vec<_>,&'static str>>()?;
+            Ok(result.into_iter().next())
         }
 
     }
 
 
-    pub fn autologstar_aux(&mut self, max_labels : usize, mut max_depth : usize, initial_active : String, initial_passive : String, max_active : usize, max_passive : usize, onlybool : bool, eh: &mut EventHandler, done : Option<Arc<AtomicBool>>) -> Option<(usize, Vec<(AutoOperation,Problem)>)> {
+    pub fn autologstar_aux(&mut self, max_labels : usize, mut max_depth : usize, initial_active : String, initial_passive : String, max_active : usize, max_passive : usize, onlybool : bool, eh: &mut EventHandler, done : Option<Arc<AtomicBool>>) -> Result<Option<(usize, Vec<(AutoOperation,Problem)>)>, &'static str> {
+        self.require_regular()?;
         let d = self.active.finite_degree();
         let initial = if initial_active == "" {
-            Problem::from_string(format!("M^{}\nP U^{}\n\nM UP\nU U",d,d-1)).unwrap()
+            // maximal independent set, on hypergraphs at most one node of each hyperedge is in the set
+            let pd = self.passive.finite_degree();
+            Problem::from_string(format!("M^{}\nP U^{}\n\nM UP^{}\nU^{}",d,d-1,pd-1,pd)).unwrap()
         } else {
-            Problem::from_string_active_passive(initial_active,initial_passive).unwrap().0
+            Problem::from_string_active_passive(initial_active,initial_passive)?.0
         };
         
         if onlybool {
@@ -279,7 +289,7 @@ impl Problem {
             for _ in 0..max_depth {
                 if let Some(done) = done.as_ref() {
                     if done.load(std::sync::atomic::Ordering::Relaxed) {
-                        return None;
+                        return Ok(None);
                     }
                 }
                 num_steps += 1;
@@ -304,12 +314,12 @@ impl Problem {
                 //    panic!("wtf");
                 //}
                 //println!("{}",p);
-                self.compute_triviality_with_input_with_sat(p.clone());
+                self.compute_triviality_with_input_with_sat(p.clone())?;
                 if self.is_trivial_with_input.unwrap() {
                     if let Some(done) = done.as_ref() {
                         done.store(true,std::sync::atomic::Ordering::Relaxed);
                     }
-                    return Some((v.len(),v))
+                    return Ok(Some((v.len(),v)))
                 }
 
                 let r = if p.labels().len() > max_labels {
@@ -323,7 +333,7 @@ impl Problem {
                         let labels = p.labels();
                         let random_label = *labels.choose(&mut rng).unwrap();
                         let set_label = vec![random_label];
-                        let new_p = p.logstar_mis(&set_label);
+                        let new_p = p.logstar_mis(&set_label)?;
                         if new_p.labels().len() > max_labels {
                             continue;
                         }
@@ -336,7 +346,7 @@ impl Problem {
                         let labels = p.labels();
                         let random_label = *labels.choose(&mut rng).unwrap();
                         let set_label = vec![random_label];
-                        let (new_p,map) = p.logstar_dup(&set_label);
+                        let (new_p,map) = p.logstar_dup(&set_label)?;
                         let (_,dups) = map.iter().next().unwrap();
                         if dups.len() == 1 {
                             continue;
@@ -358,7 +368,7 @@ impl Problem {
                         let labels = p.labels();
                         let random_label = *labels.choose(&mut rng).unwrap();
                         let set_label = vec![random_label];
-                        let new_p = p.logstar_see(&set_label);
+                        let new_p = p.logstar_see(&set_label)?;
                         if new_p.labels().len() > max_labels {
                             continue;
                         }
@@ -388,4 +398,34 @@ impl Problem {
     }
 
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use crate::{line::Degree, problem::Problem};
+
+    #[test]
+    fn logstar_hypergraph() {
+        let p = Problem::from_string("A A A\nB B B\n\nA B B\nA A B").unwrap();
+        let a = p.mapping_label_text.iter().find(|(_,s)|s == "A").unwrap().0;
+
+        let see = p.logstar_see_one(a).unwrap();
+        assert_eq!(see.passive.degree, Degree::Finite(3));
+        assert!(see.mapping_label_text.iter().all(|(_,s)|s != "A"));
+
+        let mis = p.logstar_mis(&vec![a]).unwrap();
+        assert_eq!(mis.passive.degree, Degree::Finite(3));
+        assert!(mis.mapping_label_text.iter().any(|(_,s)|s == "(A_m)"));
+
+        // on graphs, the copies of a label are named after the label on the other side
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let m = p.mapping_label_text.iter().find(|(_,s)|s == "M").unwrap().0;
+        let see = p.logstar_see_one(m).unwrap();
+        assert!(see.mapping_label_text.iter().any(|(_,s)|s == "(M_U)" || s == "(M_P)"));
+
+        let p = Problem::from_string("A A A\n\nA*").unwrap();
+        let a = p.mapping_label_text[0].0;
+        assert!(p.logstar_see_one(a).is_err());
+        assert!(p.logstar_mis(&vec![a]).is_err());
+    }
+}
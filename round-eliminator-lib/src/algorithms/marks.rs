@@ -3,16 +3,23 @@ use std::{collections::HashMap, time::Instant};
 use bit_vec::BitVec;
 use itertools::{Itertools};
 use rand::Rng;
-use rustsat::{instances::SatInstance, solvers::SolverResult, types::{constraints::CardConstraint, Lit}};
+use rustsat::{instances::SatInstance, types::{constraints::CardConstraint, Lit}};
 
 use crate::{constraint::Constraint, group::{Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem};
-use rustsat::solvers::Solve;
 
-use super::event::EventHandler;
+use super::{event::EventHandler, problem_triviality::solve_sat};
 
 use rand::seq::SliceRandom;
 
-type TableIndex = Label;
+/// Index of a subset of labels, the subset contains the labels whose position corresponds to a bit that is set.
+type TableIndex = usize;
+
+/// The maximum number of choices of subsets by a node, and by a passive node, read from the environment variable RE_MARKS_MAX_TABLE_ENTRIES.
+/// Each choice of a node uses one bit, a small fraction of them is kept in memory while prefiltering, and each choice can give a clause.
+/// Without the variable, the only bound is that the tables must be addressable.
+fn max_table_entries_from_env() -> Option<u64> {
+    std::env::var("RE_MARKS_MAX_TABLE_ENTRIES").ok().and_then(|s|s.parse::<u64>().ok())
+}
 
 fn mark_exists<T,F>(choice : &Vec<TableIndex>, successors : &Vec<Vec<usize>>, handled : &mut BitVec, num_handled : &mut usize, choice_to_index : &T, set_to_string : &F, subsets : &Vec<Vec<Label>>, complements : &Vec<Vec<Label>>) where T : Fn(&Vec<TableIndex>) -> usize, F : Fn(usize, &Vec<Label>) -> String{
     if handled.get(choice_to_index(choice)).unwrap() {
@@ -87,74 +94,60 @@ fn handle_choice_nodes<T,F>(active : &Constraint, choice : &Vec<TableIndex>, pre
 
 impl Problem {
 
-    pub fn apply_marks_technique(&mut self, eh: &mut EventHandler) {
-        let r = self.marks(eh);
+    pub fn apply_marks_technique(&mut self, eh: &mut EventHandler) -> Result<(), &'static str> {
+        let r = self.marks(eh)?;
         self.marks_works = Some(r);
+        Ok(())
     }
 
-    pub fn marks(&self, eh: &mut EventHandler) -> bool {
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("only works when the passive degree is 2");
+    /// The number of choices of subsets of labels by a node and by a passive node, that is, the sizes of the tables used by `marks`, or None if they do not fit in a u64.
+    pub(crate) fn marks_table_size(&self) -> Option<(u64, u64)> {
+        let num_subsets = 2u64.checked_pow(self.labels().len() as u32)?;
+        Some((num_subsets.checked_pow(self.active.finite_degree() as u32)?, num_subsets.checked_pow(self.passive.finite_degree() as u32)?))
+    }
+
+    /// Returns true if Marks' technique shows that the problem cannot be solved by Borel (and hence LOCAL) algorithms.
+    /// It works for any finite passive degree, where each passive node is a hyperedge, and the hyperedges incident to a node have distinct colors.
+    /// The tables contain one entry for each choice of subsets of labels by a node and by a passive node, hence an error is returned if they cannot be addressed, or if they are larger than the bound given by `max_table_entries_from_env`.
+    pub fn marks(&self, eh: &mut EventHandler) -> Result<bool, &'static str> {
+        self.marks_with_max_table_entries(max_table_entries_from_env(), eh)
+    }
+
+    fn marks_with_max_table_entries(&self, max_table_entries : Option<u64>, eh: &mut EventHandler) -> Result<bool, &'static str> {
+        self.require_regular()?;
+        if self.active.degree == Degree::Star || self.passive.degree == Degree::Star {
+            return Err("The degrees must be finite.");
         }
-        
-        let labels = self.labels();
-        if labels.len() > std::mem::size_of::<TableIndex>() * 8 {
-            panic!("too many labels");
+
+        let fits = |entries : u64| usize::try_from(entries).is_ok() && max_table_entries.map_or(true, |max|entries <= max);
+        match self.marks_table_size() {
+            Some((node, edge)) if fits(node) && fits(edge) => {},
+            _ => { return Err("There are too many labels for this degree."); }
         }
-        let labels_as_group = Group::from(labels.clone());
+        // the tables can be addressed, hence the following values fit in a usize
+        let labels = self.labels();
         let degree = self.active.finite_degree();
         let passive_degree = self.passive.finite_degree();
+        let num_subsets : usize = 1 << labels.len();
 
         let h : HashMap<_,_> = self.mapping_label_text.iter().cloned().collect();
         let set_to_string = |i : usize, set : &Vec<Label>|{
             format!("({}_{})",i,set.iter().map(|x|&h[x]).join(""))
         };
 
-        //println!("generating subsets");
-
-        let mut subsets = vec![];
-        let mut subsets_as_groups = vec![];
-        let mut complements = vec![];
-        
-        /* 
-        let mut passive = self.passive.clone();
-        passive.maximize(eh);
-        let mut active = self.active.clone();
-        active.maximize(eh);
-        let rcs = passive.groups().unique().map(|g|g.0.clone()).chain(active.groups().unique().map(|g|g.0.clone()));
-        for subset in rcs {
-            let subset_as_group = Group(subset.clone());
-            let complement = labels_as_group.difference(&subset_as_group);
-            complements.push(complement.0);
-            subsets.push(subset);
-            subsets_as_groups.push(subset_as_group);
-        }*/
-        
-        
-        for subset in labels.iter().cloned().powerset() {
-            let subset_as_group = Group::from(subset.clone());
-            let complement = labels_as_group.difference(&subset_as_group);
-            complements.push(complement.as_vec());
-            subsets.push(subset);
-            subsets_as_groups.push(subset_as_group);
-        }
-
+        let subset_of = |mask : TableIndex| -> Vec<Label> {
+            labels.iter().enumerate().filter(|(i,_)|mask & (1 << i) != 0).map(|(_,&l)|l).collect()
+        };
+        let full = num_subsets - 1;
+        let subsets : Vec<Vec<Label>> = (0..num_subsets).map(subset_of).collect();
+        let complements : Vec<Vec<Label>> = (0..num_subsets).map(|mask|subset_of(full & !mask)).collect();
 
+        // the subsets obtained by removing or adding a single label
         let mut predecessors = vec![];
         let mut successors = vec![];
-        for subset in 0..subsets.len() {
-            let mut v_pred = vec![];
-            let mut v_succ = vec![];
-            for other in 0..subsets.len() {
-                if subsets_as_groups[subset].is_superset(&subsets_as_groups[other]) && subsets_as_groups[subset].len() == subsets_as_groups[other].len()+1 {
-                    v_pred.push(other);
-                }
-                if subsets_as_groups[other].is_superset(&subsets_as_groups[subset]) && subsets_as_groups[other].len() == subsets_as_groups[subset].len()+1 {
-                    v_succ.push(other);
-                }
-            }
-            predecessors.push(v_pred);
-            successors.push(v_succ);
+        for mask in 0..num_subsets {
+            predecessors.push((0..labels.len()).filter(|i|mask & (1 << i) != 0).map(|i|mask & !(1 << i)).collect_vec());
+            successors.push((0..labels.len()).filter(|i|mask & (1 << i) == 0).map(|i|mask | (1 << i)).collect_vec());
         }
 
         //println!("generating variables");
@@ -251,6 +244,11 @@ impl Problem {
 
         
         // edge coloring given
+        // For each color i of the hyperedges and each set of labels S, table[i][S] tells which player wins the game of color i with S, and it is the same for all nodes.
+        // In a hyperedge of color i, each incident node plays this game against all the other incident nodes together, hence, by determinacy, either it or the others win.
+        // The incident nodes are mapped to each other by the action of the group whose Cayley graph is the hypergraph, so they play the same game.
+        // If all of them won with sets whose complements cannot be completed to a passive configuration, combining their strategies would give a contradiction,
+        // hence at most passive_degree-1 of them can win. For passive degree 2 this is the constraint used by Marks for edges.
         let edge_choices = (0..passive_degree).map(|_|0..subsets.len()).multi_cartesian_product();
         // .filter(|v|v[0] == v[1]); // in many cases this is sufficient to get a LB, in this case a sat solver would not be even needed
         let len = edge_choices.clone().count();
@@ -285,27 +283,7 @@ impl Problem {
 
         eh.notify("calling the sat solver",0,0);
 
-        #[cfg(feature = "all")]
-        {
-            let mut solver = rustsat_minisat::core::Minisat::default();
-            solver.add_cnf(instance.into_cnf().0).unwrap();
-            let res = solver.solve().unwrap();
-
-            res == SolverResult::Unsat
-        }
-
-        #[cfg(feature = "onlyrust")]
-        {
-            let mut instance = instance;
-            let mut dimacs = std::io::BufWriter::new(Vec::new());
-            instance.convert_to_cnf();
-            instance.write_dimacs(&mut dimacs).unwrap();
-            let dimacs = dimacs.into_inner().unwrap();
-            let mut solver = varisat::solver::Solver::new();
-            solver.add_dimacs_cnf(&dimacs[..]).unwrap();
-            let solution = solver.solve().unwrap();
-            !solution
-        }
+        Ok(solve_sat(instance, &[], eh)?.is_none())
     }
 }
 
@@ -325,7 +303,7 @@ B B Y
 AX BY
 XY XY").unwrap();
     println!("1");
-    assert!(!p.marks(eh));
+    assert_eq!(p.marks(eh), Ok(false));
 
     let p = Problem::from_string("1 1 1
 2 2 2
@@ -373,15 +351,42 @@ XY XY").unwrap();
 
     let p = Problem::from_string("A B B\n\nB AB").unwrap();
     println!("4");
-    assert!(p.marks(eh));
+    assert_eq!(p.marks(eh), Ok(true));
 
 
     let p = Problem::from_string("A A A\n\nA A").unwrap();
     println!("5");
-    assert!(!p.marks(eh));
+    assert_eq!(p.marks(eh), Ok(false));
 
     let p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
     println!("6");
-    assert!(p.marks(eh));
+    assert_eq!(p.marks(eh), Ok(true));
+
+}
 
-}
\ No newline at end of file
+#[test]
+fn marks_hypergraph_test(){
+    let eh = &mut EventHandler::null();
+
+    // solvable in zero rounds, so Marks' technique cannot give a lower bound
+    let p = Problem::from_string("A A A\n\nA A A").unwrap();
+    assert_eq!(p.marks(eh), Ok(false));
+
+    let p = Problem::from_string("A A A\nB B B\n\nA AB AB\nB B B").unwrap();
+    assert_eq!(p.marks(eh), Ok(false));
+
+    let p = Problem::from_string("A A A\n\nA*").unwrap();
+    assert!(p.marks(eh).is_err());
+
+    // 2-coloring of a 3-uniform hypergraph where no hyperedge is monochromatic
+    let p = Problem::from_string("A A A\nB B B\n\nA A B\nA B B").unwrap();
+    assert_eq!(p.marks(eh), Ok(true));
+
+    // the tables are larger than the given bound
+    let p = Problem::from_string("A A A A A A\nB B B B B B\nC C C C C C\nD D D D D D\nE E E E E E\n\nA B C D E").unwrap();
+    assert!(p.marks_with_max_table_entries(Some(1 << 24), eh).is_err());
+
+    // the tables cannot be addressed
+    let p = Problem::from_string("A A A A A A\nB B B B B B\nC C C C C C\nD D D D D D\nE E E E E E\nF F F F F F\nG G G G G G\nH H H H H H\nI I I I I I\nJ J J J J J\nK K K K K K\n\nABCDEFGHIJK ABCDEFGHIJK").unwrap();
+    assert!(p.marks(eh).is_err());
+}
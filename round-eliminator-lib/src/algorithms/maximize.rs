@@ -4,6 +4,7 @@ use dashmap::{DashMap, DashSet};
 use parking_lot::RwLock;
 use streaming_iterator::StreamingIterator;
 use std::time::Instant;
+use serde::{Deserialize, Serialize};
 
 use crate::line::CompressedLine;
 use crate::{
@@ -14,7 +15,36 @@ use crate::{
     part::Part,
 };
 
-use super::event::EventHandler;
+use super::{checkpoint::{checkpoint_key, load_checkpoint, save_checkpoint, CheckpointConfig}, event::EventHandler, spill::{MemoryBudget, SpilledLines}};
+
+/// The state of `maximize_custom` at some point of the computation.
+/// If `done_pairs` is not empty, the current step has been partially executed:
+/// the results of the pairs of lines marked as done are already dominated by the lines in `partial`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaximizeCheckpoint {
+    pub step : usize,
+    pub lines : Vec<Line>,
+    pub lines_of_previous_step : Vec<Line>,
+    /// The content of `seen` at the beginning of the current step.
+    pub seen : Vec<CompressedLine>,
+    pub done_pairs : Vec<bool>,
+    pub partial : Vec<Line>,
+    pub finished : bool,
+}
+
+/// Estimates collected by `maximize_from_checkpoint`, they are only computed when not running on wasm.
+#[derive(Clone, Copy, Debug, Default)]
+struct MaximizeStats {
+    peak_memory : usize,
+    /// The largest (estimated) memory used by `seen`.
+    peak_seen : usize,
+    /// The number of times lines have been written to disk.
+    spills : usize,
+}
+
+fn pair_index(i : usize, j : usize) -> usize {
+    i * (i+1) / 2 + j
+}
 
 impl Constraint {
 
@@ -25,27 +55,89 @@ impl Constraint {
         allow_empty : bool,
         track_unions : bool,
         tracking : Option<&DashMap<Line, (Line, Line, Line, Vec<Vec<usize>>, Vec<(usize, usize, Operation)>)>>,
+        checkpoint_tag : Option<&str>,
         f_is_superset : FS,
         f_union : FU,
         f_intersection : FI
-    ) where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
+    ) -> Result<(), String> where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
  
         if self.is_maximized || self.lines.is_empty() {
             self.is_maximized = true;
-            return;
+            return Ok(());
         }
 
+        // checkpoints are identified by the input, and they cannot store the tracking information
+        let checkpointing = match (checkpoint_tag, CheckpointConfig::from_env()) {
+            (Some(tag), Some(config)) if tracking.is_none() => {
+                let key = checkpoint_key(&(&self.lines, self.degree, allow_empty));
+                Some((config.path(tag, &key), config.interval))
+            }
+            _ => None
+        };
+        let resumed : Option<MaximizeCheckpoint> = checkpointing.as_ref().filter(|(path,_)|path.exists()).and_then(|(path,_)|{
+            match load_checkpoint(path) {
+                Ok(checkpoint) => Some(checkpoint),
+                Err(e) => {
+                    eprintln!("ignoring checkpoint {}: {}", path.display(), e);
+                    None
+                }
+            }
+        });
+
+        self.maximize_from_checkpoint(eh, allow_empty, track_unions, tracking, checkpointing, resumed, MemoryBudget::from_env(), f_is_superset, f_union, f_intersection).map(|_|())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn maximize_from_checkpoint<FS,FU,FI>(
+        &mut self,
+        eh: &mut EventHandler,
+        allow_empty : bool,
+        track_unions : bool,
+        tracking : Option<&DashMap<Line, (Line, Line, Line, Vec<Vec<usize>>, Vec<(usize, usize, Operation)>)>>,
+        checkpointing : Option<(std::path::PathBuf, std::time::Duration)>,
+        resumed : Option<MaximizeCheckpoint>,
+        budget : Option<MemoryBudget>,
+        f_is_superset : FS,
+        f_union : FU,
+        f_intersection : FI
+    ) -> Result<MaximizeStats, String> where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
+
         let becomes_star = 100;
 
+        let mut last_checkpoint = Instant::now();
+        let mut wrote_checkpoint = false;
+
+        #[allow(unused_mut)]
+        let mut stats = MaximizeStats::default();
+
         let seen = DashSet::new();
         let mut lines_of_previous_step = HashSet::new();
+        let mut step = 0;
+        let mut resumed_partial = None;
 
         let lines = std::mem::take(&mut self.lines);
         let empty = self.clone();
-        for mut line in lines {
-            line.normalize();
-            seen.insert(line.compressed());
-            self.add_line_and_discard_non_maximal_with_custom_supersets(line, Some(f_is_superset));
+        if let Some(checkpoint) = resumed {
+            if checkpoint.finished {
+                self.lines = checkpoint.lines;
+                self.is_maximized = true;
+                return Ok(stats);
+            }
+            for line in checkpoint.seen {
+                seen.insert(line);
+            }
+            lines_of_previous_step = checkpoint.lines_of_previous_step.into_iter().collect();
+            self.lines = checkpoint.lines;
+            step = checkpoint.step;
+            if !checkpoint.done_pairs.is_empty() {
+                resumed_partial = Some((checkpoint.done_pairs, checkpoint.partial));
+            }
+        } else {
+            for mut line in lines {
+                line.normalize();
+                seen.insert(line.compressed());
+                self.add_line_and_discard_non_maximal_with_custom_supersets(line, Some(f_is_superset));
+            }
         }
 
         //let original_lines = self.lines.clone();
@@ -63,8 +155,36 @@ impl Constraint {
             let lines_of_current_step = lines.iter().cloned().collect();
             let without_one = without_one(lines);
 
+            // a checkpoint taken in the middle of a step needs the content of `seen` at the beginning of the step,
+            // since `seen` may already contain lines obtained from pairs that are not yet done
+            let seen_at_step_start : Vec<CompressedLine> = if checkpointing.is_some() {
+                seen.iter().map(|x|x.key().clone()).collect()
+            } else {
+                vec![]
+            };
+            let make_checkpoint = |done_pairs : Vec<bool>, partial : Vec<Line>, finished : bool| MaximizeCheckpoint {
+                step,
+                lines : lines.clone(),
+                lines_of_previous_step : lines_of_previous_step.iter().cloned().collect(),
+                seen : seen_at_step_start.clone(),
+                done_pairs,
+                partial,
+                finished
+            };
+            if let Some((path, interval)) = &checkpointing {
+                if resumed_partial.is_none() && last_checkpoint.elapsed() > *interval {
+                    if let Err(e) = save_checkpoint(path, &make_checkpoint(vec![], vec![], false)) {
+                        eprintln!("cannot write checkpoint: {}", e);
+                    }
+                    wrote_checkpoint = true;
+                    last_checkpoint = Instant::now();
+                }
+            }
+
             #[cfg(target_arch = "wasm32")]
             let newconstraint = {
+                let _ = resumed_partial.take();
+                let _ = budget;
                 let mut newconstraint = self.clone();
                 for i in 0..lines.len() {
                     let mut candidates2 = empty.clone();
@@ -116,12 +236,39 @@ impl Constraint {
                     num_cpus::get()
                 };
 
+                let len = lines.len();
+                let total = len * (len+1)/2;
+                let (done_pairs, initial) = match resumed_partial.take() {
+                    Some((done_pairs, partial)) if done_pairs.len() == total => (done_pairs, partial),
+                    _ => (vec![false; total], self.lines.clone())
+                };
+                let to_do = done_pairs.iter().filter(|&&done|!done).count();
+                let done_pairs : Vec<_> = done_pairs.into_iter().map(AtomicBool::new).collect();
+
                 let v = append_only_vec::AppendOnlyVec::<_>::new();
-                for line in self.lines.iter() {
-                    v.push((AtomicBool::new(false),line.clone()));
+                for line in initial {
+                    v.push((AtomicBool::new(false),line));
                 }
-                //let newconstraint = std::sync::Arc::new(RwLock::new(v));
-                let newconstraint = std::sync::Arc::new(v);
+                // when the memory budget is reached, the candidate lines are written to disk and the consumers continue with a new set of lines,
+                // the maximal lines of the union of all the partitions are the same as the ones that would have been obtained without spilling
+                let newconstraint = std::sync::Arc::new(RwLock::new(v));
+                let mut spilled = budget.as_ref().map(|budget|SpilledLines::new(budget.dir.clone()));
+                let degree = self.degree;
+                let seed_lines = &self.lines;
+                let seed_bytes : usize = seed_lines.iter().map(|line|line.estimated_size()).sum();
+                let added_bytes = AtomicUsize::new(0);
+                // set when the budget cannot be respected, the pairs that are not yet sent to the workers are then skipped
+                let over_budget = AtomicBool::new(false);
+                let seen_entry_bytes = lines.first().map(|line|line.compressed().len()).unwrap_or(0) + std::mem::size_of::<CompressedLine>() + 16;
+                let merge = |current : &append_only_vec::AppendOnlyVec<(AtomicBool,Line)>, spilled : &Option<SpilledLines>| -> Result<Vec<Line>, String> {
+                    let mut merged = Constraint { lines: current.iter().filter(|(removed,_)|!removed.load(Ordering::SeqCst)).map(|(_,line)|line.clone()).collect(), is_maximized: false, degree };
+                    for partition in spilled.iter().flat_map(|spilled|spilled.partitions()) {
+                        for line in partition? {
+                            merged.add_line_and_discard_non_maximal_with_custom_supersets(line, Some(f_is_superset));
+                        }
+                    }
+                    Ok(merged.lines)
+                };
 
                 crossbeam::scope(|s| {
                     let (in_tx, in_rx) =  crossbeam_channel::bounded(128);
@@ -133,11 +280,19 @@ impl Constraint {
                     let seen = &seen;
                     let lines = &lines;
                     let without_one = &without_one;
+                    let done_pairs = &done_pairs;
+                    let added_bytes = &added_bytes;
+                    let over_budget = &over_budget;
     
                     s.spawn(|_|{
                         for i in 0..lines.len() {
                             for j in 0..=i {
-                                in_tx.send((i,j)).unwrap();
+                                if over_budget.load(Ordering::Relaxed) {
+                                    break;
+                                }
+                                if !done_pairs[pair_index(i,j)].load(Ordering::Relaxed) {
+                                    in_tx.send((i,j)).unwrap();
+                                }
                             }
                         }
                         drop(in_tx);
@@ -152,7 +307,7 @@ impl Constraint {
                                 if lines_of_previous_step.contains(&lines[i]) && 
                                    lines_of_previous_step.contains(&lines[j])
                                 {
-                                    out_tx.send(vec![]).unwrap();
+                                    out_tx.send((pair_index(i,j),vec![])).unwrap();
                                     continue;
                                 }
 
@@ -173,7 +328,7 @@ impl Constraint {
                                         tracking.entry(a).or_insert(b);
                                     }
                                 }
-                                out_tx.send(candidates).unwrap();
+                                out_tx.send((pair_index(i,j),candidates)).unwrap();
                             }
                             //println!("done producing new lines");
                             if n_workers == 0  {
@@ -194,7 +349,8 @@ impl Constraint {
                             //let mut times = 0;
                             let mut goodlines = VecDeque::new();
 
-                            while let Ok(candidates) = out_rx.recv() {
+                            while let Ok((pair,candidates)) = out_rx.recv() {
+                                let newconstraint = newconstraint.read();
                                 //times += 1;
                                 /*if thread_num == 0 && times % 128 == 0 {
                                     //let (bad,tot) = {
@@ -237,6 +393,7 @@ impl Constraint {
                                         }
                                     };
                                     if is_not_included {
+                                        added_bytes.fetch_add(newline.estimated_size() + std::mem::size_of::<(AtomicBool,Line)>(), Ordering::Relaxed);
                                         let added_pos = newconstraint.push((AtomicBool::new(false),newline.clone()));
                                         if newconstraint.iter().skip(checked_len).take(added_pos-checked_len)
                                                 .any(|(_,oldline)| oldline.includes_with_custom_supersets(&newline, Some(f_is_superset))) {
@@ -253,32 +410,85 @@ impl Constraint {
 
                                     }
                                 }
+                                done_pairs[pair].store(true,Ordering::SeqCst);
                                 progress_tx.send(()).unwrap();
                             }
                         });
                     }
 
+                    // the consumers hold the remaining senders, hence receiving fails only if they all stopped
+                    drop(progress_tx);
                     //let now = std::time::Instant::now();
-                    let len = lines.len();
-                    let total = len * (len+1)/2;
                     let mut last_notify = Instant::now();
-                    for received in 0..total {
-                        progress_rx.recv().unwrap();
+                    for received in 0..to_do {
+                        if progress_rx.recv().is_err() {
+                            // all the workers stopped early since the budget has been exceeded
+                            break;
+                        }
+                        let seen_bytes = seen.len() * seen_entry_bytes;
+                        let memory = seed_bytes + added_bytes.load(Ordering::Relaxed) + seen_bytes;
+                        stats.peak_memory = std::cmp::max(stats.peak_memory, memory);
+                        stats.peak_seen = std::cmp::max(stats.peak_seen, seen_bytes);
+                        if let Some(budget) = &budget {
+                            if memory > budget.bytes {
+                                // lines in `seen` are never produced again, and the fixed point procedure relies on it, hence `seen` cannot be emptied
+                                if seen_bytes > budget.bytes {
+                                    over_budget.store(true, Ordering::Relaxed);
+                                }
+                                if added_bytes.load(Ordering::Relaxed) > budget.bytes / 4 {
+                                    let mut newconstraint = newconstraint.write();
+                                    let spilled = spilled.as_mut().unwrap();
+                                    match spilled.spill(newconstraint.iter().filter(|(removed,_)|!removed.load(Ordering::SeqCst)).map(|(_,line)|line)) {
+                                        Ok(()) => {
+                                            stats.spills += 1;
+                                            let v = append_only_vec::AppendOnlyVec::<_>::new();
+                                            for line in seed_lines {
+                                                v.push((AtomicBool::new(false),line.clone()));
+                                            }
+                                            *newconstraint = v;
+                                            added_bytes.store(0, Ordering::Relaxed);
+                                        }
+                                        Err(e) => {
+                                            eprintln!("cannot spill lines to disk: {}", e);
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if let Some((path, interval)) = &checkpointing {
+                            if last_checkpoint.elapsed() > *interval {
+                                // pairs are marked as done only after their lines are added, hence the lines of done pairs are dominated by the current lines
+                                let done : Vec<bool> = done_pairs.iter().map(|done|done.load(Ordering::SeqCst)).collect();
+                                let saved = merge(&newconstraint.read(), &spilled).and_then(|partial|save_checkpoint(path, &make_checkpoint(done, partial, false)));
+                                if let Err(e) = saved {
+                                    eprintln!("cannot write checkpoint: {}", e);
+                                }
+                                wrote_checkpoint = true;
+                                last_checkpoint = Instant::now();
+                            }
+                        }
                         if last_notify.elapsed().as_millis() > 100 {
+                            let received = received + total - to_do;
                             let a = (2. *received as f64).sqrt() as usize;
                             let b = if len > 0 { len } else {1};
                             let a = b * (a*a)  / (b*b);
-                            eh.notify("combining line pairs", a,b);
+                            eh.notify(format!("combining line pairs, peak memory {} MB", stats.peak_memory >> 20), a,b);
                             last_notify = Instant::now();
                         }
                     }
                 }).unwrap();
 
+                if over_budget.load(Ordering::SeqCst) {
+                    return Err(format!("the memory budget of {} MB is too small, the lines that have already been produced use {} MB and cannot be discarded", budget.as_ref().unwrap().bytes >> 20, (seen.len() * seen_entry_bytes) >> 20));
+                }
+
                 //let newconstraint = newconstraint.read();
                 //let c1 = newconstraint.iter().filter(|(removed,_)|!removed.load(Ordering::SeqCst)).count();
                 //let c2 = newconstraint.iter().filter(|(removed,_)|removed.load(Ordering::SeqCst)).count();
                 //println!("bad {}, good {}",c2,c1);
-                Constraint{ lines: newconstraint.iter().filter(|(removed,_)|!removed.load(Ordering::SeqCst)).map(|(_,line)|line.clone()).collect(), is_maximized: false, degree: self.degree }
+                let lines = merge(&newconstraint.read(), &spilled)?;
+                stats.peak_memory = std::cmp::max(stats.peak_memory, lines.iter().map(|line|line.estimated_size()).sum::<usize>() + seen.len() * seen_entry_bytes);
+                Constraint{ lines, is_maximized: false, degree: self.degree }
             };
 
             //println!("seen elements: {}, seen_pairs elements: {}",seen.len(),seen_pairs.len());
@@ -289,16 +499,63 @@ impl Constraint {
             *self = newconstraint;
 
             lines_of_previous_step = lines_of_current_step;
+            step += 1;
+        }
+
+        // the result of long computations is kept, so that running them again returns immediately
+        if let Some((path, _)) = &checkpointing {
+            if wrote_checkpoint {
+                let checkpoint = MaximizeCheckpoint { step, lines : self.lines.clone(), lines_of_previous_step : vec![], seen : vec![], done_pairs : vec![], partial : vec![], finished : true };
+                if let Err(e) = save_checkpoint(path, &checkpoint) {
+                    eprintln!("cannot write checkpoint: {}", e);
+                }
+            }
         }
 
         self.is_maximized = true;
+        Ok(stats)
     }
 
+    /// Same as `try_maximize`, for the callers that cannot handle errors.
+    /// Errors only happen when a memory budget is set, see `MemoryBudget::from_env`.
     pub fn maximize(&mut self, eh: &mut EventHandler) {
+        if let Err(e) = self.try_maximize(eh) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Maximizes the constraint, failing if the memory budget is too small or if the lines written to disk cannot be read back.
+    pub fn try_maximize(&mut self, eh: &mut EventHandler) -> Result<(), String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(pool) = super::distributed::worker_pool() {
+            // a poisoned lock only means that another computation panicked, the connections that it broke are removed by the next one
+            let mut streams = pool.lock().unwrap_or_else(|e|e.into_inner());
+            match self.maximize_with_workers(&mut streams, eh) {
+                Ok(()) => { return Ok(()); }
+                Err(e) => { eprintln!("{}, maximizing locally", e); }
+            }
+        }
+        let f_is_superset = |g1 : &Group ,g2 : &Group |{ g1.is_superset(g2) };
+        let f_union = |g1 : &Group ,g2 : &Group |{ g1.union(g2) };
+        let f_intersection = |g1 : &Group ,g2 : &Group |{ g1.intersection(g2) };
+        self.maximize_custom(eh,false,false,None,Some("maximize"),f_is_superset,f_union,f_intersection)
+    }
+
+    /// Continues a computation of `maximize` from the given checkpoint file.
+    /// It gives the same result that the original computation would have given.
+    pub fn maximize_resume(path : &std::path::Path, eh: &mut EventHandler) -> Result<Self, String> {
+        let checkpoint : MaximizeCheckpoint = load_checkpoint(path)?;
+        let degree = checkpoint.lines.first().map(|line|line.degree()).ok_or("the checkpoint does not contain any line")?;
+        if checkpoint.finished {
+            return Ok(Constraint { lines : checkpoint.lines, is_maximized : true, degree });
+        }
+        let interval = CheckpointConfig::from_env().map(|config|config.interval).unwrap_or(std::time::Duration::from_secs(300));
+        let mut constraint = Constraint { lines : vec![], is_maximized : false, degree };
         let f_is_superset = |g1 : &Group ,g2 : &Group |{ g1.is_superset(g2) };
         let f_union = |g1 : &Group ,g2 : &Group |{ g1.union(g2) };
         let f_intersection = |g1 : &Group ,g2 : &Group |{ g1.intersection(g2) };
-        self.maximize_custom(eh,false,false,None,f_is_superset,f_union,f_intersection);
+        constraint.maximize_from_checkpoint(eh, false, false, None, Some((path.to_owned(), interval)), Some(checkpoint), MemoryBudget::from_env(), f_is_superset, f_union, f_intersection)?;
+        Ok(constraint)
     }
 }
 
@@ -549,7 +806,7 @@ pub fn combine_lines_custom<FS,FU,FI>(
 }
 
 
-fn combine_lines(
+pub(crate) fn combine_lines(
     l1: &Line,
     l2: &Line,
     l1_without_one: &[Line],
@@ -687,3 +944,72 @@ fn crash_test(){
     //}
 }
 
+
+#[test]
+fn resume_from_checkpoint(){
+    let mut eh = EventHandler::null();
+    let p = crate::problem::Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+    let mut expected = p.passive.clone();
+    expected.maximize(&mut eh);
+
+    let mut start = Constraint { lines : vec![], is_maximized : false, degree : p.passive.degree };
+    for mut line in p.passive.lines.clone() {
+        line.normalize();
+        start.add_line_and_discard_non_maximal(line);
+    }
+    let len = start.lines.len();
+    let checkpoint = MaximizeCheckpoint {
+        step : 0,
+        lines : start.lines.clone(),
+        lines_of_previous_step : vec![],
+        seen : start.lines.iter().map(|line|line.compressed()).collect(),
+        done_pairs : vec![false; len * (len+1) / 2],
+        partial : start.lines.clone(),
+        finished : false
+    };
+    let path = std::env::temp_dir().join(format!("re-maximize-test-{}.ckpt", std::process::id()));
+    super::checkpoint::save_checkpoint(&path, &checkpoint).unwrap();
+    let resumed = Constraint::maximize_resume(&path, &mut eh).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let sorted = |c : &Constraint| { let mut v = c.lines.clone(); v.sort(); v };
+    assert_eq!(sorted(&resumed), sorted(&expected));
+}
+
+
+#[test]
+fn maximize_with_memory_budget(){
+    let mut eh = EventHandler::null();
+    let f_is_superset = |g1 : &Group ,g2 : &Group |{ g1.is_superset(g2) };
+    let f_union = |g1 : &Group ,g2 : &Group |{ g1.union(g2) };
+    let f_intersection = |g1 : &Group ,g2 : &Group |{ g1.intersection(g2) };
+    let sorted = |c : &Constraint| { let mut v = c.lines.clone(); v.sort(); v };
+
+    let p = crate::problem::Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+    let mut expected = p.passive.clone();
+    let unbudgeted = expected.maximize_from_checkpoint(&mut eh, false, false, None, None, None, None, f_is_superset, f_union, f_intersection).unwrap();
+    assert_eq!(unbudgeted.spills, 0);
+
+    // `seen` always fits in this budget, while at the end of the first step, the line MU U does not
+    let dir = std::env::temp_dir().join(format!("re-spill-test-{}", std::process::id()));
+    let budget = MemoryBudget { bytes : unbudgeted.peak_seen, dir : dir.clone() };
+    let mut c = p.passive.clone();
+    let stats = c.maximize_from_checkpoint(&mut eh, false, false, None, None, None, Some(budget), f_is_superset, f_union, f_intersection).unwrap();
+    assert!(stats.spills > 0);
+    assert_eq!(std::fs::read_dir(&dir).map(|d|d.count()).unwrap_or(0), 0);
+    let _ = std::fs::remove_dir(&dir);
+    assert_eq!(sorted(&c), sorted(&expected));
+}
+
+#[test]
+fn maximize_over_memory_budget(){
+    let mut eh = EventHandler::null();
+    let f_is_superset = |g1 : &Group ,g2 : &Group |{ g1.is_superset(g2) };
+    let f_union = |g1 : &Group ,g2 : &Group |{ g1.union(g2) };
+    let f_intersection = |g1 : &Group ,g2 : &Group |{ g1.intersection(g2) };
+    let p = crate::problem::Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+    let budget = MemoryBudget { bytes : 0, dir : std::env::temp_dir() };
+    let mut c = p.passive.clone();
+    let result = c.maximize_from_checkpoint(&mut eh, false, false, None, None, None, Some(budget), f_is_superset, f_union, f_intersection);
+    assert!(result.unwrap_err().contains("memory budget"));
+}
@@ -1,6 +1,8 @@
 pub mod choices;
 pub mod coloring_solvability;
 pub mod diagram;
+pub mod diagram_export;
+pub mod latex;
 pub mod discard_useless;
 pub mod event;
 pub mod group_iter;
@@ -11,17 +13,23 @@ pub mod line_normalizer;
 pub mod mapping_problem;
 pub mod max_clique;
 pub mod maximize;
+pub mod automorphisms;
 pub mod merge_equivalent;
 pub mod multisets_pairing;
 pub mod orientation;
 pub mod part_parser;
+pub mod extended_format;
 pub mod problem_triviality;
+pub mod incremental_triviality;
 pub mod relax;
 pub mod renaming;
 pub mod speedup;
 pub mod autoub;
 pub mod autolb;
 pub mod fixpoint;
+pub mod fixpoint_report;
+pub mod fixpoint_search;
+pub mod fixpoint_verify;
 pub mod multigraph;
 pub mod marks;
 pub mod merge_subdiagram;
@@ -32,5 +40,13 @@ pub mod delta_edge_coloring;
 pub mod lll;
 pub mod genetic;
 pub mod dual;
+pub mod classify;
 pub mod logstar;
-pub mod nofixpoint;
\ No newline at end of file
+pub mod nofixpoint;
+pub mod sat;
+pub mod checkpoint;
+pub mod nonregular;
+pub mod node_centred;
+pub mod spill;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod distributed;
@@ -1,13 +1,262 @@
-use crate::problem::Problem;
+use std::{collections::{HashMap, HashSet}, fmt::Display};
 
+use itertools::Itertools;
+use permutator::Permutation;
+use rustsat::{instances::SatInstance, types::{constraints::CardConstraint, Lit}};
+use serde::{Deserialize, Serialize};
 
+use crate::{group::{Group, GroupType}, line::{Degree, Line}, part::Part, problem::Problem};
 
+use super::{event::EventHandler, problem_triviality::solve_sat};
 
+/// A multigraph where every node has the same degree.
+/// Parallel edges are represented by repeating the same pair in `edges`, and a self loop `(v,v)` uses two ports of `v`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Multigraph {
+    pub nodes : usize,
+    pub degree : usize,
+    pub edges : Vec<(usize,usize)>,
+}
 
+impl Multigraph {
+    /// For each node, the list of (edge index, side of the edge) of its ports, in order.
+    fn ports(&self) -> Vec<Vec<(usize,usize)>> {
+        let mut ports = vec![vec![]; self.nodes];
+        for (i,&(u,v)) in self.edges.iter().enumerate() {
+            ports[u].push((i,0));
+            ports[v].push((i,1));
+        }
+        ports
+    }
 
+    fn is_connected(&self) -> bool {
+        if self.nodes == 0 {
+            return true;
+        }
+        let mut adj = vec![vec![]; self.nodes];
+        for &(u,v) in &self.edges {
+            adj[u].push(v);
+            adj[v].push(u);
+        }
+        let mut seen = vec![false; self.nodes];
+        let mut stack = vec![0];
+        seen[0] = true;
+        while let Some(u) = stack.pop() {
+            for &v in &adj[u] {
+                if !seen[v] {
+                    seen[v] = true;
+                    stack.push(v);
+                }
+            }
+        }
+        seen.into_iter().all(|x|x)
+    }
+
+    /// Lexicographically smallest sorted edge list among all relabelings of the nodes.
+    /// It is only computed for small graphs, for larger ones the edge list is returned as is.
+    fn canonical_form(&self) -> Vec<(usize,usize)> {
+        let normalize = |perm : &[usize]| -> Vec<(usize,usize)> {
+            self.edges.iter().map(|&(u,v)|{
+                let (a,b) = (perm[u],perm[v]);
+                if a <= b { (a,b) } else { (b,a) }
+            }).sorted().collect()
+        };
+        if self.nodes > 7 {
+            return normalize(&(0..self.nodes).collect_vec());
+        }
+        (0..self.nodes).permutations(self.nodes).map(|perm|normalize(&perm)).min().unwrap_or_default()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("graph G {\n");
+        for v in 0..self.nodes {
+            s.push_str(&format!("    {};\n", v));
+        }
+        for &(u,v) in &self.edges {
+            s.push_str(&format!("    {} -- {};\n", u, v));
+        }
+        s.push_str("}\n");
+        s
+    }
+}
+
+impl Display for Multigraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} nodes, degree {}", self.nodes, self.degree)?;
+        for &(u,v) in &self.edges {
+            writeln!(f, "{} {}", u, v)?;
+        }
+        Ok(())
+    }
+}
+
+fn regular_multigraphs_aux(pairs : &[(usize,usize)], i : usize, remaining : &mut Vec<usize>, edges : &mut Vec<(usize,usize)>, allow_self_loops : bool, n : usize, degree : usize, result : &mut Vec<Multigraph>) {
+    if i == pairs.len() {
+        if remaining.iter().all(|&r|r == 0) {
+            result.push(Multigraph { nodes : n, degree, edges : edges.clone() });
+        }
+        return;
+    }
+    let (u,v) = pairs[i];
+    let max = if u == v {
+        if allow_self_loops { remaining[u] / 2 } else { 0 }
+    } else {
+        std::cmp::min(remaining[u],remaining[v])
+    };
+    for m in (0..=max).rev() {
+        let used = if u == v { 2*m } else { m };
+        remaining[u] -= used;
+        if u != v {
+            remaining[v] -= m;
+        }
+        // after the last pair involving u, all its ports must be used
+        let u_done = v == n-1;
+        if !u_done || remaining[u] == 0 {
+            for _ in 0..m {
+                edges.push((u,v));
+            }
+            regular_multigraphs_aux(pairs, i+1, remaining, edges, allow_self_loops, n, degree, result);
+            for _ in 0..m {
+                edges.pop();
+            }
+        }
+        remaining[u] += used;
+        if u != v {
+            remaining[v] += m;
+        }
+    }
+}
+
+/// Returns all connected `degree`-regular multigraphs on `n` nodes, up to isomorphism (isomorphism is only checked for at most 7 nodes).
+pub fn regular_multigraphs(n : usize, degree : usize, allow_self_loops : bool) -> Vec<Multigraph> {
+    if n == 0 || (n * degree) % 2 != 0 {
+        return vec![];
+    }
+    let pairs = (0..n).flat_map(|u|(u..n).map(move |v|(u,v))).collect_vec();
+    let mut result = vec![];
+    regular_multigraphs_aux(&pairs, 0, &mut vec![degree; n], &mut vec![], allow_self_loops, n, degree, &mut result);
+
+    let mut seen = HashSet::new();
+    result.into_iter()
+        .filter(|g|g.is_connected())
+        .filter(|g|seen.insert(g.canonical_form()))
+        .collect()
+}
 
 impl Problem {
-    fn find_unsolvable_multigraph(&self) {
-        
+
+    /// Checks whether the problem admits a valid labeling on the given multigraph, by using a sat solver.
+    /// Each node of the multigraph is an active node, and each edge is a passive node of degree 2.
+    /// An error is returned if the sat solver fails.
+    pub fn is_solvable_on_multigraph(&self, g : &Multigraph, eh : &mut EventHandler) -> Result<bool, &'static str> {
+        let labels = self.labels();
+        let squish : HashMap<_,_> = labels.iter().enumerate().map(|(i,&l)|(l,i)).collect();
+        let num_labels = labels.len();
+
+        let active_lines : Vec<Vec<usize>> = self.active.all_choices(false).into_iter().flat_map(|line|{
+            let mut parts = line.parts.clone();
+            parts.permutation().map(|perm|{
+                perm.iter().map(|part|squish[&part.group.first()]).collect_vec()
+            }).collect_vec()
+        }).unique().collect();
+
+        let mut allowed = vec![vec![false; num_labels]; num_labels];
+        for i in 0..num_labels {
+            for j in 0..num_labels {
+                let line = Line { parts : vec![
+                    Part { gtype : GroupType::ONE, group : Group::from(vec![labels[i]]) },
+                    Part { gtype : GroupType::ONE, group : Group::from(vec![labels[j]]) }
+                ]};
+                allowed[i][j] = self.passive.includes_single_line(&line);
+            }
+        }
+
+        let mut instance: SatInstance = SatInstance::new();
+
+        // edge_label[e][side][l] is true iff the port on side `side` of edge `e` gets label `l`
+        let edge_label : Vec<Vec<Vec<Lit>>> = g.edges.iter().map(|_|
+            (0..2).map(|_|(0..num_labels).map(|_|instance.new_lit()).collect()).collect()
+        ).collect();
+
+        for e in &edge_label {
+            for side in e {
+                instance.add_card_constr(CardConstraint::new_eq(side.iter().cloned(),1));
+            }
+            for i in 0..num_labels {
+                for j in 0..num_labels {
+                    if !allowed[i][j] {
+                        instance.add_clause([!e[0][i], !e[1][j]].into());
+                    }
+                }
+            }
+        }
+
+        for ports in g.ports() {
+            let choices = active_lines.iter().map(|line|{
+                let choice = instance.new_lit();
+                let cube = line.iter().zip(ports.iter()).map(|(&l,&(e,side))|edge_label[e][side][l]).collect_vec();
+                instance.add_lit_impl_cube(choice, &cube);
+                choice
+            }).collect_vec();
+            instance.add_clause(choices.into_iter().collect());
+        }
+
+        let instance = instance.sanitize();
+        Ok(solve_sat(instance, &[], eh)?.is_some())
+    }
+
+    /// Searches for a connected multigraph with at most `max_nodes` nodes on which the problem cannot be solved.
+    /// Multigraphs are tried in order of increasing number of nodes, so the returned one is among the smallest ones.
+    pub fn find_unsolvable_multigraph(&self, max_nodes : usize, allow_self_loops : bool, eh : &mut EventHandler) -> Result<Option<Multigraph>, &'static str> {
+        self.require_regular()?;
+        if self.passive.degree != Degree::Finite(2) {
+            return Err("The passive degree must be 2.");
+        }
+        if self.active.degree == Degree::Star {
+            return Err("The active degree must be finite.");
+        }
+        let degree = self.active.finite_degree();
+
+        for n in 1..=max_nodes {
+            let graphs = regular_multigraphs(n, degree, allow_self_loops);
+            let len = graphs.len();
+            for (i,g) in graphs.into_iter().enumerate() {
+                eh.notify(format!("multigraphs on {} nodes", n), i, len);
+                if !self.is_solvable_on_multigraph(&g, eh)? {
+                    return Ok(Some(g));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::regular_multigraphs;
+
+    #[test]
+    fn enumeration() {
+        assert_eq!(regular_multigraphs(2, 3, false).len(), 1);
+        assert_eq!(regular_multigraphs(3, 3, false).len(), 0);
+        assert_eq!(regular_multigraphs(4, 3, false).len(), 2);
+        assert_eq!(regular_multigraphs(1, 2, true).len(), 1);
+    }
+
+    #[test]
+    fn unsolvable_multigraph() {
+        let eh = &mut EventHandler::null();
+
+        // sinkless orientation is solvable on every multigraph without self loops
+        let p = Problem::from_string("O OI OI\n\nI O").unwrap();
+        assert!(p.find_unsolvable_multigraph(4, false, eh).unwrap().is_none());
+
+        // proper 2-coloring is not solvable on a triangle
+        let p = Problem::from_string("A A\nB B\n\nA B").unwrap();
+        let g = p.find_unsolvable_multigraph(4, false, eh).unwrap().unwrap();
+        assert_eq!(g.nodes, 3);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,373 @@
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::Constraint, group::{Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem};
+
+/// The labels that a node of a node-centred LCL sees on one of its half-edges:
+/// its own input and output label (if the problem has them), and the output label of the half-edge (if the problem has them).
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct HalfEdgeView {
+    pub input : Option<String>,
+    pub node : Option<String>,
+    pub half_edge : Option<String>,
+}
+
+/// A configuration allowed on nodes: given the input of the node, it may output the node label and the labels on its half-edges.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct NodeConfiguration {
+    pub input : Option<String>,
+    pub node : Option<String>,
+    /// Either empty, if the problem has no half-edge labels, or one label for each half-edge.
+    pub half_edges : Vec<String>,
+}
+
+/// One endpoint of an allowed edge configuration, None matches any label.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub node : Option<String>,
+    pub half_edge : Option<String>,
+}
+
+impl Endpoint {
+    fn matches(&self, view : &HalfEdgeView) -> bool {
+        (self.node.is_none() || self.node == view.node) && (self.half_edge.is_none() || self.half_edge == view.half_edge)
+    }
+}
+
+/// An LCL on Δ-regular graphs described in the node-centred way used in papers, with node constraints and edge constraints,
+/// where outputs can be on nodes, on half-edges, or both, and nodes may receive an input.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeCentredLcl {
+    pub degree : usize,
+    pub nodes : Vec<NodeConfiguration>,
+    /// Edges are not oriented, a pair allows both orders.
+    pub edges : Vec<(Endpoint, Endpoint)>,
+}
+
+/// The result of the conversion, each label of the problem corresponds to what a node sees on one half-edge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConvertedLcl {
+    pub problem : Problem,
+    pub mapping : Vec<(Label, HalfEdgeView)>,
+}
+
+impl NodeCentredLcl {
+    /// Radius-1 constraints on node labels: each entry gives the input of a node (if any), its label, and the labels of its neighbors.
+    /// A node writes on each half-edge the label that it expects on the neighbor, and edges check that the expectations are consistent.
+    pub fn from_neighbourhoods(degree : usize, neighbourhoods : Vec<(Option<String>, String, Vec<String>)>) -> Self {
+        let labels : BTreeSet<String> = neighbourhoods.iter().map(|(_,node,_)|node.clone()).collect();
+        let nodes = neighbourhoods.into_iter().map(|(input, node, neighbours)|NodeConfiguration { input, node : Some(node), half_edges : neighbours }).collect();
+        let edges = labels.iter().cartesian_product(labels.iter())
+            .filter(|(x,y)|x <= y)
+            .map(|(x,y)|(
+                Endpoint { node : Some(x.clone()), half_edge : Some(y.clone()) },
+                Endpoint { node : Some(y.clone()), half_edge : Some(x.clone()) }
+            ))
+            .collect();
+        Self { degree, nodes, edges }
+    }
+
+    /// Parses a description such as
+    /// ```text
+    /// degree 3
+    /// node A : a a b
+    /// node [i] B : b b b
+    /// edge A.a B.b
+    /// edge *.b *.b
+    /// ```
+    /// where `node` lines give an optional input in brackets, an optional node label, and after `:` the half-edge labels (or nothing),
+    /// and `edge` lines give two endpoints of the form `node.half_edge`, where `*` matches any label.
+    /// An endpoint without `.` refers to the node label if the problem has no half-edge labels, and to the half-edge label otherwise.
+    /// Lines of the form `neighbourhood [i] A : B B C` describe radius-1 constraints, see `from_neighbourhoods`, and they cannot be mixed with the other kinds of lines.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(text : &str) -> Result<Self, String> {
+        let mut degree = None;
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        let mut neighbourhoods = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = |msg : &str| format!("line {}: {}", i + 1, msg);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match keyword {
+                "degree" => {
+                    degree = Some(rest.trim().parse::<usize>().map_err(|_|error("invalid degree"))?);
+                }
+                "node" | "neighbourhood" | "neighborhood" => {
+                    let (input, rest) = match rest.trim().strip_prefix('[') {
+                        Some(r) => {
+                            let (input, rest) = r.split_once(']').ok_or_else(||error("missing ]"))?;
+                            (Some(input.trim().to_owned()), rest)
+                        }
+                        None => (None, rest)
+                    };
+                    let (node, half_edges) = rest.split_once(':').unwrap_or((rest, ""));
+                    let node = Some(node.trim().to_owned()).filter(|s|!s.is_empty());
+                    let half_edges : Vec<String> = half_edges.split_whitespace().map(|s|s.to_owned()).collect();
+                    if keyword == "node" {
+                        nodes.push(NodeConfiguration { input, node, half_edges });
+                    } else {
+                        let node = node.ok_or_else(||error("missing node label"))?;
+                        neighbourhoods.push((input, node, half_edges));
+                    }
+                }
+                "edge" => {
+                    let endpoints : Vec<&str> = rest.split_whitespace().collect();
+                    if endpoints.len() != 2 {
+                        return Err(error("an edge must have two endpoints"));
+                    }
+                    let e1 = parse_endpoint(endpoints[0]);
+                    let e2 = parse_endpoint(endpoints[1]);
+                    edges.push((e1, e2));
+                }
+                _ => {
+                    return Err(error("unknown keyword"));
+                }
+            }
+        }
+
+        let degree = degree.ok_or("missing degree")?;
+        if !neighbourhoods.is_empty() {
+            if !nodes.is_empty() || !edges.is_empty() {
+                return Err("neighbourhood constraints cannot be mixed with node and edge constraints".into());
+            }
+            return Ok(Self::from_neighbourhoods(degree, neighbourhoods));
+        }
+
+        // endpoints without `.` are resolved now that we know which kinds of labels are used
+        let has_half_edges = nodes.iter().any(|n|!n.half_edges.is_empty());
+        for (e1, e2) in edges.iter_mut() {
+            for e in [e1, e2] {
+                if let (Some(node), None) = (&e.node, &e.half_edge) {
+                    if has_half_edges && !node.ends_with('.') {
+                        e.half_edge = e.node.take();
+                    }
+                }
+                if let Some(node) = e.node.as_mut() {
+                    if let Some(n) = node.strip_suffix('.') {
+                        *node = n.to_owned();
+                    }
+                }
+                if e.node.as_deref() == Some("*") || e.node.as_deref() == Some("") {
+                    e.node = None;
+                }
+            }
+        }
+
+        Ok(Self { degree, nodes, edges })
+    }
+
+    /// Converts the description into a problem in the formalism of round eliminator: active nodes are the nodes, passive nodes are the edges.
+    /// If nodes have inputs, the input is stored in the labels, and the problem describing the input is attached to the result, together with the labels allowed for each input.
+    pub fn to_problem(&self) -> Result<ConvertedLcl, String> {
+        if self.degree == 0 {
+            return Err("the degree must be positive".into());
+        }
+        let mut views : Vec<Vec<HalfEdgeView>> = vec![];
+        for config in &self.nodes {
+            let half_edges : Vec<Option<String>> = if config.half_edges.is_empty() {
+                vec![None; self.degree]
+            } else if config.half_edges.len() == self.degree {
+                config.half_edges.iter().cloned().map(Some).collect()
+            } else {
+                return Err(format!("a node configuration has {} half-edge labels, but the degree is {}", config.half_edges.len(), self.degree));
+            };
+            if config.node.is_none() && config.half_edges.is_empty() {
+                return Err("a node configuration must contain some output".into());
+            }
+            views.push(half_edges.into_iter().map(|half_edge|HalfEdgeView { input : config.input.clone(), node : config.node.clone(), half_edge }).collect());
+        }
+        if views.is_empty() {
+            return Err("there are no node configurations".into());
+        }
+
+        let all_views : Vec<HalfEdgeView> = views.iter().flatten().cloned().unique().sorted().collect();
+        let label_of : HashMap<&HalfEdgeView, Label> = all_views.iter().enumerate().map(|(i,v)|(v, i as Label)).collect();
+        let mapping : Vec<(Label, HalfEdgeView)> = all_views.iter().enumerate().map(|(i,v)|(i as Label, v.clone())).collect();
+
+        let single = |l : Label| Part { gtype : GroupType::ONE, group : Group::from(vec![l]) };
+
+        let mut active = Constraint { lines : vec![], is_maximized : false, degree : Degree::Finite(self.degree) };
+        for v in &views {
+            let mut line = Line { parts : v.iter().map(|v|single(label_of[v])).collect() };
+            line.normalize();
+            active.add_line_and_discard_non_maximal(line);
+        }
+
+        let mut passive = Constraint { lines : vec![], is_maximized : false, degree : Degree::Finite(2) };
+        for (i, v1) in all_views.iter().enumerate() {
+            for v2 in &all_views[i..] {
+                if self.edges.iter().any(|(e1,e2)|(e1.matches(v1) && e2.matches(v2)) || (e1.matches(v2) && e2.matches(v1))) {
+                    let mut line = Line { parts : vec![single(label_of[v1]), single(label_of[v2])] };
+                    line.normalize();
+                    passive.add_line_and_discard_non_maximal(line);
+                }
+            }
+        }
+        if passive.lines.is_empty() {
+            return Err("no edge configuration is allowed".into());
+        }
+
+        let mapping_label_text = mapping.iter().map(|(l, v)|(*l, label_name(v))).collect();
+        let mut problem = new_problem(active, passive, mapping_label_text);
+
+        let inputs : BTreeSet<&String> = self.nodes.iter().filter_map(|n|n.input.as_ref()).collect();
+        if !inputs.is_empty() {
+            if self.nodes.iter().any(|n|n.input.is_none()) {
+                return Err("either all node configurations have an input or none of them".into());
+            }
+            let inputs = inputs.into_iter().cloned().collect_vec();
+            let mapping_input_text : Vec<(Label, String)> = inputs.iter().enumerate().map(|(i, s)|(i as Label, name(s))).collect();
+            let input_active = Constraint {
+                lines : (0..inputs.len()).map(|i|Line { parts : vec![Part { gtype : GroupType::Many(self.degree as crate::group::Exponent), group : Group::from(vec![i as Label]) }] }).collect(),
+                is_maximized : false,
+                degree : Degree::Finite(self.degree)
+            };
+            let input_passive = Constraint {
+                lines : vec![Line { parts : vec![Part { gtype : GroupType::Many(2), group : Group::from((0..inputs.len() as Label).collect()) }] }],
+                is_maximized : false,
+                degree : Degree::Finite(2)
+            };
+            problem.set_input(Some(new_problem(input_active, input_passive, mapping_input_text)));
+            // a node can only output the labels of the configurations of its own input
+            problem.input_allowed_outputs = Some(inputs.iter().enumerate().map(|(i, s)|{
+                (i as Label, mapping.iter().filter(|(_, v)|v.input.as_ref() == Some(s)).map(|(l, _)|*l).collect())
+            }).collect());
+        }
+
+        Ok(ConvertedLcl { problem, mapping })
+    }
+}
+
+impl ConvertedLcl {
+    /// Describes a label of the converted problem in terms of the original description.
+    pub fn describe(&self, label : Label) -> Option<String> {
+        let (_, v) = self.mapping.iter().find(|(l,_)|*l == label)?;
+        let mut parts = vec![];
+        if let Some(input) = &v.input {
+            parts.push(format!("input {}", input));
+        }
+        if let Some(node) = &v.node {
+            parts.push(format!("node {}", node));
+        }
+        if let Some(half_edge) = &v.half_edge {
+            parts.push(format!("half-edge {}", half_edge));
+        }
+        Some(parts.join(", "))
+    }
+}
+
+fn new_problem(active : Constraint, passive : Constraint, mapping_label_text : Vec<(Label, String)>) -> Problem {
+    Problem {
+        active,
+        passive,
+        passive_gen : None,
+        mapping_label_text,
+        mapping_label_oldlabels: None,
+        mapping_oldlabel_labels: None,
+        mapping_oldlabel_text: None,
+        trivial_sets: None,
+        coloring_sets: None,
+        diagram_indirect: None,
+        diagram_indirect_old: None,
+        diagram_direct: None,
+        orientation_coloring_sets: None,
+        orientation_trivial_sets: None,
+        orientation_given: None,
+        fixpoint_diagram : None,
+        fixpoint_procedure_works : None,
+        marks_works : None,
+        demisifiable : None,
+        is_trivial_with_input : None,
+        triviality_with_input : None,
+        trivial_given_input : None,
+        expressions : None,
+        input_given : None,
+        input_allowed_outputs : None,
+        other_degrees : None
+    }
+}
+
+fn parse_endpoint(s : &str) -> Endpoint {
+    let any = |s : &str| if s == "*" || s.is_empty() { None } else { Some(s.to_owned()) };
+    match s.split_once('.') {
+        Some((node, half_edge)) if !half_edge.is_empty() && half_edge != "*" => Endpoint { node : any(node), half_edge : any(half_edge) },
+        // `x.` and `x.*` are explicit node labels, it is kept with the dot until we know how to resolve it
+        Some((node, _)) => Endpoint { node : any(node).map(|n|format!("{}.", n)), half_edge : None },
+        None => Endpoint { node : any(s), half_edge : None },
+    }
+}
+
+/// Labels with more than one character are written in parentheses, as in the text format of problems.
+fn name(s : &str) -> String {
+    if s.chars().count() == 1 { s.to_owned() } else { format!("({})", s) }
+}
+
+fn label_name(v : &HalfEdgeView) -> String {
+    let s = [&v.input, &v.node, &v.half_edge].into_iter().flatten().join("_");
+    name(&s)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::algorithms::event::EventHandler;
+
+    use super::NodeCentredLcl;
+
+    #[test]
+    fn node_coloring() {
+        let lcl = NodeCentredLcl::parse("degree 3\nnode 1\nnode 2\nnode 3\nedge 1 2\nedge 1 3\nedge 2 3").unwrap();
+        let converted = lcl.to_problem().unwrap();
+        assert_eq!(format!("{}", converted.problem), "1^3\n2^3\n3^3\n\n1 2\n1 3\n2 3\n");
+        assert_eq!(converted.describe(0).unwrap(), "node 1");
+    }
+
+    #[test]
+    fn sinkless_orientation() {
+        // half-edge labels: O outgoing, I incoming
+        let lcl = NodeCentredLcl::parse("degree 3\nnode : O O O\nnode : O O I\nnode : O I I\nedge O I").unwrap();
+        let mut p = lcl.to_problem().unwrap().problem;
+        assert_eq!(p.active.lines.len(), 3);
+        p.compute_triviality(&mut EventHandler::null());
+        assert!(p.trivial_sets.as_ref().unwrap().is_empty());
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        // maximal independent set as radius-1 constraints: nodes in the set have no neighbor in the set, and the others have at least one
+        let lcl = NodeCentredLcl::parse("degree 2\nneighbourhood M : U U\nneighbourhood U : M U\nneighbourhood U : M M").unwrap();
+        let converted = lcl.to_problem().unwrap();
+        // the labels are M_U, U_M and U_U
+        assert_eq!(converted.problem.labels().len(), 3);
+        assert_eq!(converted.problem.passive.lines.len(), 2);
+    }
+
+    #[test]
+    fn inputs() {
+        // nodes with input a must output 1, and nodes with input b must output 2
+        let lcl = NodeCentredLcl::parse("degree 2\nnode [a] 1\nnode [b] 2\nedge 1 1\nedge 1 2\nedge 2 2").unwrap();
+        let mut converted = lcl.to_problem().unwrap();
+        assert!(converted.problem.input_given.is_some());
+        converted.problem.compute_triviality_given_input();
+        assert!(converted.problem.is_trivial_given_input());
+
+        // the input may assign a to two neighbors
+        let lcl = NodeCentredLcl::parse("degree 2\nnode [a] 1\nnode [b] 2\nedge 1 2").unwrap();
+        let mut converted = lcl.to_problem().unwrap();
+        converted.problem.compute_triviality_given_input();
+        assert!(!converted.problem.is_trivial_given_input());
+
+        // nodes with input b cannot output the labels of the nodes with input a
+        let lcl = NodeCentredLcl::parse("degree 2\nnode [a] 1\nnode [b] 2\nedge 1 1").unwrap();
+        let mut converted = lcl.to_problem().unwrap();
+        converted.problem.compute_triviality_given_input();
+        assert!(!converted.problem.is_trivial_given_input());
+    }
+}
@@ -7,7 +7,7 @@ use itertools::{iproduct, Itertools};
 use petgraph::{algo::toposort, Graph};
 use rustsat::{instances::SatInstance, types::{constraints::CardConstraint, Lit}};
 
-use crate::{algorithms::{diagram::{compute_direct_diagram, diagram_indirect_to_reachability_adj, diagram_to_indirect}, event::EventHandler, fixpoint::{expression_for_line_at, TreeNode}, problem_triviality::solve_sat}, group::{Group, GroupType, Label}, line::Line, part::Part, problem::Problem};
+use crate::{algorithms::{diagram::{compute_direct_diagram, diagram_indirect_to_reachability_adj, diagram_to_indirect}, event::EventHandler, fixpoint::{expression_for_line_at, TreeNode}, fixpoint_report::{FixpointFailure, FixpointObstruction}, problem_triviality::solve_sat}, group::{Group, GroupType, Label}, line::Line, part::Part, problem::Problem};
 
 
 
@@ -888,7 +888,7 @@ impl Context<Label> {
 
 impl Problem {
     
-    pub fn nofixpoint_find_algorithm(&self, exprs: &Vec<Expr<Label>>, context : &Context<Label>) -> Option<String> {
+    pub fn nofixpoint_find_algorithm(&self, exprs: &Vec<Expr<Label>>, context : &Context<Label>, eh : &mut EventHandler) -> Option<String> {
         println!("got zero round line, trying to get an algorithm");
         for e in exprs {
             println!("{}",e.convert(&context.mapping_label_text));
@@ -1085,7 +1085,8 @@ impl Problem {
 
         // extract the solution
         let ordering_flattened = ordering.iter().flat_map(|v|v.iter().cloned()).collect_vec();
-        if let Some(solution) = solve_sat(instance,&ordering_flattened) {
+        // the algorithm is only additional information for the failure, hence it is omitted if the solver fails
+        if let Ok(Some(solution)) = solve_sat(instance,&ordering_flattened,eh) {
             let true_lits : HashSet<_> = solution.into_iter().collect();
             let mut numbers = (0..numbers).collect_vec();
             numbers.sort_by(|&a,&b|{
@@ -1116,7 +1117,7 @@ impl Problem {
     }
 
 
-    fn nofixpoint(&self, eh : &mut EventHandler) -> Result<Problem,String> {
+    fn nofixpoint(&self, eh : &mut EventHandler) -> Result<Problem,FixpointFailure> {
         let degree = self.active.finite_degree();
 
         let mut context = Context::init_from_problem(self);
@@ -1139,27 +1140,11 @@ impl Problem {
                 if not_all_of_these.iter().all(|(m,e)|{
                     m.is_pred(e,&mut context.relations)
                 }) {
-                    println!("cannot get a fixed point!");
-                    let mut s = String::new();
-                    s += "No fixed point can be found. These expressions will be pairwise compatible with any diagram: ";
-                    let exprs = not_all_of_these.iter().map(|(_,e)|e).unique();
-                    for e in exprs {
-                        s += &format!("{}, ",e.convert(&context.mapping_label_text));
-                    }
-                    s += "\nOriginal expressions:\n";
-                    for e in original_expr[i].iter() {
-                        s += &format!("{}\n",e.convert(&context.mapping_label_text));
-                    }
-                    s += "\n";
-                    if let Some(algo) = self.nofixpoint_find_algorithm(&original_expr[i],&context) {
-                        s += "Obtained algorithm:\n";
-                        s += &algo;
-                        //return Err(s);
-                    } else {
-                        s += "Cannot convert it into an algorithm";
-                    }
-                    //println!("{}",s);
-                    return Err(s);
+                    let compatible = not_all_of_these.iter().map(|(_,e)|e).unique().map(|e|e.convert(&context.mapping_label_text).to_string()).collect();
+                    let original = original_expr[i].iter().map(|e|e.convert(&context.mapping_label_text).to_string()).collect();
+                    let algorithm = self.nofixpoint_find_algorithm(&original_expr[i],&context,eh);
+                    let obstruction = FixpointObstruction::Expressions { compatible, original, algorithm };
+                    return Err(FixpointFailure::new("No fixed point can be found.", vec![obstruction], self.mapping_label_text.clone(), false));
                 }
             }
 
@@ -1258,7 +1243,7 @@ impl Problem {
 
     
 
-    pub fn fixpoint_loop(&self, eh: &mut EventHandler) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), String> {
+    pub fn fixpoint_loop(&self, eh: &mut EventHandler) -> Result<(Self,Vec<(Label,Label)>,Vec<(Label,Label)>), FixpointFailure> {
         self.nofixpoint(eh).map(|p|(p,vec![],vec![]))
     }
 
@@ -0,0 +1,195 @@
+use std::collections::{BTreeSet, HashSet};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::Constraint, group::{Exponent, Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem};
+
+use super::event::EventHandler;
+
+/// The constraints for the degrees that are not the one of `active` and `passive`, for problems on graphs that are not regular, such as trees with leaves.
+/// Each constraint contains the configurations allowed on nodes of one degree, and `active` and `passive` of the problem are the ones of largest degree.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
+pub struct OtherDegrees {
+    pub active : Vec<Constraint>,
+    pub passive : Vec<Constraint>,
+}
+
+impl OtherDegrees {
+    /// Returns None if there are no other degrees, so that regular problems are always represented in the same way.
+    pub fn new(active : Vec<Constraint>, passive : Vec<Constraint>) -> Option<Self> {
+        if active.is_empty() && passive.is_empty() {
+            None
+        } else {
+            Some(Self { active, passive })
+        }
+    }
+
+    pub fn map<F>(&self, mut f : F) -> Self where F : FnMut(&Constraint) -> Constraint {
+        Self { active : self.active.iter().map(&mut f).collect(), passive : self.passive.iter().map(&mut f).collect() }
+    }
+
+    pub fn map_active<F>(&self, f : F) -> Self where F : FnMut(&Constraint) -> Constraint {
+        Self { active : self.active.iter().map(f).collect(), passive : self.passive.clone() }
+    }
+
+    pub fn map_passive<F>(&self, f : F) -> Self where F : FnMut(&Constraint) -> Constraint {
+        Self { active : self.active.clone(), passive : self.passive.iter().map(f).collect() }
+    }
+
+    pub fn swap_sides(&self) -> Self {
+        Self { active : self.passive.clone(), passive : self.active.clone() }
+    }
+}
+
+impl Problem {
+    pub fn is_regular(&self) -> bool {
+        self.other_degrees.is_none()
+    }
+
+    /// Operations that only look at `active` and `passive` call this, so that they do not silently ignore the other degrees.
+    pub(crate) fn require_regular(&self) -> Result<(), &'static str> {
+        if self.is_regular() {
+            Ok(())
+        } else {
+            Err("This operation only supports problems where all nodes have the same degree")
+        }
+    }
+
+    /// The active constraints of all degrees, starting from the one of largest degree.
+    pub fn active_constraints(&self) -> Vec<&Constraint> {
+        std::iter::once(&self.active).chain(self.other_degrees.iter().flat_map(|o|o.active.iter())).collect()
+    }
+
+    /// The passive constraints of all degrees, starting from the one of largest degree.
+    pub fn passive_constraints(&self) -> Vec<&Constraint> {
+        std::iter::once(&self.passive).chain(self.other_degrees.iter().flat_map(|o|o.passive.iter())).collect()
+    }
+
+    pub fn active_labels_appearing(&self) -> HashSet<Label> {
+        self.active_constraints().into_iter().flat_map(|c|c.labels_appearing()).collect()
+    }
+
+    pub fn passive_labels_appearing(&self) -> HashSet<Label> {
+        self.passive_constraints().into_iter().flat_map(|c|c.labels_appearing()).collect()
+    }
+
+    /// Maximizes the passive constraints of all degrees, see `Constraint::try_maximize` for the errors.
+    pub fn maximize_passive(&mut self, eh : &mut EventHandler) -> Result<(), String> {
+        self.passive.try_maximize(eh)?;
+        if let Some(other) = self.other_degrees.as_mut() {
+            for c in other.passive.iter_mut() {
+                c.try_maximize(eh)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Zero-round solvability for non-regular problems: each node chooses a configuration that depends only on its degree,
+    /// and since nodes of any degree may be neighbors of the same passive node, all the labels used in the chosen configurations must be usable together on passive nodes of any degree.
+    /// The trivial sets are the unions of the labels of the chosen configurations.
+    pub(crate) fn compute_triviality_nonregular(&mut self, eh : &mut EventHandler) {
+        if self.passive.degree != Degree::Finite(2) {
+            self.passive.maximize(eh);
+        }
+        if let Some(other) = self.other_degrees.as_mut() {
+            for c in other.passive.iter_mut().filter(|c|c.degree != Degree::Finite(2)) {
+                c.maximize(eh);
+            }
+        }
+
+        let passives = self.passive_constraints();
+        if passives.iter().any(|c|c.lines.is_empty()) {
+            self.trivial_sets = Some(vec![]);
+            return;
+        }
+
+        let good = |set : &BTreeSet<Label>| {
+            passives.iter().all(|c|{
+                let gtype = match c.degree {
+                    Degree::Finite(x) => GroupType::Many(x as Exponent),
+                    Degree::Star => GroupType::Star,
+                };
+                let line = Line { parts : vec![Part { gtype, group : Group::from(set.iter().cloned().collect()) }] };
+                c.includes(&line)
+            })
+        };
+
+        let actives = self.active_constraints();
+        let mut unions : Vec<BTreeSet<Label>> = vec![BTreeSet::new()];
+        for (i, active) in actives.iter().enumerate() {
+            eh.notify("triviality", i, actives.len());
+            let sets = active.minimal_sets_of_all_choices();
+            unions = unions.iter()
+                .cartesian_product(sets.iter())
+                .map(|(union, set)|union.iter().chain(set.iter()).cloned().collect::<BTreeSet<_>>())
+                .unique()
+                .filter(|union|good(union))
+                .collect();
+        }
+
+        self.trivial_sets = Some(unions.into_iter().map(|union|union.into_iter().collect()).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn nonregular_parse() {
+        let p = Problem::from_string_nonregular("A A A\nB B B\nA\nB\n\nA B").unwrap();
+        assert!(!p.is_regular());
+        assert_eq!(p.active.lines.len(), 2);
+        assert_eq!(p.other_degrees.as_ref().unwrap().active.len(), 1);
+        assert_eq!(format!("{}", p), "A^3\nB^3\nA\nB\n\nA B\n");
+
+        // non-regular problems must be requested explicitly
+        assert!(Problem::from_string("A A A\nB B B\nA\nB\n\nA B").is_err());
+        assert!(Problem::from_string_nonregular("A A A\nB B B\n\nA B").unwrap().is_regular());
+    }
+
+    #[test]
+    fn nonregular_unsupported() {
+        let p = Problem::from_string_nonregular("A A A\nB B B\nA\nB\n\nA B").unwrap();
+        assert!(p.dual_problem(&p, &mut EventHandler::null()).is_err());
+        assert!(p.inverse_speedup().is_err());
+        assert!(p.marks(&mut EventHandler::null()).is_err());
+    }
+
+    #[test]
+    fn nonregular_triviality() {
+        let eh = &mut EventHandler::null();
+
+        // leaves cannot use the label of the other nodes, and two leaves may be neighbors
+        let mut p = Problem::from_string_nonregular("A A A\nB\n\nA A\nA B").unwrap();
+        p.compute_triviality(eh);
+        assert!(p.trivial_sets.as_ref().unwrap().is_empty());
+
+        let mut p = Problem::from_string_nonregular("A A A\nB\n\nA A\nA B\nB B").unwrap();
+        p.compute_triviality(eh);
+        assert!(!p.trivial_sets.as_ref().unwrap().is_empty());
+
+        let mut p = Problem::from_string_nonregular("A A A\nB B B\nA\nB\n\nA B").unwrap();
+        p.compute_triviality(eh);
+        assert!(p.trivial_sets.as_ref().unwrap().is_empty());
+    }
+
+    #[test]
+    fn nonregular_speedup() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string_nonregular("A A A\nB B B\nA\nB\n\nA B").unwrap();
+        p.compute_partial_diagram(eh);
+        let sp = p.speedup(eh);
+        // the passive side of the new problem contains the constraints for leaves
+        let other = sp.other_degrees.as_ref().unwrap();
+        assert!(other.active.is_empty());
+        assert_eq!(other.passive.len(), 1);
+        assert_eq!(sp.labels().len(), 2);
+
+        let mut sp = sp;
+        sp.compute_triviality(eh);
+        assert!(sp.trivial_sets.as_ref().unwrap().is_empty());
+    }
+}
@@ -2,15 +2,14 @@ use std::collections::{HashMap, HashSet};
 
 use itertools::Itertools;
 use permutator::Permutation;
-use rustsat::types::{Lit, TernaryVal};
+use rustsat::types::Lit;
 use rustsat::{instances::SatInstance, types::constraints::CardConstraint};
-use rustsat::solvers::{Solve, SolverResult};
 
 use crate::{
     algorithms::mapping_problem::mapping_problem::MappingProblem, constraint::Constraint, group::{Exponent, Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::Problem
 };
 
-use super::event::EventHandler;
+use super::{event::EventHandler, incremental_triviality::IncrementalTriviality};
 
 impl Problem {
     pub fn compute_triviality(&mut self, eh: &mut EventHandler) {
@@ -18,6 +17,11 @@ impl Problem {
             panic!("triviality has been computed already");
         }
 
+        if !self.is_regular() {
+            self.compute_triviality_nonregular(eh);
+            return;
+        }
+
         if self.passive.degree != Degree::Finite(2) {
             self.passive.maximize(eh);
         }
@@ -54,21 +58,71 @@ impl Problem {
         self.trivial_sets = Some(trivial_sets);
     }
 
-    pub fn compute_triviality_with_input(&mut self, other:Problem, sat : bool) {
+    pub fn compute_triviality_with_input(&mut self, other:Problem, sat : bool) -> Result<(), &'static str> {
+        self.require_regular()?;
+        other.require_regular()?;
         if other.labels().len() == 0 {
             self.is_trivial_with_input = Some(true);
             self.triviality_with_input = Some((vec![],vec![]));
-            return;
+            return Ok(());
         }
         if sat {
-            self.compute_triviality_with_input_with_sat(other);
+            self.compute_triviality_with_input_with_sat(other)
         } else {
-            self.compute_triviality_with_input_without_sat(other);
+            self.compute_triviality_with_input_without_sat(other)
         }
     }
 
-    pub fn compute_triviality_with_input_without_sat(&mut self, other:Problem) {
-        
+    /// Attaches `input` to the problem, and forgets whether the problem is zero-round solvable given the previous input.
+    pub fn set_input(&mut self, input : Option<Problem>) {
+        self.input_given = input.map(Box::new);
+        self.input_allowed_outputs = None;
+        self.trivial_given_input = None;
+    }
+
+    /// The labels that a node can output given its input, in a problem obtained from this one where each label `l` became the labels `image(l)`.
+    pub fn allowed_outputs_after_relabelling<F>(&self, image : F) -> Option<Vec<(Label, Vec<Label>)>> where F : Fn(Label) -> Vec<Label> {
+        self.input_allowed_outputs.as_ref().map(|allowed|allowed.iter().map(|(i,outputs)|{
+            (*i, outputs.iter().flat_map(|&l|image(l)).unique().sorted().collect())
+        }).collect())
+    }
+
+    /// If an input is attached to the problem, checks whether the problem is zero-round solvable given that input.
+    /// The result is stored in `trivial_given_input`, and `is_trivial_with_input` and `triviality_with_input` are left untouched.
+    pub fn compute_triviality_given_input(&mut self) {
+        if self.trivial_given_input.is_some() {
+            return;
+        }
+        // the check enumerates the configurations, hence it requires finite degrees, and it ignores the other degrees of non-regular problems
+        if self.active.degree == Degree::Star || self.passive.degree == Degree::Star || !self.is_regular() {
+            return;
+        }
+        if let Some(input) = self.input_given.clone() {
+            if input.is_regular() && input.active.degree == self.active.degree && input.passive.degree == self.passive.degree {
+                let is_trivial_with_input = self.is_trivial_with_input.take();
+                let triviality_with_input = self.triviality_with_input.take();
+                // if the sat solver fails, the result stays unknown
+                let result = match self.input_allowed_outputs.clone() {
+                    Some(allowed) => self.compute_triviality_with_restricted_input_with_sat(*input, &allowed),
+                    None => self.compute_triviality_with_input(*input, true)
+                };
+                if result.is_ok() {
+                    self.trivial_given_input = self.is_trivial_with_input;
+                }
+                self.is_trivial_with_input = is_trivial_with_input;
+                self.triviality_with_input = triviality_with_input;
+            }
+        }
+    }
+
+    pub fn is_trivial_given_input(&self) -> bool {
+        self.input_given.is_some() && self.trivial_given_input == Some(true)
+    }
+
+    pub fn compute_triviality_with_input_without_sat(&mut self, other:Problem) -> Result<(), &'static str> {
+        self.require_regular()?;
+        other.require_regular()?;
+
         let mut mapping = MappingProblem::new(
             other.clone(),
             self.clone()
@@ -89,40 +143,25 @@ impl Problem {
         } else {
             self.is_trivial_with_input = Some(false);
         }
+        Ok(())
+    }
+
+    pub fn compute_triviality_with_input_with_sat(&mut self, input:Problem) -> Result<(), &'static str> {
+        self.compute_triviality_with_restricted_input_with_sat(input, &vec![])
     }
 
-    pub fn compute_triviality_with_input_with_sat(&mut self, input:Problem) {
+    /// Same as `compute_triviality_with_input_with_sat`, but each input label listed in `allowed` can only be mapped to the output labels listed with it.
+    pub fn compute_triviality_with_restricted_input_with_sat(&mut self, input:Problem, allowed : &Vec<(Label, Vec<Label>)>) -> Result<(), &'static str> {
+        self.require_regular()?;
+        input.require_regular()?;
         let (newinput,mapping) = input.make_all_labels_different();
 
         let squish_labels : HashMap<_,_> = self.labels().iter().enumerate().map(|(i,&l)|(l,i as Label)).collect();
 
-        let all_lines = |c : &Constraint, permutations : bool, squish : Option<&HashMap<Label,Label>>| -> Vec<Vec<Label>> {
-            c.all_choices(false).into_iter().flat_map(|line|{
-                let parts_to_labels = |parts : &Vec<Part>|{
-                    parts.iter().map(|part|{
-                        let l = part.group.first();
-                        if let Some(squish) = squish {
-                            squish[&l]
-                        } else {
-                            l
-                        }
-                    }).collect() 
-                };
-                let mut parts = line.parts.clone();
-                if !permutations {
-                    vec![parts_to_labels(&parts)].into_iter()
-                } else {
-                    parts.permutation().map(|perm|{
-                        parts_to_labels(&perm)
-                    }).collect::<Vec<_>>().into_iter()
-                }
-            }).unique().collect()
-        };
-
-        let input_active_lines = all_lines(&newinput.active, false, None);
-        let input_passive_lines = all_lines(&newinput.passive, false, None);
-        let problem_active_lines = all_lines(&self.active, true, Some(&squish_labels));
-        let problem_passive_lines = all_lines(&self.passive, true, Some(&squish_labels));
+        let input_active_lines = choice_lines(&newinput.active, false, None);
+        let input_passive_lines = choice_lines(&newinput.passive, false, None);
+        let problem_active_lines = choice_lines(&self.active, true, Some(&squish_labels));
+        let problem_passive_lines = choice_lines(&self.passive, true, Some(&squish_labels));
 
         let problem_num_labels = squish_labels.len();
         let input_num_labels = newinput.labels().len();
@@ -188,9 +227,20 @@ impl Problem {
             instance.add_card_constr(CardConstraint::new_eq(ith_label_mapped_to_jth_label[i].iter().cloned(),1));
         }
 
+        for (l,outputs) in allowed {
+            let outputs : HashSet<Label> = outputs.iter().filter_map(|o|squish_labels.get(o).cloned()).collect();
+            for &i in mapping.get(l).into_iter().flatten() {
+                for j in 0..problem_num_labels {
+                    if !outputs.contains(&(j as Label)) {
+                        instance.add_unit(!ith_label_mapped_to_jth_label[i as usize][j]);
+                    }
+                }
+            }
+        }
+
         let instance = instance.sanitize();
         let lits : Vec<_> = ith_label_mapped_to_jth_label.iter().flat_map(|v|v.into_iter().cloned()).collect();
-        if let Some(solution) = solve_sat(instance, &lits) {
+        if let Some(solution) = solve_sat(instance, &lits, &mut EventHandler::null())? {
             let true_lits : HashSet<_> = solution.into_iter().collect();
             let mut label_mapping = vec![];
 
@@ -220,10 +270,7 @@ impl Problem {
         } else {
             self.is_trivial_with_input = Some(false);
         }
-        
-
-
-
+        Ok(())
     }
 
     pub fn make_all_labels_different(&self) -> (Self,HashMap<Label,Vec<Label>>) {
@@ -314,21 +361,29 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees : None
         },map)
     }
     
-    pub fn compute_subinput_that_gives_nontriviality_aux<F>(&mut self, input : Problem, sat : bool, smallest : Label, seen : &mut HashMap<Problem,Label>, f : &mut F) -> Option<Problem> where F : FnMut(Problem) {
+    pub fn compute_subinput_that_gives_nontriviality_aux<F>(&mut self, input : Problem, sat : bool, smallest : Label, seen : &mut HashMap<Problem,Label>, incremental : &mut Option<IncrementalTriviality>, f : &mut F) -> Result<Option<Problem>, &'static str> where F : FnMut(Problem) {
         if let Some(x) = seen.get(&input) {
             if *x <= smallest {
-                return None;
+                return Ok(None);
             }
         }
         seen.insert(input.clone(), smallest);
 
-        self.compute_triviality_with_input(input.clone(), sat);
+        if let Some(incremental) = incremental.as_mut() {
+            self.compute_triviality_with_input_incremental(input.clone(), incremental)?;
+        } else {
+            self.compute_triviality_with_input(input.clone(), sat)?;
+        }
         if self.is_trivial_with_input.unwrap() {
-            return None;
+            return Ok(None);
         }
 
         let mut labels = input.labels();
@@ -344,58 +399,63 @@ impl Problem {
             let subset : HashSet<_> = labels.iter().cloned().filter(|&x|x!=l).collect();
             let mut subinput = input.harden_keep(&subset, true);
             subinput.discard_useless_stuff(false, &mut EventHandler::null());
-            if let Some(p) = self.compute_subinput_that_gives_nontriviality_aux(subinput.clone(),sat,l+1, seen, f) {    
+            if let Some(p) = self.compute_subinput_that_gives_nontriviality_aux(subinput.clone(),sat,l+1, seen, incremental, f)? {    
                 if p.labels().len() < best.labels().len() {
                     best = p;
                 }   
             }
         }
 
-        Some(best)
+        Ok(Some(best))
     }
 
-    pub fn compute_subinput_that_gives_nontriviality<F>(&mut self, input : Problem, sat : bool, mut f : F) -> Option<Problem> where F : FnMut(Problem){
+    pub fn compute_subinput_that_gives_nontriviality<F>(&mut self, input : Problem, sat : bool, mut f : F) -> Result<Option<Problem>, &'static str> where F : FnMut(Problem){
+        self.require_regular()?;
+        input.require_regular()?;
         let mut seen = HashMap::new();
-        self.compute_subinput_that_gives_nontriviality_aux(input, sat, 0, &mut seen,&mut f)
+        // all the subinputs are obtained by hardening the input, possibly after adding predecessors, hence the sat instance can be built only once
+        let mut incremental = if sat {
+            let mut universe = vec![input.clone()];
+            if input.diagram_indirect.is_some() {
+                universe.push(input.harden_keep(&input.labels().into_iter().collect(), true));
+            }
+            Some(IncrementalTriviality::new(self, &universe)?)
+        } else {
+            None
+        };
+        self.compute_subinput_that_gives_nontriviality_aux(input, sat, 0, &mut seen, &mut incremental, &mut f)
     }
 }
 
-pub fn solve_sat(instance : SatInstance, lits : &[Lit]) -> Option<Vec<Lit>> {
-
-
-    #[cfg(feature = "all")]
-    {
-        let mut solver = rustsat_minisat::core::Minisat::default();
-        solver.add_cnf(instance.into_cnf().0).unwrap();
-        let res = solver.solve().unwrap();
-        if res != SolverResult::Sat {
-            return None;
+/// Returns all the lines that can be obtained by picking one label from each group, as vectors of labels.
+/// If `permutations` is true, all the orderings of each line are returned.
+/// If `squish` is given, labels are renamed according to it.
+pub(crate) fn choice_lines(c : &Constraint, permutations : bool, squish : Option<&HashMap<Label,Label>>) -> Vec<Vec<Label>> {
+    c.all_choices(false).into_iter().flat_map(|line|{
+        let parts_to_labels = |parts : &Vec<Part>|{
+            parts.iter().map(|part|{
+                let l = part.group.first();
+                if let Some(squish) = squish {
+                    squish[&l]
+                } else {
+                    l
+                }
+            }).collect() 
+        };
+        let mut parts = line.parts.clone();
+        if !permutations {
+            vec![parts_to_labels(&parts)].into_iter()
+        } else {
+            parts.permutation().map(|perm|{
+                parts_to_labels(&perm)
+            }).collect::<Vec<_>>().into_iter()
         }
-        let solution = solver.full_solution().unwrap();
-        let true_vars = lits.iter().filter(|lit|{
-            solution.lit_value(**lit) == TernaryVal::True
-        }).cloned().collect_vec();
-        return Some(true_vars);        
-    }
+    }).unique().collect()
+}
 
-    #[cfg(feature = "onlyrust")]
-    {
-        let mut instance = instance;
-        let mut dimacs = std::io::BufWriter::new(Vec::new());
-        instance.convert_to_cnf();
-        instance.write_dimacs(&mut dimacs).unwrap();
-        let dimacs = dimacs.into_inner().unwrap();
-        let mut solver = varisat::solver::Solver::new();
-        solver.add_dimacs_cnf(&dimacs[..]).unwrap();
-        let solution = solver.solve().unwrap();
-        if !solution {
-            return None;
-        }
-        let lits = solver.model().unwrap().into_iter().filter(|lit|lit.is_positive());
-        let lits = lits.map(|lit|Lit::new(lit.to_dimacs() as u32, false)).collect();
-        
-        return Some(lits);
-    }
+/// Same as `sat::solve`, the details of the error are printed on stderr.
+pub fn solve_sat(instance : SatInstance, lits : &[Lit], eh : &mut EventHandler) -> Result<Option<Vec<Lit>>, &'static str> {
+    super::sat::solve(instance, lits, eh).map_err(super::sat::report_error)
 }
 
 
@@ -427,11 +487,46 @@ mod tests {
     fn triviality_sat(){
         let mut p = Problem::from_string("M M M\nP U U\n\nM UP\nU U").unwrap();
         let input = Problem::from_string("A A A\nB B B\n\nA B").unwrap();
-        p.compute_triviality_with_input_with_sat(input);
+        p.compute_triviality_with_input_with_sat(input).unwrap();
 
         let mut p = Problem::from_string("M M M\nP U U\n\nM UP\nU U").unwrap();
         let input = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
-        p.compute_triviality_with_input_with_sat(input);
+        p.compute_triviality_with_input_with_sat(input).unwrap();
+    }
+
+    #[test]
+    fn triviality_given_input(){
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A A A\nB B B\n\nA B").unwrap();
+        let input = Problem::from_string("X X X\nY Y Y\n\nX Y").unwrap();
+        p.set_input(Some(input));
+        p.compute_triviality(eh);
+        p.compute_triviality_given_input();
+        assert!(p.trivial_sets.as_ref().unwrap().is_empty());
+        assert!(p.is_trivial_given_input());
+
+        p.compute_partial_diagram(eh);
+        let mut sp = p.speedup(eh);
+        let input = sp.input_given.as_ref().unwrap();
+        assert_eq!(input.active.degree, sp.active.degree);
+        assert_eq!(input.passive.degree, sp.passive.degree);
+        sp.compute_triviality_given_input();
+        assert!(sp.trivial_given_input.is_some());
+
+        // checking other inputs does not change the result for the attached one
+        let no_input = Problem::from_string("X X X\n\nX X").unwrap();
+        p.compute_triviality_with_input(no_input.clone(), true).unwrap();
+        assert_eq!(p.is_trivial_with_input, Some(false));
+        assert!(p.is_trivial_given_input());
+        p.compute_triviality_given_input();
+        assert!(p.is_trivial_given_input());
+
+        // changing the input forgets the result
+        p.set_input(Some(no_input));
+        assert_eq!(p.trivial_given_input, None);
+        p.compute_triviality_given_input();
+        assert_eq!(p.trivial_given_input, Some(false));
+        assert_eq!(p.is_trivial_with_input, Some(false));
     }
 
 }
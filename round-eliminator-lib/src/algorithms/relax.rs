@@ -11,6 +11,7 @@ impl Problem {
     pub fn relax_merge_group(&self, from : &Vec<Label>, to: Label) -> Self {
         let active = self.active.relax_group(from, to, true);
         let passive = self.passive.relax_group(from, to, true);
+        let other_degrees = self.other_degrees.as_ref().map(|other|other.map(|c|c.relax_group(from, to, true)));
 
         Problem {
             active,
@@ -34,13 +35,18 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs : self.allowed_outputs_after_relabelling(|l|vec![if from.contains(&l) { to } else { l }]),
+            other_degrees
         }
     }
 
     pub fn relax_merge(&self, from: Label, to: Label) -> Self {
         let active = self.active.relax(from, to, true);
         let passive = self.passive.relax(from, to, true);
+        let other_degrees = self.other_degrees.as_ref().map(|other|other.map(|c|c.relax(from, to, true)));
 
         Problem {
             active,
@@ -64,19 +70,31 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs : self.allowed_outputs_after_relabelling(|l|vec![if l == from { to } else { l }]),
+            other_degrees
         }
     }
 
     pub fn relax_many_merges(&self, merges : &Vec<(Label,Label)>) -> Self {
         let mut active = self.active.clone();
         let mut passive = self.passive.clone();
+        let mut other_degrees = self.other_degrees.clone();
         let mut merges = merges.clone();
+        let mut image : HashMap<Label, Label> = HashMap::new();
         for i in 0..merges.len() {
             let (from,to) = merges[i];
             if from != to {
+                for l in self.labels() {
+                    if image.get(&l).cloned().unwrap_or(l) == from {
+                        image.insert(l, to);
+                    }
+                }
                 active = active.relax(from, to, true);
                 passive = passive.relax(from, to, true);
+                other_degrees = other_degrees.map(|other|other.map(|c|c.relax(from, to, true)));
                 for j in i+1..merges.len() {
                     if merges[j].0 == from {
                         merges[j].0 = to;
@@ -110,7 +128,11 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs : self.allowed_outputs_after_relabelling(|l|vec![image.get(&l).cloned().unwrap_or(l)]),
+            other_degrees
         }
     }
 
@@ -118,8 +140,10 @@ impl Problem {
         let diagram = self.diagram_indirect_to_reachability_adj();
         let succ : Vec<_> = diagram[&to].iter().cloned().collect();
         let mut passive = self.passive.clone();
+        let mut other_degrees = self.other_degrees.clone();
         for l in succ {
             passive = passive.relax(from, l, false);
+            other_degrees = other_degrees.map(|other|other.map_passive(|c|c.relax(from, l, false)));
         }
 
         Problem {
@@ -144,7 +168,11 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.clone(),
+            input_allowed_outputs : self.input_allowed_outputs.clone(),
+            other_degrees
         }
     }
 }
@@ -0,0 +1,452 @@
+use std::{fmt::Display, io::Write, sync::{atomic::{AtomicUsize, Ordering}, OnceLock}, time::Instant};
+
+use itertools::Itertools;
+use rustsat::{instances::{Cnf, SatInstance}, types::Lit};
+use serde::{Deserialize, Serialize};
+
+use super::event::EventHandler;
+
+/// Which sat solver is used.
+/// `Minisat` is available with the `all` feature, `Varisat` with the `onlyrust` feature,
+/// and `External` runs a solver binary that reads DIMACS and prints the result in the format of the SAT competition.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SatBackend {
+    Minisat,
+    Varisat,
+    External(String),
+}
+
+impl Default for SatBackend {
+    fn default() -> Self {
+        if cfg!(feature = "all") {
+            SatBackend::Minisat
+        } else {
+            SatBackend::Varisat
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SatConfig {
+    pub backend : SatBackend,
+    /// Additional arguments passed to the external solver, before the name of the cnf file.
+    pub external_args : Vec<String>,
+    /// If set, every generated cnf is written in this directory.
+    pub dump_cnf : Option<String>,
+    /// If set, statistics are reported after each call, through the event handler, or on stderr if there is none.
+    pub print_stats : bool,
+}
+
+impl SatConfig {
+    /// Reads the configuration from the environment:
+    /// RE_SAT_BACKEND can be minisat, varisat, or the path of a solver binary,
+    /// RE_SAT_ARGS contains whitespace separated arguments for the external solver,
+    /// RE_SAT_DUMP is a directory where cnf files are stored,
+    /// RE_SAT_STATS, if set, enables reporting statistics.
+    pub fn from_env() -> Self {
+        let backend = match std::env::var("RE_SAT_BACKEND") {
+            Ok(s) if s == "minisat" => SatBackend::Minisat,
+            Ok(s) if s == "varisat" => SatBackend::Varisat,
+            Ok(s) if !s.is_empty() => SatBackend::External(s),
+            _ => SatBackend::default()
+        };
+        let external_args = std::env::var("RE_SAT_ARGS").map(|s|s.split_whitespace().map(|x|x.to_owned()).collect()).unwrap_or_default();
+        let dump_cnf = std::env::var("RE_SAT_DUMP").ok();
+        let print_stats = std::env::var("RE_SAT_STATS").is_ok();
+        Self { backend, external_args, dump_cnf, print_stats }
+    }
+
+    /// The configuration read from the environment the first time it is needed.
+    pub fn cached_from_env() -> &'static Self {
+        static CONFIG : OnceLock<SatConfig> = OnceLock::new();
+        CONFIG.get_or_init(Self::from_env)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SatStats {
+    pub backend : String,
+    pub vars : usize,
+    pub clauses : usize,
+    pub satisfiable : bool,
+    pub millis : u128,
+    pub dumped_to : Option<String>,
+}
+
+impl Display for SatStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sat solver {}: {} variables, {} clauses, {} in {} ms", self.backend, self.vars, self.clauses, if self.satisfiable {"SAT"} else {"UNSAT"}, self.millis)?;
+        if let Some(file) = &self.dumped_to {
+            write!(f, ", cnf written to {}", file)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait SatSolver {
+    fn name(&self) -> String;
+    /// Returns None if the formula is unsatisfiable, otherwise the value of each of the `vars` variables.
+    fn solve(&mut self, cnf : Cnf, dimacs : &str, vars : usize) -> Result<Option<Vec<bool>>, String>;
+}
+
+#[cfg(feature = "all")]
+pub struct MinisatSolver;
+
+#[cfg(feature = "all")]
+impl SatSolver for MinisatSolver {
+    fn name(&self) -> String {
+        "minisat".into()
+    }
+
+    fn solve(&mut self, cnf : Cnf, _dimacs : &str, vars : usize) -> Result<Option<Vec<bool>>, String> {
+        use rustsat::solvers::{Solve, SolverResult};
+        use rustsat::types::{TernaryVal, Var};
+
+        let mut solver = rustsat_minisat::core::Minisat::default();
+        solver.add_cnf(cnf).map_err(|e|e.to_string())?;
+        let res = solver.solve().map_err(|e|e.to_string())?;
+        if res != SolverResult::Sat {
+            return Ok(None);
+        }
+        let solution = solver.full_solution().map_err(|e|e.to_string())?;
+        Ok(Some((0..vars).map(|i|solution.var_value(Var::new(i as u32)) == TernaryVal::True).collect()))
+    }
+}
+
+#[cfg(feature = "onlyrust")]
+pub struct VarisatSolver;
+
+#[cfg(feature = "onlyrust")]
+impl SatSolver for VarisatSolver {
+    fn name(&self) -> String {
+        "varisat".into()
+    }
+
+    fn solve(&mut self, _cnf : Cnf, dimacs : &str, vars : usize) -> Result<Option<Vec<bool>>, String> {
+        let mut solver = varisat::solver::Solver::new();
+        solver.add_dimacs_cnf(dimacs.as_bytes()).map_err(|e|e.to_string())?;
+        if !solver.solve().map_err(|e|e.to_string())? {
+            return Ok(None);
+        }
+        let mut values = vec![false; vars];
+        for lit in solver.model().unwrap() {
+            let idx = lit.to_dimacs().unsigned_abs() - 1;
+            if idx < vars {
+                values[idx] = lit.is_positive();
+            }
+        }
+        Ok(Some(values))
+    }
+}
+
+pub struct ExternalSolver {
+    pub path : String,
+    pub args : Vec<String>,
+}
+
+impl SatSolver for ExternalSolver {
+    fn name(&self) -> String {
+        self.path.clone()
+    }
+
+    fn solve(&mut self, _cnf : Cnf, dimacs : &str, vars : usize) -> Result<Option<Vec<bool>>, String> {
+        let file = write_cnf_file(&std::env::temp_dir().to_string_lossy(), dimacs)?;
+        let output = std::process::Command::new(&self.path)
+            .args(&self.args)
+            .arg(&file)
+            .output()
+            .map_err(|e|format!("cannot run {}: {}", self.path, e));
+        let _ = std::fs::remove_file(&file);
+        let output = output?;
+        parse_solver_output(&String::from_utf8_lossy(&output.stdout), output.status.code(), vars)
+    }
+}
+
+/// Parses the output of a solver that follows the SAT competition format (lines starting with "s" and "v").
+pub fn parse_solver_output(output : &str, exit_code : Option<i32>, vars : usize) -> Result<Option<Vec<bool>>, String> {
+    let mut satisfiable = match exit_code {
+        Some(10) => Some(true),
+        Some(20) => Some(false),
+        _ => None
+    };
+    let mut values = vec![false; vars];
+    for line in output.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("s") => {
+                match tokens.next() {
+                    Some("SATISFIABLE") => { satisfiable = Some(true); },
+                    Some("UNSATISFIABLE") => { satisfiable = Some(false); },
+                    _ => {}
+                }
+            }
+            Some("v") => {
+                for token in tokens {
+                    let x : i64 = token.parse().map_err(|_|format!("invalid literal in solver output: {}", token))?;
+                    if x != 0 && ((x.unsigned_abs() as usize) <= vars) {
+                        values[x.unsigned_abs() as usize - 1] = x > 0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    match satisfiable {
+        Some(true) => Ok(Some(values)),
+        Some(false) => Ok(None),
+        None => Err("the solver did not report whether the formula is satisfiable".into())
+    }
+}
+
+pub fn cnf_to_dimacs(cnf : &Cnf, vars : usize) -> String {
+    let mut s = format!("p cnf {} {}\n", vars, cnf.len());
+    for clause in cnf.iter() {
+        s += &clause.iter().map(|lit|lit.to_ipasir().to_string()).join(" ");
+        s += " 0\n";
+    }
+    s
+}
+
+static CNF_COUNTER : AtomicUsize = AtomicUsize::new(0);
+
+fn write_cnf_file(dir : &str, dimacs : &str) -> Result<String, String> {
+    let n = CNF_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = std::path::Path::new(dir).join(format!("re-{}-{}.cnf", std::process::id(), n));
+    let mut file = std::fs::File::create(&path).map_err(|e|format!("cannot create {}: {}", path.display(), e))?;
+    file.write_all(dimacs.as_bytes()).map_err(|e|e.to_string())?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+fn solver_for(config : &SatConfig) -> Result<Box<dyn SatSolver>, String> {
+    match &config.backend {
+        #[cfg(feature = "all")]
+        SatBackend::Minisat => Ok(Box::new(MinisatSolver)),
+        #[cfg(feature = "onlyrust")]
+        SatBackend::Varisat => Ok(Box::new(VarisatSolver)),
+        SatBackend::External(path) => Ok(Box::new(ExternalSolver{ path : path.clone(), args : config.external_args.clone() })),
+        #[allow(unreachable_patterns)]
+        backend => Err(format!("the sat backend {:?} has not been compiled in", backend))
+    }
+}
+
+/// Solves the given instance with the configured backend.
+/// If it is satisfiable, it returns the subset of `lits` that are true in the solution, together with the statistics of the call.
+pub fn solve_with_config(instance : SatInstance, lits : &[Lit], config : &SatConfig) -> Result<(Option<Vec<Lit>>,SatStats), String> {
+    let (cnf, _) = instance.into_cnf();
+    let vars = cnf.iter().flat_map(|clause|clause.iter().map(|lit|lit.var().idx() + 1))
+        .chain(lits.iter().map(|lit|lit.var().idx() + 1))
+        .max()
+        .unwrap_or(0);
+    let clauses = cnf.len();
+    let dimacs = cnf_to_dimacs(&cnf, vars);
+
+    let dumped_to = match &config.dump_cnf {
+        Some(dir) => Some(write_cnf_file(dir, &dimacs)?),
+        None => None
+    };
+
+    let mut solver = solver_for(config)?;
+    let start = Instant::now();
+    let values = solver.solve(cnf, &dimacs, vars)?;
+    let stats = SatStats {
+        backend : solver.name(),
+        vars,
+        clauses,
+        satisfiable : values.is_some(),
+        millis : start.elapsed().as_millis(),
+        dumped_to
+    };
+    let solution = values.map(|values|{
+        lits.iter().filter(|lit|values[lit.var().idx()] != lit.is_neg()).cloned().collect()
+    });
+    Ok((solution, stats))
+}
+
+/// Used by the functions whose errors are static strings: the details of the error are printed on stderr.
+pub fn report_error(e : String) -> &'static str {
+    eprintln!("sat solver failed: {}", e);
+    "The sat solver failed, the details have been printed on the standard error"
+}
+
+/// Sends the statistics of a call to `eh`, or prints them on stderr if `eh` discards them.
+fn report_stats(stats : String, eh : &mut EventHandler) {
+    if eh.is_null() {
+        eprintln!("{}", stats);
+    } else {
+        eh.notify(stats, 0, 0);
+    }
+}
+
+/// Solves the given instance with the backend configured through the environment, see `SatConfig::from_env`.
+/// An error is returned if the backend is not available or if it fails.
+/// If RE_SAT_STATS is set, the statistics are reported through `eh`.
+pub fn solve(instance : SatInstance, lits : &[Lit], eh : &mut EventHandler) -> Result<Option<Vec<Lit>>, String> {
+    let config = SatConfig::cached_from_env();
+    let (solution, stats) = solve_with_config(instance, lits, config)?;
+    if config.print_stats {
+        report_stats(stats.to_string(), eh);
+    }
+    Ok(solution)
+}
+
+/// A solver that keeps its clauses between calls, and that is queried under different assumptions.
+pub trait IncrementalSatSolver {
+    fn name(&self) -> String;
+    /// Returns None if the formula is unsatisfiable when all the assumptions are true, otherwise the value of each variable.
+    fn solve_assuming(&mut self, assumptions : &[Lit]) -> Result<Option<Vec<bool>>, String>;
+}
+
+#[cfg(feature = "all")]
+pub struct MinisatIncremental {
+    solver : rustsat_minisat::core::Minisat,
+    vars : usize,
+}
+
+#[cfg(feature = "all")]
+impl IncrementalSatSolver for MinisatIncremental {
+    fn name(&self) -> String {
+        "minisat".into()
+    }
+
+    fn solve_assuming(&mut self, assumptions : &[Lit]) -> Result<Option<Vec<bool>>, String> {
+        use rustsat::solvers::{Solve, SolveIncremental, SolverResult};
+        use rustsat::types::{TernaryVal, Var};
+
+        let res = self.solver.solve_assumps(assumptions).map_err(|e|e.to_string())?;
+        if res != SolverResult::Sat {
+            return Ok(None);
+        }
+        let solution = self.solver.full_solution().map_err(|e|e.to_string())?;
+        Ok(Some((0..self.vars).map(|i|solution.var_value(Var::new(i as u32)) == TernaryVal::True).collect()))
+    }
+}
+
+#[cfg(feature = "onlyrust")]
+pub struct VarisatIncremental {
+    solver : varisat::solver::Solver<'static>,
+    vars : usize,
+}
+
+#[cfg(feature = "onlyrust")]
+impl IncrementalSatSolver for VarisatIncremental {
+    fn name(&self) -> String {
+        "varisat".into()
+    }
+
+    fn solve_assuming(&mut self, assumptions : &[Lit]) -> Result<Option<Vec<bool>>, String> {
+        let assumptions = assumptions.iter().map(|lit|varisat::Lit::from_dimacs(lit.to_ipasir() as isize)).collect_vec();
+        self.solver.assume(&assumptions);
+        if !self.solver.solve().map_err(|e|e.to_string())? {
+            return Ok(None);
+        }
+        let mut values = vec![false; self.vars];
+        for lit in self.solver.model().unwrap() {
+            let idx = lit.to_dimacs().unsigned_abs() - 1;
+            if idx < self.vars {
+                values[idx] = lit.is_positive();
+            }
+        }
+        Ok(Some(values))
+    }
+}
+
+/// Used for solvers that are not incremental: on each call the formula is solved from scratch, with the assumptions added as unit clauses.
+pub struct RerunIncremental {
+    solver : Box<dyn SatSolver>,
+    cnf : Cnf,
+    vars : usize,
+}
+
+impl IncrementalSatSolver for RerunIncremental {
+    fn name(&self) -> String {
+        self.solver.name()
+    }
+
+    fn solve_assuming(&mut self, assumptions : &[Lit]) -> Result<Option<Vec<bool>>, String> {
+        let mut cnf = self.cnf.clone();
+        for &lit in assumptions {
+            cnf.add_unit(lit);
+        }
+        let dimacs = cnf_to_dimacs(&cnf, self.vars);
+        self.solver.solve(cnf, &dimacs, self.vars)
+    }
+}
+
+/// An instance that is encoded once and then solved many times under different assumptions.
+pub struct IncrementalSat {
+    solver : Box<dyn IncrementalSatSolver>,
+    vars : usize,
+    clauses : usize,
+    print_stats : bool,
+}
+
+impl IncrementalSat {
+    pub fn with_config(instance : SatInstance, config : &SatConfig) -> Result<Self, String> {
+        let (cnf, _) = instance.into_cnf();
+        let vars = cnf.iter().flat_map(|clause|clause.iter().map(|lit|lit.var().idx() + 1)).max().unwrap_or(0);
+        let clauses = cnf.len();
+
+        if let Some(dir) = &config.dump_cnf {
+            write_cnf_file(dir, &cnf_to_dimacs(&cnf, vars))?;
+        }
+
+        let solver : Box<dyn IncrementalSatSolver> = match &config.backend {
+            #[cfg(feature = "all")]
+            SatBackend::Minisat => {
+                use rustsat::solvers::Solve;
+                let mut solver = rustsat_minisat::core::Minisat::default();
+                solver.add_cnf(cnf).map_err(|e|e.to_string())?;
+                Box::new(MinisatIncremental { solver, vars })
+            }
+            #[cfg(feature = "onlyrust")]
+            SatBackend::Varisat => {
+                let mut solver = varisat::solver::Solver::new();
+                solver.add_dimacs_cnf(cnf_to_dimacs(&cnf, vars).as_bytes()).map_err(|e|e.to_string())?;
+                Box::new(VarisatIncremental { solver, vars })
+            }
+            _ => Box::new(RerunIncremental { solver : solver_for(config)?, cnf, vars })
+        };
+
+        Ok(Self { solver, vars, clauses, print_stats : config.print_stats })
+    }
+
+    /// Creates the solver with the backend configured through the environment, see `SatConfig::from_env`.
+    /// All the literals that are later used as assumptions or queried must appear in the instance.
+    pub fn new(instance : SatInstance) -> Result<Self, String> {
+        Self::with_config(instance, SatConfig::cached_from_env())
+    }
+
+    /// If the instance is satisfiable when all the `assumptions` are true, it returns the subset of `lits` that are true in the solution.
+    /// If statistics are enabled, they are reported through `eh`.
+    pub fn solve(&mut self, assumptions : &[Lit], lits : &[Lit], eh : &mut EventHandler) -> Result<Option<Vec<Lit>>, String> {
+        let start = Instant::now();
+        let values = self.solver.solve_assuming(assumptions)?;
+        if self.print_stats {
+            let stats = SatStats {
+                backend : self.solver.name(),
+                vars : self.vars,
+                clauses : self.clauses,
+                satisfiable : values.is_some(),
+                millis : start.elapsed().as_millis(),
+                dumped_to : None
+            };
+            report_stats(format!("{}, {} assumptions", stats, assumptions.len()), eh);
+        }
+        Ok(values.map(|values|{
+            lits.iter().filter(|lit|values.get(lit.var().idx()).map_or(false, |&v| v != lit.is_neg())).cloned().collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_solver_output;
+
+    #[test]
+    fn solver_output() {
+        let out = "c comment\ns SATISFIABLE\nv 1 -2\nv 3 0\n";
+        assert_eq!(parse_solver_output(out, Some(10), 3).unwrap(), Some(vec![true, false, true]));
+        assert_eq!(parse_solver_output("s UNSATISFIABLE\n", Some(20), 3).unwrap(), None);
+        assert!(parse_solver_output("", Some(1), 3).is_err());
+    }
+}
@@ -3,19 +3,50 @@ use std::collections::HashMap;
 use itertools::Itertools;
 
 use crate::{
+    constraint::Constraint,
     group::{Group, Label},
     problem::Problem,
 };
 
-use super::event::EventHandler;
+use super::{event::EventHandler, nonregular::OtherDegrees};
+
+/// Whether `speedup` and `dual_problem` should exploit the automorphisms of the problem, enabled by setting the environment variable RE_SYMMETRY to 1.
+/// The search for automorphisms has a cost even when there are none, and `maximize_symmetric` runs on a single machine and does not use checkpoints or the memory budget.
+pub(crate) fn symmetry_from_env() -> bool {
+    std::env::var("RE_SYMMETRY").map_or(false, |s|s == "1")
+}
 
 impl Problem {
+    /// Same as `try_speedup`, for the callers that cannot handle errors.
     pub fn speedup(&self, eh: &mut EventHandler) -> Self {
+        match self.try_speedup(eh) {
+            Ok(p) => p,
+            Err(e) => panic!("{}", e)
+        }
+    }
+
+    /// Applies round elimination, failing if maximizing the passive side fails, see `Constraint::try_maximize`.
+    pub fn try_speedup(&self, eh: &mut EventHandler) -> Result<Self, String> {
         let mut newactive_before_renaming = self.passive.clone();
-        newactive_before_renaming.maximize(eh);
+        if symmetry_from_env() {
+            // symmetric copies of the lines are obtained by permuting labels instead of combining lines again
+            let generators = self.automorphism_generators();
+            newactive_before_renaming.maximize_symmetric(&generators, eh);
+        } else {
+            newactive_before_renaming.try_maximize(eh)?;
+        }
+
+        // for non-regular problems, the constraints of each degree are handled separately, but the new labels are shared
+        let mut other_before_renaming = self.other_degrees.clone();
+        if let Some(other) = other_before_renaming.as_mut() {
+            for c in other.passive.iter_mut() {
+                c.try_maximize(eh)?;
+            }
+        }
 
         let mapping_label_oldlabels: Vec<_> = newactive_before_renaming
             .groups()
+            .chain(other_before_renaming.iter().flat_map(|other|other.passive.iter().flat_map(|c|c.groups())))
             .unique()
             .map(|g| g.as_vec())
             .sorted_by_key(|v| v.iter().cloned().rev().collect::<Vec<Label>>())
@@ -27,9 +58,8 @@ impl Problem {
             .map(|(a, b)| (b.clone(), *a))
             .collect();
 
-        let active = newactive_before_renaming.edited(|g| Group::from(vec![h_oldlabels_label[&g.as_vec()]]));
-
-        let passive = self.active.edited(|g| {
+        let rename = |c : &Constraint| c.edited(|g| Group::from(vec![h_oldlabels_label[&g.as_vec()]]));
+        let existential = |c : &Constraint| c.edited(|g| {
             let h = g.as_set();
             let ng = mapping_label_oldlabels
                 .iter()
@@ -40,6 +70,16 @@ impl Problem {
             Group::from(ng)
         });
 
+        let active = rename(&newactive_before_renaming);
+        let passive = existential(&self.active);
+        let other_degrees = other_before_renaming.map(|other|OtherDegrees {
+            active : other.passive.iter().map(rename).collect(),
+            passive : other.active.iter().map(existential).collect(),
+        });
+
+        // the input is now on the passive side, where a node can output any label that contains a label that it could output before
+        let input_allowed_outputs = self.allowed_outputs_after_relabelling(|l|mapping_label_oldlabels.iter().filter(|(_, o)|o.contains(&l)).map(|p|p.0).collect());
+
         let mut p = Problem {
             active,
             passive,
@@ -62,10 +102,14 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : self.input_given.as_ref().map(|input|Box::new(input.swap_sides())),
+            input_allowed_outputs,
+            other_degrees
         };
         p.assign_chars();
-        p
+        Ok(p)
     }
 
     pub fn assign_chars(&mut self) {
@@ -0,0 +1,68 @@
+use std::{path::PathBuf, sync::atomic::{AtomicUsize, Ordering}};
+
+use crate::line::{CompressedLine, Line};
+
+/// A bound on the memory used by `maximize`.
+#[derive(Clone, Debug)]
+pub struct MemoryBudget {
+    pub bytes : usize,
+    /// Where partitions of lines are written when the budget is reached.
+    pub dir : PathBuf,
+}
+
+impl MemoryBudget {
+    /// Reads the configuration from the environment:
+    /// RE_MEMORY_BUDGET is the budget in megabytes, and RE_SPILL_DIR is the directory used for spilling (default, the temporary directory of the system).
+    pub fn from_env() -> Option<Self> {
+        let mb = std::env::var("RE_MEMORY_BUDGET").ok().and_then(|s|s.parse::<usize>().ok())?;
+        let dir = std::env::var("RE_SPILL_DIR").ok().filter(|s|!s.is_empty()).map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+        Some(Self { bytes : mb << 20, dir })
+    }
+}
+
+static SPILL_COUNTER : AtomicUsize = AtomicUsize::new(0);
+
+/// Sets of lines stored on disk in compressed form. The files are deleted when this is dropped.
+pub struct SpilledLines {
+    dir : PathBuf,
+    files : Vec<PathBuf>,
+}
+
+impl SpilledLines {
+    pub fn new(dir : PathBuf) -> Self {
+        Self { dir, files : vec![] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Writes the lines to a new partition.
+    pub fn spill<'a>(&mut self, lines : impl Iterator<Item=&'a Line>) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e|format!("cannot create {}: {}", self.dir.display(), e))?;
+        let compressed : Vec<CompressedLine> = lines.map(|line|line.compressed()).collect();
+        let encoded = bincode::serialize(&compressed).map_err(|e|e.to_string())?;
+        let id = SPILL_COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path = self.dir.join(format!("re-spill-{}-{}.bin", std::process::id(), id));
+        std::fs::write(&path, encoded).map_err(|e|format!("cannot write {}: {}", path.display(), e))?;
+        self.files.push(path);
+        Ok(())
+    }
+
+    /// Reads the partitions one at a time.
+    pub fn partitions(&self) -> impl Iterator<Item=Result<Vec<Line>, String>> + '_ {
+        self.files.iter().map(|path|{
+            let encoded = std::fs::read(path).map_err(|e|format!("cannot read {}: {}", path.display(), e))?;
+            let compressed : Vec<CompressedLine> = bincode::deserialize(&encoded).map_err(|e|format!("cannot decode {}: {}", path.display(), e))?;
+            Ok(compressed.iter().map(Line::from_compressed).collect())
+        })
+    }
+}
+
+impl Drop for SpilledLines {
+    fn drop(&mut self) {
+        for path in &self.files {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -98,6 +98,34 @@ impl Constraint {
         Ok(constraint)
     }
 
+    /// Parses lines that may have different degrees, and returns one constraint for each degree, starting from the largest one.
+    pub fn parse_by_degree<S: AsRef<str>>(
+        text: S,
+        mapping: &mut HashMap<String, Label>,
+    ) -> Result<Vec<Constraint>, &'static str> {
+        let text = text.as_ref();
+        let lines: Vec<_> = text
+            .lines()
+            .map(|l| Line::parse(l, mapping))
+            .collect::<Result<_, _>>()?;
+        if lines.is_empty() {
+            return Err("Empty constraint");
+        }
+        let mut degrees: Vec<_> = lines.iter().map(|line| line.degree()).collect();
+        degrees.sort();
+        degrees.dedup();
+        degrees.reverse();
+        Ok(degrees.into_iter().map(|degree| {
+            let mut constraint = Constraint {
+                lines: lines.iter().filter(|line| line.degree() == degree).cloned().collect(),
+                is_maximized: false,
+                degree,
+            };
+            constraint.discard_non_maximal_lines();
+            constraint
+        }).collect())
+    }
+
     pub fn includes(&self, other: &Line) -> bool {
         if !self.is_maximized && self.degree != Degree::Finite(2) {
             panic!("this should not happen");
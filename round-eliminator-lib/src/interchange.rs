@@ -0,0 +1,419 @@
+//! A documented and versioned format to exchange LCL problems with other tools, in JSON or YAML.
+//!
+//! Unlike the serde representation of `Problem`, that contains the state of the computations, this format only contains the description of the problem:
+//! ```json
+//! {
+//!   "format": "lcl-interchange",
+//!   "version": 1,
+//!   "name": "maximal independent set",
+//!   "labels": [ { "name": "M", "description": "in the set" }, { "name": "P" }, { "name": "U" } ],
+//!   "degrees": { "active": [3], "passive": [2] },
+//!   "active": [ [ { "labels": ["M"] }, { "labels": ["U"], "count": 2 } ], [ { "labels": ["P"], "count": 3 } ] ],
+//!   "passive": [ [ { "labels": ["M"] }, { "labels": ["P", "U"] } ], [ { "labels": ["U"], "count": 2 } ] ],
+//!   "bounds": [ { "model": "deterministic LOCAL", "lower": "log* n", "upper": "log* n", "references": ["linial92"] } ],
+//!   "references": [ { "key": "linial92", "title": "Locality in Distributed Graph Algorithms", "doi": "10.1137/0221015" } ]
+//! }
+//! ```
+//! Each line of a constraint is a list of groups, a group allows any of its labels, and `count` (by default 1) is either a number or `"*"`.
+//! Lines of different degrees describe problems on graphs that are not regular, and `degrees`, if present, must list the degrees used by the lines, from the largest.
+//! An `input`, if present, is a problem in the same format (without bounds) that describes a labelling given to the nodes.
+//! `SCHEMA` is a JSON schema of the format. Readers accept all the versions up to `VERSION`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{algorithms::extended_format::AnnotatedProblem, group::{GroupType, Label}, line::{Degree, Line}, problem::Problem};
+
+pub const FORMAT : &str = "lcl-interchange";
+pub const VERSION : u32 = 1;
+
+pub const SCHEMA : &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "LCL interchange format, version 1",
+  "$defs": {
+    "group": {
+      "type": "object",
+      "properties": {
+        "labels": { "type": "array", "items": { "type": "string", "minLength": 1 }, "minItems": 1 },
+        "count": { "oneOf": [ { "type": "integer", "minimum": 1 }, { "const": "*" } ] }
+      },
+      "required": ["labels"]
+    },
+    "constraint": { "type": "array", "items": { "type": "array", "items": { "$ref": "#/$defs/group" }, "minItems": 1 }, "minItems": 1 },
+    "degrees": { "type": "array", "items": { "oneOf": [ { "type": "integer", "minimum": 1 }, { "const": "*" } ] } },
+    "problem": {
+      "type": "object",
+      "properties": {
+        "format": { "const": "lcl-interchange" },
+        "version": { "type": "integer", "minimum": 1 },
+        "name": { "type": "string" },
+        "description": { "type": "string" },
+        "labels": { "type": "array", "items": { "type": "object", "properties": { "name": { "type": "string" }, "description": { "type": "string" } }, "required": ["name"] } },
+        "degrees": { "type": "object", "properties": { "active": { "$ref": "#/$defs/degrees" }, "passive": { "$ref": "#/$defs/degrees" } } },
+        "active": { "$ref": "#/$defs/constraint" },
+        "passive": { "$ref": "#/$defs/constraint" },
+        "input": { "$ref": "#/$defs/problem" },
+        "bounds": { "type": "array", "items": { "type": "object", "properties": {
+          "model": { "type": "string" }, "lower": { "type": "string" }, "upper": { "type": "string" },
+          "references": { "type": "array", "items": { "type": "string" } } }, "required": ["model"] } },
+        "references": { "type": "array", "items": { "type": "object", "properties": {
+          "key": { "type": "string" }, "title": { "type": "string" }, "authors": { "type": "array", "items": { "type": "string" } },
+          "url": { "type": "string" }, "doi": { "type": "string" } }, "required": ["key"] } }
+      },
+      "required": ["active", "passive"]
+    }
+  },
+  "allOf": [ { "$ref": "#/$defs/problem" } ],
+  "required": ["format", "version", "active", "passive"]
+}
+"##;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Count {
+    Finite(usize),
+    /// Only `"*"` is valid.
+    Star(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InterchangeGroup {
+    pub labels : Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count : Option<Count>,
+}
+
+pub type InterchangeLine = Vec<InterchangeGroup>;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LabelInfo {
+    pub name : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description : Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Degrees {
+    #[serde(default)]
+    pub active : Vec<Count>,
+    #[serde(default)]
+    pub passive : Vec<Count>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KnownBound {
+    /// For example `deterministic LOCAL` or `randomized LOCAL`.
+    pub model : String,
+    /// Complexities are written as functions of n, for example `1`, `log* n`, `log n`, `n`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lower : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upper : Option<String>,
+    /// Keys of `references`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references : Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Reference {
+    pub key : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title : Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors : Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doi : Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InterchangeProblem {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version : Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description : Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels : Vec<LabelInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degrees : Option<Degrees>,
+    pub active : Vec<InterchangeLine>,
+    pub passive : Vec<InterchangeLine>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input : Option<Box<InterchangeProblem>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bounds : Vec<KnownBound>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references : Vec<Reference>,
+}
+
+fn count_of_degree(degree : Degree) -> Count {
+    match degree {
+        Degree::Finite(d) => Count::Finite(d),
+        Degree::Star => Count::Star("*".into()),
+    }
+}
+
+/// Label names are written without the parentheses used by the text format for labels of more than one character.
+fn plain_name(text : &str) -> String {
+    text.strip_prefix('(').and_then(|t|t.strip_suffix(')')).unwrap_or(text).to_owned()
+}
+
+fn text_name(name : &str, at : &str) -> Result<String, String> {
+    if name.is_empty() || name.chars().any(|c|c.is_whitespace() || "()^*".contains(c)) {
+        return Err(format!("{}: invalid label name `{}`", at, name));
+    }
+    Ok(if name.chars().count() == 1 { name.to_owned() } else { format!("({})", name) })
+}
+
+fn lines_to_interchange<'a>(lines : impl Iterator<Item = &'a Line>, mapping : &HashMap<Label, String>) -> Vec<InterchangeLine> {
+    lines.map(|line|line.parts.iter().map(|part|InterchangeGroup {
+        labels : part.group.iter().map(|l|plain_name(&mapping[l])).collect(),
+        count : match part.gtype {
+            GroupType::ONE => None,
+            GroupType::Many(n) => Some(Count::Finite(n as usize)),
+            GroupType::Star => Some(Count::Star("*".into())),
+        },
+    }).collect()).collect()
+}
+
+fn lines_to_text(lines : &[InterchangeLine], side : &str) -> Result<String, String> {
+    if lines.is_empty() {
+        return Err(format!("{}: the constraint is empty", side));
+    }
+    let mut text = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let at = format!("{}[{}]", side, i);
+        if line.is_empty() {
+            return Err(format!("{}: the line is empty", at));
+        }
+        let mut parts = vec![];
+        for (j, group) in line.iter().enumerate() {
+            let at = format!("{}[{}]", at, j);
+            if group.labels.is_empty() {
+                return Err(format!("{}: the group is empty", at));
+            }
+            let labels : String = group.labels.iter().map(|l|text_name(l, &at)).collect::<Result<_, _>>()?;
+            let count = match &group.count {
+                None | Some(Count::Finite(1)) => String::new(),
+                Some(Count::Finite(0)) => return Err(format!("{}: the count must be positive", at)),
+                Some(Count::Finite(n)) if *n > crate::group::Exponent::MAX as usize => return Err(format!("{}: the count is too large", at)),
+                Some(Count::Finite(n)) => format!("^{}", n),
+                Some(Count::Star(s)) if s == "*" => "*".into(),
+                Some(Count::Star(s)) => return Err(format!("{}: invalid count `{}`", at, s)),
+            };
+            parts.push(format!("{}{}", labels, count));
+        }
+        text.push(parts.join(" "));
+    }
+    Ok(text.join("\n"))
+}
+
+impl InterchangeProblem {
+    pub fn from_problem(p : &Problem, descriptions : &[(Label, String)]) -> Self {
+        let mapping : HashMap<Label, String> = p.mapping_label_text.iter().cloned().collect();
+        let descriptions : HashMap<Label, &String> = descriptions.iter().map(|(l, d)|(*l, d)).collect();
+        let labels = p.labels().into_iter().map(|l|LabelInfo { name : plain_name(&mapping[&l]), description : descriptions.get(&l).map(|d|d.to_string()) }).collect();
+        Self {
+            format : Some(FORMAT.into()),
+            version : Some(VERSION),
+            name : None,
+            description : None,
+            labels,
+            degrees : Some(Degrees {
+                active : p.active_constraints().iter().map(|c|count_of_degree(c.degree)).collect(),
+                passive : p.passive_constraints().iter().map(|c|count_of_degree(c.degree)).collect(),
+            }),
+            active : lines_to_interchange(p.active_constraints().into_iter().flat_map(|c|c.lines.iter()), &mapping),
+            passive : lines_to_interchange(p.passive_constraints().into_iter().flat_map(|c|c.lines.iter()), &mapping),
+            input : p.input_given.as_ref().map(|input|{
+                let mut input = Self::from_problem(input, &[]);
+                input.format = None;
+                input.version = None;
+                Box::new(input)
+            }),
+            bounds : vec![],
+            references : vec![],
+        }
+    }
+
+    /// Checks the version and the content, and converts the description into a problem.
+    pub fn to_problem(&self) -> Result<AnnotatedProblem, String> {
+        if let Some(format) = &self.format {
+            if format != FORMAT {
+                return Err(format!("unknown format `{}`", format));
+            }
+        }
+        if let Some(version) = self.version {
+            if version == 0 || version > VERSION {
+                return Err(format!("unsupported version {}, the supported versions are 1 to {}", version, VERSION));
+            }
+        }
+        self.convert()
+    }
+
+    fn convert(&self) -> Result<AnnotatedProblem, String> {
+        let active = lines_to_text(&self.active, "active")?;
+        let passive = lines_to_text(&self.passive, "passive")?;
+        let (mut problem, _) = Problem::from_string_active_passive_nonregular(active, passive).map_err(|e|e.to_string())?;
+
+        if let Some(degrees) = &self.degrees {
+            let check = |declared : &Vec<Count>, actual : Vec<Count>, side : &str| {
+                if !declared.is_empty() && *declared != actual {
+                    Err(format!("{}: the declared degrees do not match the lines", side))
+                } else {
+                    Ok(())
+                }
+            };
+            check(&degrees.active, problem.active_constraints().iter().map(|c|count_of_degree(c.degree)).collect(), "degrees.active")?;
+            check(&degrees.passive, problem.passive_constraints().iter().map(|c|count_of_degree(c.degree)).collect(), "degrees.passive")?;
+        }
+
+        let label_of : HashMap<String, Label> = problem.mapping_label_text.iter().map(|(l, s)|(plain_name(s), *l)).collect();
+        let mut declared = BTreeSet::new();
+        let mut descriptions = vec![];
+        for info in &self.labels {
+            if !declared.insert(&info.name) {
+                return Err(format!("labels: `{}` is declared twice", info.name));
+            }
+            let label = label_of.get(&info.name).ok_or_else(||format!("labels: `{}` does not appear in the constraints", info.name))?;
+            if let Some(description) = &info.description {
+                descriptions.push((*label, description.clone()));
+            }
+        }
+        descriptions.sort();
+
+        let keys : BTreeSet<&String> = self.references.iter().map(|r|&r.key).collect();
+        if keys.len() != self.references.len() {
+            return Err("references: some keys are used twice".into());
+        }
+        for (i, bound) in self.bounds.iter().enumerate() {
+            if let Some(key) = bound.references.iter().find(|k|!keys.contains(k)) {
+                return Err(format!("bounds[{}]: unknown reference `{}`", i, key));
+            }
+        }
+
+        if let Some(input) = &self.input {
+            let input = input.convert().map_err(|e|format!("input.{}", e))?.problem;
+            if input.active.degree != problem.active.degree || input.passive.degree != problem.passive.degree {
+                return Err("input: the degrees of the input are different from the ones of the problem".into());
+            }
+            problem.set_input(Some(input));
+        }
+
+        Ok(AnnotatedProblem { problem, descriptions })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(text : &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e|e.to_string())
+    }
+
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).unwrap()
+    }
+
+    pub fn from_yaml(text : &str) -> Result<Self, String> {
+        serde_yaml::from_str(text).map_err(|e|e.to_string())
+    }
+}
+
+impl AnnotatedProblem {
+    /// The problem in the interchange format, bounds and references can be added to the result.
+    pub fn to_interchange(&self) -> InterchangeProblem {
+        InterchangeProblem::from_problem(&self.problem, &self.descriptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::{InterchangeProblem, KnownBound, Reference};
+
+    #[test]
+    fn interchange() {
+        let p = Problem::from_extended_string("label M : in the set\n\nM U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut i = p.to_interchange();
+        i.bounds.push(KnownBound { model : "deterministic LOCAL".into(), lower : Some("log* n".into()), upper : Some("log* n".into()), references : vec!["linial92".into()] });
+        i.references.push(Reference { key : "linial92".into(), title : None, authors : vec![], url : None, doi : None });
+
+        let json = InterchangeProblem::from_json(&i.to_json()).unwrap();
+        assert_eq!(json, i);
+        let yaml = InterchangeProblem::from_yaml(&i.to_yaml()).unwrap();
+        assert_eq!(yaml, i);
+
+        let q = json.to_problem().unwrap();
+        assert_eq!(q.to_string(), p.to_string());
+
+        // labels with more than one character and non-regular problems
+        let p = Problem::from_string_nonregular("(0a) (0a) (0a)\n(0a)\n\n(0a) (0a)").unwrap();
+        let q = InterchangeProblem::from_json(&InterchangeProblem::from_problem(&p, &[]).to_json()).unwrap().to_problem().unwrap();
+        assert_eq!(q.problem.to_string(), p.to_string());
+    }
+
+    #[test]
+    fn interchange_nonregular() {
+        let text = r#"{"format":"lcl-interchange","version":1,"labels":[{"name":"M","description":"in the set"},{"name":"P"},{"name":"U"}],
+            "degrees":{"active":[3,1],"passive":[2]},
+            "active":[[{"labels":["M"]},{"labels":["U"],"count":2}],[{"labels":["P"],"count":3}],[{"labels":["M"]}],[{"labels":["P"]}]],
+            "passive":[[{"labels":["M"]},{"labels":["P","U"]}],[{"labels":["U"],"count":2}]]}"#;
+        let p = InterchangeProblem::from_json(text).unwrap().to_problem().unwrap();
+        assert!(!p.problem.is_regular());
+        assert_eq!(p.descriptions.len(), 1);
+
+        let i = p.to_interchange();
+        assert_eq!(i.degrees.as_ref().unwrap().active, vec![super::Count::Finite(3), super::Count::Finite(1)]);
+        let q = InterchangeProblem::from_json(&i.to_json()).unwrap().to_problem().unwrap();
+        assert_eq!(q.problem.to_string(), p.problem.to_string());
+        assert_eq!(q.descriptions, p.descriptions);
+    }
+
+    #[test]
+    fn interchange_errors() {
+        let mut i = InterchangeProblem::from_problem(&Problem::from_string("A B B\n\nA B").unwrap(), &[]);
+        i.version = Some(super::VERSION + 1);
+        assert!(i.to_problem().unwrap_err().contains("unsupported version"));
+
+        let mut i = InterchangeProblem::from_json(r#"{"active":[[{"labels":["A"],"count":3}]],"passive":[[{"labels":["A","B"],"count":"**"}]]}"#).unwrap();
+        assert_eq!(i.to_problem().unwrap_err(), "passive[0][0]: invalid count `**`");
+        i.passive[0][0].count = None;
+        i.degrees = Some(super::Degrees { active : vec![super::Count::Finite(2)], passive : vec![] });
+        assert_eq!(i.to_problem().unwrap_err(), "degrees.active: the declared degrees do not match the lines");
+    }
+
+    #[test]
+    fn interchange_schema() {
+        let schema : serde_json::Value = serde_json::from_str(super::SCHEMA).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let valid = |json : &str| validator.is_valid(&serde_json::from_str(json).unwrap());
+
+        let p = Problem::from_extended_string("label M : in the set\n\nM U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut i = p.to_interchange();
+        i.bounds.push(KnownBound { model : "deterministic LOCAL".into(), lower : Some("log* n".into()), upper : None, references : vec!["linial92".into()] });
+        i.references.push(Reference { key : "linial92".into(), title : Some("Locality in Distributed Graph Algorithms".into()), authors : vec![], url : None, doi : None });
+        assert!(valid(&i.to_json()));
+
+        // labels with more than one character, non-regular problems, stars and inputs
+        let p = Problem::from_string_nonregular("(0a) (0a) (0a)\n(0a)\n\n(0a) (0a)").unwrap();
+        assert!(valid(&InterchangeProblem::from_problem(&p, &[]).to_json()));
+        let p = Problem::from_string("A AB*\n\nB AB*").unwrap();
+        assert!(valid(&InterchangeProblem::from_problem(&p, &[]).to_json()));
+        let mut p = Problem::from_string("A B B\n\nA B").unwrap();
+        p.set_input(Some(Problem::from_string("X X X\n\nX X").unwrap()));
+        assert!(valid(&InterchangeProblem::from_problem(&p, &[]).to_json()));
+
+        assert!(!valid(r#"{"format":"lcl-interchange","version":1,"active":[[{"labels":["A"],"count":3}]],"passive":[[{"labels":["A"],"count":"**"}]]}"#));
+        assert!(!valid(r#"{"format":"lcl-interchange","version":1,"active":[[{"labels":[]}]],"passive":[[{"labels":["A"]}]]}"#));
+        assert!(!valid(r#"{"active":[[{"labels":["A"]}]],"passive":[[{"labels":["A"]}]]}"#));
+    }
+}
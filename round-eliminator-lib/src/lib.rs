@@ -14,6 +14,7 @@ pub mod line;
 pub mod part;
 pub mod problem;
 pub mod serial;
+pub mod interchange;
 pub mod directed;
 pub mod kpartite;
 //#[cfg(test)]
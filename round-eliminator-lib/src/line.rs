@@ -1,4 +1,4 @@
-use crate::group::{GroupType, Label, Exponent};
+use crate::group::{Group, GroupType, Label, Exponent};
 use crate::part::Part;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -95,6 +95,38 @@ impl Line {
         compressed
     }
 
+    /// Inverse of `compressed`.
+    pub fn from_compressed(compressed : &CompressedLine) -> Line {
+        let encoded = lz4_flex::decompress_size_prepended(compressed).unwrap();
+        let v : Vec<CompressedLineEntry> = bincode::deserialize(&encoded).unwrap();
+        let mut parts : Vec<Part> = vec![];
+        let mut labels : Vec<Label> = vec![];
+        let mut gtype = None;
+        for entry in v {
+            match entry {
+                CompressedLineEntry::NewPart(t) => {
+                    if let Some(gtype) = gtype {
+                        parts.push(Part { gtype, group : Group::from(std::mem::take(&mut labels)) });
+                    }
+                    gtype = Some(t);
+                }
+                CompressedLineEntry::Label(l) => {
+                    let prev = labels.last().cloned().unwrap_or(0);
+                    labels.push(prev + l);
+                }
+            }
+        }
+        if let Some(gtype) = gtype {
+            parts.push(Part { gtype, group : Group::from(labels) });
+        }
+        Line { parts }
+    }
+
+    /// An estimate of the number of bytes used by this line.
+    pub fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Line>() + self.parts.iter().map(|part|std::mem::size_of::<Part>() + part.group.len() * std::mem::size_of::<Label>()).sum::<usize>()
+    }
+
     pub fn all_choices(&self, normalize : bool) -> Vec<Line> {
         let c = crate::constraint::Constraint{ lines : vec![self.clone()], is_maximized: false, degree: self.degree() };
         c.all_choices(normalize)
@@ -102,7 +134,7 @@ impl Line {
 }
 pub type CompressedLine = Vec<u8>;
 
-#[derive(Clone,Hash,Eq,PartialEq, PartialOrd,Ord,Debug,Serialize)]
+#[derive(Clone,Hash,Eq,PartialEq, PartialOrd,Ord,Debug,Serialize,Deserialize)]
 pub enum CompressedLineEntry{
     NewPart(GroupType),
     Label(Label),
@@ -161,6 +193,15 @@ mod tests {
         assert_eq!(p.degree_without_star(), 7);
     }
 
+    #[test]
+    fn compressed_roundtrip() {
+        let p = Line::parse("AB AB* AB^3 ABC", &mut HashMap::new()).unwrap();
+        assert_eq!(Line::from_compressed(&p.compressed()), p);
+
+        let p = Line::parse("A BC^2 ABC^4", &mut HashMap::new()).unwrap();
+        assert_eq!(Line::from_compressed(&p.compressed()), p);
+    }
+
     #[test]
     #[should_panic]
     fn convert_err() {
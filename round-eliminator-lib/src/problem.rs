@@ -7,6 +7,7 @@ use crate::{algorithms::event::EventHandler, constraint::Constraint, group::{Exp
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use crate::algorithms::fixpoint::FixpointDiagram;
+use crate::algorithms::nonregular::OtherDegrees;
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub struct Problem {
@@ -31,7 +32,17 @@ pub struct Problem {
     pub demisifiable : Option<Vec<(Vec<Label>,Vec<Label>)>>,
     pub is_trivial_with_input : Option<bool>,
     pub triviality_with_input : Option<(Vec<(Label, String)>,Vec<(Label, Vec<Label>)>)>,
-    pub expressions : Option<String>
+    /// Whether the problem is zero-round solvable given `input_given`, kept separate from `is_trivial_with_input`, that is used for arbitrary inputs.
+    pub trivial_given_input : Option<bool>,
+    pub expressions : Option<String>,
+    /// A labelling that is given as input to the nodes, described as a problem with the same degrees.
+    /// All zero-round solvability checks are done relative to it.
+    pub input_given : Option<Box<Problem>>,
+    /// For each label of `input_given`, the labels that a node receiving it can output, if not all of them.
+    /// Operations that change the labels translate it, possibly allowing more labels than needed.
+    pub input_allowed_outputs : Option<Vec<(Label, Vec<Label>)>>,
+    /// For problems on graphs that are not regular, the constraints of the other degrees.
+    pub other_degrees : Option<OtherDegrees>
 }
 
 pub type DiagramDirect = (Vec<(Label, Vec<Label>)>, Vec<(Label, Label)>);
@@ -61,7 +72,40 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees : self.other_degrees.clone()
+        }
+    }
+
+    /// Exchanges the active and the passive side.
+    /// The input of the problem obtained by applying speedup is the original input with the sides swapped.
+    pub fn swap_sides(&self) -> Self {
+        let mut p = self.clone();
+        std::mem::swap(&mut p.active, &mut p.passive);
+        p.other_degrees = self.other_degrees.as_ref().map(|other|other.swap_sides());
+        p.passive_gen = None;
+        p.trivial_sets = None;
+        p.coloring_sets = None;
+        p.diagram_indirect = None;
+        p.diagram_indirect_old = None;
+        p.diagram_direct = None;
+        p.orientation_coloring_sets = None;
+        p.orientation_trivial_sets = None;
+        p.is_trivial_with_input = None;
+        p.triviality_with_input = None;
+        p.trivial_given_input = None;
+        p
+    }
+
+    /// The text representation of the problem followed by the one of its input, if any.
+    /// Two problems that look the same but have different inputs must not be considered equal by automatic searches.
+    pub fn to_string_with_input(&self) -> String {
+        match &self.input_given {
+            Some(input) => format!("{}\ninput:\n{}", self, input),
+            None => self.to_string()
         }
     }
 
@@ -70,12 +114,26 @@ impl Problem {
         passive: S,
     ) -> Result<(Self,bool), &'static str> {
         let mut mapping_label_text = HashMap::new();
-
         let active = Constraint::parse(active, &mut mapping_label_text)?;
         let passive = Constraint::parse(passive, &mut mapping_label_text)?;
+        Ok(Self::from_constraints(active, passive, None, mapping_label_text))
+    }
 
-        let missing_labels = active.labels_appearing() != passive.labels_appearing();
+    /// Same as `from_string_active_passive`, but lines of different degrees are allowed, and they describe a problem on graphs that are not regular.
+    pub fn from_string_active_passive_nonregular<S: AsRef<str>>(
+        active: S,
+        passive: S,
+    ) -> Result<(Self,bool), &'static str> {
+        let mut mapping_label_text = HashMap::new();
+        let mut active = Constraint::parse_by_degree(active, &mut mapping_label_text)?;
+        let mut passive = Constraint::parse_by_degree(passive, &mut mapping_label_text)?;
+        let other_degrees = OtherDegrees::new(active.split_off(1), passive.split_off(1));
+        let active = active.pop().unwrap();
+        let passive = passive.pop().unwrap();
+        Ok(Self::from_constraints(active, passive, other_degrees, mapping_label_text))
+    }
 
+    fn from_constraints(active : Constraint, passive : Constraint, other_degrees : Option<OtherDegrees>, mapping_label_text : HashMap<String, Label>) -> (Self,bool) {
         let mapping_label_text = mapping_label_text
             .into_iter()
             .map(|(a, b)| (b, a))
@@ -103,23 +161,36 @@ impl Problem {
             demisifiable : None,
             is_trivial_with_input : None,
             triviality_with_input : None,
-            expressions : None
+            trivial_given_input : None,
+            expressions : None,
+            input_given : None,
+            input_allowed_outputs : None,
+            other_degrees
         };
-        Ok((p,missing_labels))
+        let missing_labels = p.active_labels_appearing() != p.passive_labels_appearing();
+        (p,missing_labels)
     }
 
-    pub fn from_string<S: AsRef<str>>(s: S) -> Result<Self, &'static str> {
-        let s = s.as_ref();
+    fn split_active_passive(s : &str) -> (String, String) {
         let mut lines = s.lines();
-
         let active = lines.by_ref().take_while(|l| !l.is_empty()).join("\n");
         let passive = lines.take_while(|l| !l.is_empty()).join("\n");
+        (active, passive)
+    }
 
+    pub fn from_string<S: AsRef<str>>(s: S) -> Result<Self, &'static str> {
+        let (active, passive) = Self::split_active_passive(s.as_ref());
         Self::from_string_active_passive(active, passive).map(|p|p.0)
     }
 
+    /// Same as `from_string`, but lines of different degrees are allowed, see `from_string_active_passive_nonregular`.
+    pub fn from_string_nonregular<S: AsRef<str>>(s: S) -> Result<Self, &'static str> {
+        let (active, passive) = Self::split_active_passive(s.as_ref());
+        Self::from_string_active_passive_nonregular(active, passive).map(|p|p.0)
+    }
+
     pub fn labels(&self) -> Vec<Label> {
-        self.active.labels_appearing().into_iter().chain(self.passive.labels_appearing().into_iter()).unique().sorted().collect()
+        self.active_labels_appearing().into_iter().chain(self.passive_labels_appearing().into_iter()).unique().sorted().collect()
         //let mut labels: Vec<_> = self.mapping_label_text.iter().map(|(l, _)| *l).collect();
         //labels.sort_unstable();
         //labels
@@ -189,7 +260,11 @@ impl Problem {
                 demisifiable : None,
                 is_trivial_with_input : None,
                 triviality_with_input : None,
-                expressions : None
+                trivial_given_input : None,
+                expressions : None,
+                input_given : self.input_given.clone(),
+                input_allowed_outputs : self.input_allowed_outputs.clone(),
+                other_degrees : self.other_degrees.clone()
         }
     }
 
@@ -272,11 +347,11 @@ impl Problem {
 impl Display for Problem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mapping = self.mapping_label_text.iter().cloned().collect();
-        for line in &self.active.lines {
+        for line in self.active_constraints().into_iter().flat_map(|c|c.lines.iter()) {
             writeln!(f, "{}", line.to_string(&mapping))?;
         }
         writeln!(f)?;
-        for line in &self.passive.lines {
+        for line in self.passive_constraints().into_iter().flat_map(|c|c.lines.iter()) {
             writeln!(f, "{}", line.to_string(&mapping))?;
         }
         Ok(())
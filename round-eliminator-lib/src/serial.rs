@@ -3,50 +3,83 @@ use std::{collections::HashMap, time::Duration};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::{algorithms::{event::EventHandler, fixpoint::{parse_diagram, FixpointType}}, group::Label, line::Degree, problem::Problem};
+use crate::{algorithms::{autolb::{LowerBound, LowerBoundCertificate}, classify::{Classification, ClassificationBudget}, event::EventHandler, fixpoint::{parse_diagram, FixpointType}, fixpoint_report::FixpointFailure, fixpoint_search::FixpointCandidate, fixpoint_verify::FixpointCertificate}, group::Label, line::Degree, problem::Problem};
 
 pub fn fix_problem(new: &mut Problem, sort_by_strength: bool, compute_triviality_and_coloring : bool, eh: &mut EventHandler) {
-    if new.passive.degree == Degree::Finite(2) {
-        new.diagram_indirect = None;
-        new.compute_diagram(eh);
-        new.discard_useless_stuff(true, eh);
-        if sort_by_strength {
-            new.sort_active_by_strength();
-        }
-        if compute_triviality_and_coloring {
-            new.compute_triviality(eh);
-            new.compute_coloring_solvability(eh);
-            if let Some(outdegree) = new.orientation_given {
-                new.orientation_trivial_sets = None;
-                new.compute_triviality_given_orientation(outdegree, eh);
-                //new.compute_coloring_solvability_given_orientation(outdegree, eh);
-            }
-        }
-    } else {
-        new.discard_useless_stuff(false, eh);
-        if sort_by_strength {
-            new.sort_active_by_strength();
-        }
+    new.diagram_indirect = None;
+    new.compute_diagram(eh);
+    new.discard_useless_stuff(true, eh);
+    if sort_by_strength {
+        new.sort_active_by_strength();
+    }
+    if compute_triviality_and_coloring {
+        new.compute_triviality(eh);
+        compute_coloring_and_orientation(new, eh);
+        new.is_trivial_with_input = None;
+        new.triviality_with_input = None;
+        new.trivial_given_input = None;
+        new.compute_triviality_given_input();
     }
-    //new.randomized_success_prob(eh);
     new.compute_passive_gen();
 }
 
-pub fn maximize_rename_gen(new : &mut Problem, eh : &mut EventHandler) -> Result<(), &'static str> {
-    new.passive.maximize(eh);
-    new.compute_diagram(eh);
-    new.discard_useless_stuff(true, eh);
-    new.sort_active_by_strength();
-    new.compute_triviality(eh);
+/// Coloring solvability is computed automatically only on graphs, on hypergraphs it is expensive and it is computed on request.
+/// Triviality given an orientation is computed if one of the two sides has degree 2.
+fn compute_coloring_and_orientation(new: &mut Problem, eh: &mut EventHandler) {
     if new.passive.degree == Degree::Finite(2) {
         new.compute_coloring_solvability(eh);
-        if let Some(outdegree) = new.orientation_given {
+    }
+    if let Some(outdegree) = new.orientation_given {
+        let finite = new.active.degree != Degree::Star && new.passive.degree != Degree::Star;
+        if finite && (new.passive.degree == Degree::Finite(2) || new.active.degree == Degree::Finite(2)) {
+            new.orientation_trivial_sets = None;
             new.compute_triviality_given_orientation(outdegree, eh);
             //new.compute_coloring_solvability_given_orientation(outdegree, eh);
         }
     }
+}
+
+/// The responses for the result of a fixed point computation: the obtained problem, followed by a report if it is 0-round solvable, or the report of the failure.
+/// `duplicable` tells whether the labels of the fixed point are the ones of the default diagram, so that duplicating them can be suggested.
+fn fixpoint_responses(result : Result<(Problem,Vec<(Label,Label)>,Vec<(Label,Label)>), FixpointFailure>, duplicable : bool, eh: &mut EventHandler) -> Vec<Response> {
+    match result {
+        Ok((mut new,_,_)) => {
+            fix_problem(&mut new, true, true, eh);
+            let report = new.fixpoint_triviality_report(duplicable);
+            let mut responses = vec![Response::P(new)];
+            responses.extend(report.map(Response::FixpointFailure));
+            responses
+        }
+        Err(failure) => vec![Response::FixpointFailure(failure)],
+    }
+}
+
+/// The responses for a newly parsed problem.
+fn new_problem_responses(parsed : Result<(Problem,bool), &'static str>, eh: &mut EventHandler) -> Vec<Response> {
+    match parsed {
+        Ok((mut new, missing_labels)) => {
+            let mut responses = vec![];
+            if missing_labels {
+                responses.push(Response::W("Some labels appear on only one side!".into()));
+            }
+            fix_problem(&mut new, true, true, eh);
+            responses.push(Response::P(new));
+            responses
+        }
+        Err(s) => vec![Response::E(s.into())],
+    }
+}
+
+pub fn maximize_rename_gen(new : &mut Problem, eh : &mut EventHandler) -> Result<(), String> {
+    new.maximize_passive(eh)?;
+    new.compute_diagram(eh);
+    new.discard_useless_stuff(true, eh);
+    new.sort_active_by_strength();
+    new.compute_triviality(eh);
+    compute_coloring_and_orientation(new, eh);
+    new.compute_triviality_given_input();
     new.compute_passive_gen();
-    new.rename_by_generators()
+    new.rename_by_generators().map_err(|e|e.to_string())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -99,71 +132,61 @@ where
             return;
         }
         Request::NewProblem(active, passive) => {
-            match Problem::from_string_active_passive(active, passive) {
-                Ok((mut new, missing_labels)) => {
-                    if missing_labels {
-                        handler(Response::W("Some labels appear on only one side!".into()));
-                    }
-                    fix_problem(&mut new, true, true,&mut eh);
-                    handler(Response::P(new))
-                }
-                Err(s) => handler(Response::E(s.into())),
+            for response in new_problem_responses(Problem::from_string_active_passive(active, passive), &mut eh) {
+                handler(response);
+            }
+        }
+        Request::NewProblemNonregular(active, passive) => {
+            for response in new_problem_responses(Problem::from_string_active_passive_nonregular(active, passive), &mut eh) {
+                handler(response);
             }
         }
         Request::Speedup(mut problem) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            let mut new = problem.speedup(&mut eh);
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
+            match problem.try_speedup(&mut eh) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s)),
+            }
         }
         Request::FixpointBasic(mut problem, partial, triviality_only, sublabels) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            match problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Basic,triviality_only,&mut eh) {
-                Ok((mut new,_,_)) => {
-                    fix_problem(&mut new, true, true, &mut eh);
-                    handler(Response::P(new));
-                }
-                Err(s) => handler(Response::E(s.into())),
+            let result = problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Basic,triviality_only,&mut eh);
+            for response in fixpoint_responses(result, !partial, &mut eh) {
+                handler(response);
             }
         }
         Request::FixpointLoop(mut problem, partial, triviality_only, sublabels) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            match problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Loop,triviality_only,&mut eh) {
-                Ok((mut new,_,_)) => {
-                    fix_problem(&mut new, true, true, &mut eh);
-                    handler(Response::P(new));
-                }
-                Err(s) => handler(Response::E(s.into())),
+            let result = problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Loop,triviality_only,&mut eh);
+            for response in fixpoint_responses(result, false, &mut eh) {
+                handler(response);
             }
         }
         Request::FixpointCustom(mut problem, diagram, partial, triviality_only, sublabels) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            match problem.fixpoint_generic(if partial {Some(sublabels)} else {None}, FixpointType::Custom(diagram),triviality_only,&mut eh) {
-                Ok((mut new,_,_)) => {
-                    fix_problem(&mut new, true, true, &mut eh);
-                    handler(Response::P(new));
-                }
-                Err(s) => handler(Response::E(s.into())),
+            let result = problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Custom(diagram),triviality_only,&mut eh);
+            for response in fixpoint_responses(result, false, &mut eh) {
+                handler(response);
             }
         }
         Request::FixpointDup(mut problem, dups, partial, triviality_only, sublabels, track) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            match problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Dup(dups, track),triviality_only,&mut eh) {
-                Ok((mut new,_,_)) => {
-                    fix_problem(&mut new, true, true, &mut eh);
-                    handler(Response::P(new));
-                }
-                Err(s) => handler(Response::E(s.into())),
+            let result = problem.fixpoint_generic(if partial {Some(sublabels)} else {None},FixpointType::Dup(dups, track),triviality_only,&mut eh);
+            for response in fixpoint_responses(result, false, &mut eh) {
+                handler(response);
             }
         }
         Request::InverseSpeedup(problem) => {
@@ -172,12 +195,16 @@ where
                     "Cannot perform inverse speedup if the active side contains a star.".into(),
                 ));
             } else {
-                let mut new = problem.inverse_speedup();
-                if new.active.degree != Degree::Finite(1) {
-                    new.trivial_sets = Some(vec![]);
+                match problem.inverse_speedup() {
+                    Ok(mut new) => {
+                        if new.active.degree != Degree::Finite(1) {
+                            new.trivial_sets = Some(vec![]);
+                        }
+                        fix_problem(&mut new, false, false, &mut eh);
+                        handler(Response::P(new));
+                    }
+                    Err(s) => handler(Response::E(s.into())),
                 }
-                fix_problem(&mut new, false, false, &mut eh);
-                handler(Response::P(new));
             }
         }
         Request::AllDifferentLabels(problem) => {
@@ -186,13 +213,20 @@ where
                     "Cannot perform this operation if the active side contains a star.".into(),
                 ));
             } else {
-                let mut new = problem.inverse_speedup();
-                let active = new.active;
-                let passive = new.passive;
-                new.active = passive;
-                new.passive = active;
-                fix_problem(&mut new, false, false, &mut eh);
-                handler(Response::P(new));
+                match problem.inverse_speedup() {
+                    Ok(mut new) => {
+                        let active = new.active;
+                        let passive = new.passive;
+                        new.active = passive;
+                        new.passive = active;
+                        let allowed = new.input_allowed_outputs.take();
+                        new.set_input(problem.input_given.as_deref().cloned());
+                        new.input_allowed_outputs = allowed;
+                        fix_problem(&mut new, false, false, &mut eh);
+                        handler(Response::P(new));
+                    }
+                    Err(s) => handler(Response::E(s.into())),
+                }
             }
         }
         Request::DeltaEdgeColoring(problem) => {
@@ -210,32 +244,30 @@ where
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            let mut new = problem.speedup(&mut eh);
-            new.passive.maximize(&mut eh);
-            new.compute_diagram(&mut eh);
-            new.discard_useless_stuff(true, &mut eh);
-            new.sort_active_by_strength();
-            new.compute_triviality(&mut eh);
-            if new.passive.degree == Degree::Finite(2) {
-                new.compute_coloring_solvability(&mut eh);
-                if let Some(outdegree) = new.orientation_given {
-                    new.compute_triviality_given_orientation(outdegree, &mut eh);
-                    //new.compute_coloring_solvability_given_orientation(outdegree, &mut eh);
+            match problem.try_speedup(&mut eh).and_then(|mut new|new.maximize_passive(&mut eh).map(|()|new)) {
+                Ok(mut new) => {
+                    new.compute_diagram(&mut eh);
+                    new.discard_useless_stuff(true, &mut eh);
+                    new.sort_active_by_strength();
+                    new.compute_triviality(&mut eh);
+                    compute_coloring_and_orientation(&mut new, &mut eh);
+                    new.compute_triviality_given_input();
+                    new.compute_passive_gen();
+                    handler(Response::P(new));
                 }
+                Err(s) => handler(Response::E(s)),
             }
-            new.compute_passive_gen();
-            handler(Response::P(new));
         }
         Request::SpeedupMaximizeRenamegen(mut problem) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
-            let mut new = problem.speedup(&mut eh);
-            match maximize_rename_gen(&mut new, &mut eh) {
-                Ok(()) => {
+            let result = problem.try_speedup(&mut eh).and_then(|mut new|maximize_rename_gen(&mut new, &mut eh).map(|()|new));
+            match result {
+                Ok(new) => {
                     handler(Response::P(new));
                 }
-                Err(s) => handler(Response::E(s.into())),
+                Err(s) => handler(Response::E(s)),
             }
         }
         Request::SimplifyMerge(problem, a, b) => {
@@ -276,20 +308,19 @@ where
         }
         Request::Maximize(mut problem) => {
             problem.diagram_indirect = None;
-            problem.passive.maximize(&mut eh);
-            problem.compute_diagram(&mut eh);
-            problem.discard_useless_stuff(true, &mut eh);
-            problem.sort_active_by_strength();
-            problem.compute_triviality(&mut eh);
-            if problem.passive.degree == Degree::Finite(2) {
-                problem.compute_coloring_solvability(&mut eh);
-                if let Some(outdegree) = problem.orientation_given {
-                    problem.compute_triviality_given_orientation(outdegree, &mut eh);
-                    //problem.compute_coloring_solvability_given_orientation(outdegree, &mut eh);
+            match problem.maximize_passive(&mut eh) {
+                Ok(()) => {
+                    problem.compute_diagram(&mut eh);
+                    problem.discard_useless_stuff(true, &mut eh);
+                    problem.sort_active_by_strength();
+                    problem.compute_triviality(&mut eh);
+                    compute_coloring_and_orientation(&mut problem, &mut eh);
+                    problem.compute_triviality_given_input();
+                    problem.compute_passive_gen();
+                    handler(Response::P(problem));
                 }
+                Err(s) => handler(Response::E(s)),
             }
-            problem.compute_passive_gen();
-            handler(Response::P(problem));
         }
         Request::FullDiagram(mut problem) => {
             problem.compute_diagram_without_storing_maximized_passive(&mut eh);
@@ -316,6 +347,23 @@ where
             }
             handler(Response::P(problem));
         },
+        Request::GiveInput(mut problem, active, passive) => {
+            match Problem::from_string_active_passive(active, passive) {
+                Ok((input, missing_labels)) => {
+                    if missing_labels {
+                        handler(Response::W("Some labels appear on only one side!".into()));
+                    }
+                    if input.active.degree != problem.active.degree || input.passive.degree != problem.passive.degree {
+                        handler(Response::E("Problems have different degrees".into()));
+                    } else {
+                        problem.set_input(if input.labels().is_empty() { None } else { Some(input) });
+                        problem.compute_triviality_given_input();
+                        handler(Response::P(problem));
+                    }
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
+        },
         Request::AutoUb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive) => {
             eh.notify("autoub",0,0);
             problem.autoautoub( b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, |len,is_trivial,mut sequence|{
@@ -336,19 +384,59 @@ where
                 eh.notify("autolb",0,0);
             }, &mut eh_ignore);
         },
+        Request::AutoLbCombined(problem, max_labels, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, target_active, target_passive, target_bound) => {
+            let target = if target_active.is_empty() {
+                Ok(None)
+            } else {
+                Problem::from_string_active_passive(target_active, target_passive).map(|(target,_)|Some(target))
+            };
+            match target {
+                Ok(target) => {
+                    eh.notify("autolb",0,0);
+                    let found = problem.autolb_combined(max_labels, branching, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, target.as_ref().map(|target|(target, target_bound)), |certificate|{
+                        handler(Response::LowerBound(certificate.clone()));
+                        eh.notify("autolb",0,0);
+                    }, &mut eh_ignore, None);
+                    if found.is_none() {
+                        handler(Response::E("No lower bound found".into()));
+                    }
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
+        },
+        Request::Classify(problem, max_labels, branching, max_steps, seconds) => {
+            let budget = ClassificationBudget { max_labels, branching, max_steps, seconds : seconds as u64 };
+            problem.classify(&budget, None, None, |classification|{
+                handler(Response::Classification(classification.clone()));
+            }, &mut eh);
+        },
         Request::ColoringSolvability(mut problem) => {
             problem.compute_coloring_solvability(&mut eh);
             problem.compute_passive_gen();
             handler(Response::P(problem));
         }
         Request::Marks(mut problem) => {
-            if problem.passive.degree  != Degree::Finite(2) {
-                handler(Response::E(
-                    "It is required that the passive degree is 2.".into(),
-                ));
-            }else{
-                problem.apply_marks_technique(&mut eh);
-                handler(Response::P(problem));
+            match problem.apply_marks_technique(&mut eh) {
+                Ok(()) => handler(Response::P(problem)),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::RandomizedUb(problem, max_steps, max_lines) => {
+            match problem.randomized_upper_bound(max_steps, max_lines, &mut eh) {
+                Ok(Some(certificate)) => handler(Response::W(certificate.to_string())),
+                Ok(None) => handler(Response::E("Could not find a randomized upper bound.".into())),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::Multigraph(problem, max_nodes, allow_self_loops) => {
+            match problem.find_unsolvable_multigraph(max_nodes, allow_self_loops, &mut eh) {
+                Ok(Some(g)) => {
+                    handler(Response::W(format!("The problem is not solvable on the following multigraph:\n{}", g)));
+                }
+                Ok(None) => {
+                    handler(Response::W(format!("The problem is solvable on all multigraphs with at most {} nodes.", max_nodes)));
+                }
+                Err(s) => handler(Response::E(s.into())),
             }
         }
         Request::DefaultDiagram(mut problem, partial, _triviality_only, labels, larger, addarrows) => {
@@ -389,22 +477,29 @@ where
                 fix_problem(&mut new, true, true, &mut eh);
                 handler(Response::P(new));
             } else {
-                new = new.speedup(&mut eh);
-                match maximize_rename_gen(&mut new, &mut eh) {
-                    Ok(()) => {
+                match new.try_speedup(&mut eh).and_then(|mut new|maximize_rename_gen(&mut new, &mut eh).map(|()|new)) {
+                    Ok(new) => {
                         handler(Response::P(new));
                     }
-                    Err(s) => handler(Response::E(s.into())),
+                    Err(s) => handler(Response::E(s)),
                 }
             }
         }
         Request::Demisifiable(mut p,old) => {
             let mapping : HashMap<_,_> = p.mapping_label_text.iter().cloned().collect();
-            p.compute_demisifiable(|set|{
+            let result = p.compute_demisifiable(|set|{
                 let set = set.iter().map(|l|&mapping[l]).join("");
                 handler(Response::W(format!("Found set: {}",set)))
             },old,&mut eh);
-            handler(Response::P(p));
+            match result {
+                Ok(exact) => {
+                    if !exact {
+                        handler(Response::W("The result is only a heuristic: either there are too many labels to check all the sets, and some sets may be missing, or the problem contains a star, and only some degrees have been checked.".into()));
+                    }
+                    handler(Response::P(p));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         }
         Request::AddActivePredecessors(mut p, flip) => {
             p.add_active_predecessors();
@@ -439,8 +534,10 @@ where
                             input = t;
                         }
                         if !subset {
-                            problem.compute_triviality_with_input(input, sat);
-                            handler(Response::P(problem));
+                            match problem.compute_triviality_with_input(input, sat) {
+                                Ok(()) => handler(Response::P(problem)),
+                                Err(s) => handler(Response::E(s.into())),
+                            }
                         } else {
                             let mut best = input.labels().len()+1;
                             let mut best_arrows = 0;
@@ -463,8 +560,10 @@ where
                                     }
                                 }
                             };
-                            if problem.compute_subinput_that_gives_nontriviality(input,sat, f).is_none() {
-                                handler(Response::E("Could not find a suitable subinput".into()));
+                            match problem.compute_subinput_that_gives_nontriviality(input,sat, f) {
+                                Ok(Some(_)) => {}
+                                Ok(None) => handler(Response::E("Could not find a suitable subinput".into())),
+                                Err(s) => handler(Response::E(s.into())),
                             }
                         }
                     }
@@ -531,12 +630,9 @@ where
         Request::DoubleDual2(problem, active, passive,diagram,input_active,input_passive) => {
             match problem.doubledual_diagram(&active,&passive,&diagram,&input_active,&input_passive, &mut eh) {
                 Ok(diagram) => {
-                    match problem.fixpoint_generic(None, FixpointType::Custom(diagram),false,&mut eh) {
-                        Ok((mut new,_,_)) => {
-                            fix_problem(&mut new, true, true, &mut eh);
-                            handler(Response::P(new));
-                        }
-                        Err(s) => handler(Response::E(s.into())),
+                    let result = problem.fixpoint_generic(None, FixpointType::Custom(diagram),false,&mut eh);
+                    for response in fixpoint_responses(result, false, &mut eh) {
+                        handler(response);
                     }
                 }
                 Err(s) => handler(Response::E(s.into()))
@@ -582,8 +678,10 @@ where
                                         }
                                     }
                                 };
-                                if fp.compute_subinput_that_gives_nontriviality(input,true, f).is_none() {
-                                    handler(Response::E("Always trivial.".into()));
+                                match fp.compute_subinput_that_gives_nontriviality(input,true, f) {
+                                    Ok(Some(_)) => {}
+                                    Ok(None) => handler(Response::E("Always trivial.".into())),
+                                    Err(s) => handler(Response::E(s.into())),
                                 }
                             }
                             Err(s) => handler(Response::E(s.into())),
@@ -594,34 +692,63 @@ where
             }
         },
         Request::LogstarDup(problem, labels) => {
-            let (mut new,_) = problem.logstar_dup(&labels);
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
+            match problem.logstar_dup(&labels) {
+                Ok((mut new,_)) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         },
         Request::LogstarSee(problem, labels) => {
-            let mut new = problem.logstar_see(&labels);
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
+            match problem.logstar_see(&labels) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         },
         Request::LogstarMIS(problem, labels) => {
-            let mut new = problem.logstar_mis(&labels);
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
+            match problem.logstar_mis(&labels) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         },
         Request::AutoLogstar(mut problem, max_labels, max_depth, active, passive, max_active, max_passive, onlybool) => {
             //eh.notify("logstarautoub",0,0);
-            if let Some((len,sequence)) = problem.autologstar(max_labels, max_depth, active, passive, max_active, max_passive, onlybool, &mut eh) {
-                if !onlybool {
-                    handler(Response::Logstar(len,sequence));
-                } else {
-                    handler(Response::E("Upper Bound Found!".into()));
+            match problem.autologstar(max_labels, max_depth, active, passive, max_active, max_passive, onlybool, &mut eh) {
+                Ok(Some((len,sequence))) => {
+                    if !onlybool {
+                        handler(Response::Logstar(len,sequence));
+                    } else {
+                        handler(Response::E("Upper Bound Found!".into()));
+                    }
                 }
+                Ok(None) => {}
+                Err(s) => handler(Response::E(s.into())),
             }
         },
+        Request::FixpointSearch(mut problem, max_edits, budget) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            let found = problem.fixpoint_search(max_edits, budget, &mut eh);
+            handler(Response::FixpointCandidates(found));
+        }
+        Request::VerifyFixpoint(problem, claimed) => {
+            match problem.verify_fixpoint(&claimed, &mut eh) {
+                Ok(certificate) => handler(Response::FixpointCertificate(certificate)),
+                Err(s) => handler(Response::E(s)),
+            }
+        }
         Request::FixpointAddarrow(mut problem) => {
             let mut best = 0;
 
-            problem.fixpoint_addarrow(|arrows : Vec<(Label,Label)>,x : usize, is_trivial : bool|{
+            let result = problem.fixpoint_addarrow(|arrows : Vec<(Label,Label)>,x : usize, is_trivial : bool|{
                 let mapping : HashMap<_,_> = problem.mapping_label_text.iter().cloned().collect();
                 let arrows_str = arrows.iter().map(|(l1,l2)|format!("{} -> {}",mapping[&l1],mapping[&l2])).join(", ");
                 if !is_trivial {
@@ -633,6 +760,9 @@ where
                     handler(Response::E(msg.into()));
                 }
             });
+            if let Err(s) = result {
+                handler(Response::E(s.into()));
+            }
         },
     }
 
@@ -642,6 +772,7 @@ where
 #[derive(Deserialize, Serialize)]
 pub enum Request {
     NewProblem(String, String),
+    NewProblemNonregular(String, String),
     SimplifyMerge(Problem, Label, Label),
     SimplifyMergeGroup(Problem, Vec<Label>, Label),
     SimplifyAddarrow(Problem, Label, Label),
@@ -654,6 +785,8 @@ pub enum Request {
     FixpointCustom(Problem,String, bool, bool, Vec<Label>),
     FixpointDup(Problem,Vec<Vec<Label>>, bool, bool, Vec<Label>, bool),
     FixpointAddarrow(Problem),
+    FixpointSearch(Problem, usize, usize),
+    VerifyFixpoint(Problem, Problem),
     InverseSpeedup(Problem),
     AllDifferentLabels(Problem),
     DeltaEdgeColoring(Problem),
@@ -665,11 +798,16 @@ pub enum Request {
     RenameGenerators(Problem),
     Rename(Problem, Vec<(Label, String)>),
     Orientation(Problem, usize),
+    GiveInput(Problem, String, String),
     DefaultDiagram(Problem, bool, bool, Vec<Label>, bool, String),
     AutoUb(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize),
     AutoLb(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize),
+    AutoLbCombined(Problem, usize, usize, usize, bool, usize, bool, usize, String, String, LowerBound),
+    Classify(Problem, usize, usize, usize, usize),
     ColoringSolvability(Problem),
     Marks(Problem),
+    Multigraph(Problem, usize, bool),
+    RandomizedUb(Problem, usize, usize),
     CriticalHarden(Problem,bool, usize, bool, usize, usize, bool, bool),
     CriticalRelax(Problem,bool, usize, bool, usize, usize, bool),
     Demisifiable(Problem,bool),
@@ -698,7 +836,12 @@ pub enum Response {
     W(String),
     AutoUb(usize,Vec<(AutoOperation,Problem)>),
     AutoLb(usize,Vec<(AutoOperation,Problem)>),
-    Logstar(usize,Vec<(AutoOperation,Problem)>)
+    Logstar(usize,Vec<(AutoOperation,Problem)>),
+    FixpointFailure(FixpointFailure),
+    FixpointCandidates(Vec<FixpointCandidate>),
+    FixpointCertificate(FixpointCertificate),
+    LowerBound(LowerBoundCertificate),
+    Classification(Classification)
 }
 
 #[derive(Serialize,Deserialize,Clone)]
@@ -32,6 +32,16 @@ fn pgo_quick_test() {
 async fn main() {
     #[cfg(not(target_os = "linux"))]
     unsafe{ libmimalloc_sys::mi_option_set(26, 0) }
+    let args : Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "worker" {
+        let addr = args[2].clone();
+        let result = tokio::task::spawn_blocking(move ||round_eliminator_lib::algorithms::distributed::run_worker(&addr)).await.unwrap();
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     server("127.0.0.1:8080").await; 
 }
 
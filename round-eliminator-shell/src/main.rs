@@ -55,8 +55,21 @@ fn make_request(request : Request, problems : &mut Vec<Problem>) {
                 },
                 Response::E(e) => { println!("ERROR: {}",e); }
                 Response::W(e) => { println!("WARNING: {}",e); }
+                Response::FixpointFailure(f) => { println!("FIXED POINT: {}",f); }
+                Response::FixpointCertificate(c) => { println!("{}",serde_json::to_string_pretty(&c).unwrap()); }
+                Response::FixpointCandidates(found) => {
+                    let mut problems = problems.lock().unwrap();
+                    problems.extend(found.into_iter().map(|c|c.fixpoint));
+                }
                 Response::AutoUb(_,_) => { todo!("autoub"); }
                 Response::AutoLb(_,_) => { todo!("autolb"); }
+                Response::LowerBound(c) => {
+                    println!("LOWER BOUND: {:?}",c.bound);
+                    if let Some((_,p)) = c.sequence.into_iter().last() {
+                        problems.lock().unwrap().push(p);
+                    }
+                }
+                Response::Classification(c) => { println!("{}",c.text); }
             }
         }
     });
@@ -70,6 +83,7 @@ fn help(){
     println!("last : show last problem");
     println!("all : show all problems");
     println!("newproblem : create a new problem");
+    println!("newproblemnonregular : create a new problem whose lines may have different degrees");
     println!("merge : relax by merging two labels");
     println!("addarrow : relax by adding an arrow between two labels");
     println!("mergegroup : relax by merging multiple labels");
@@ -79,6 +93,8 @@ fn help(){
     println!("hardenkeep : harden by keeping specific labels");
     println!("mergeequivalent : merge equivalent labels");
     println!("criticalrelax : relax by critical sets");
+    println!("dot : write the diagram of the last problem to a file in the DOT format");
+    println!("svg : write the diagram of the last problem to a file as an SVG image");
 
 
 }
@@ -206,11 +222,15 @@ fn all(problems : &Vec<Problem>) {
     }
 }
 
-fn new_problem(stdin : &mut impl Iterator<Item=String>, problems : &mut Vec<Problem>){
+fn new_problem(stdin : &mut impl Iterator<Item=String>, problems : &mut Vec<Problem>, nonregular : bool){
     println!("type the active constraints, then an empty line, then the passive constraints, then an empty line.");
     let active = stdin.take_while(|line|!line.is_empty()).collect_vec();
     let passive = stdin.take_while(|line|!line.is_empty()).collect_vec();
-    let request = Request::NewProblem(active.join("\n"), passive.join("\n"));
+    let request = if nonregular {
+        Request::NewProblemNonregular(active.join("\n"), passive.join("\n"))
+    } else {
+        Request::NewProblem(active.join("\n"), passive.join("\n"))
+    };
     make_request(request, problems);
 }
 
@@ -300,6 +320,21 @@ fn critical_relax(stdin : &mut impl Iterator<Item=String>, problems : &mut Vec<P
     make_request(request, problems);
 }
 
+fn export_diagram(stdin : &mut impl Iterator<Item=String>, problems : &Vec<Problem>, svg : bool){
+    println!("type the name of the file");
+    let file = stdin.next().unwrap();
+    let last = problems.last().unwrap();
+    let diagram = if svg { last.diagram_to_svg() } else { last.diagram_to_dot() };
+    match diagram {
+        Ok(diagram) => {
+            if let Err(e) = std::fs::write(&file, diagram) {
+                println!("ERROR: {}", e);
+            }
+        }
+        Err(e) => { println!("ERROR: {}", e); }
+    }
+}
+
 fn prompt(){
     print!("> ");
     std::io::stdout().flush().unwrap();
@@ -337,7 +372,8 @@ fn main() {
             "clear" => { clear(&mut problems); }
             "last" => { last(&problems); }
             "all" => { all(&problems); }
-            "newproblem" => { new_problem(&mut lines, &mut problems) }
+            "newproblem" => { new_problem(&mut lines, &mut problems, false) }
+            "newproblemnonregular" => { new_problem(&mut lines, &mut problems, true) }
             "merge" => { merge(&mut lines,&mut problems) }
             "addarrow" => { addarrow(&mut lines,&mut problems) }
             "mergegroup" => { mergegroup(&mut lines,&mut problems) }
@@ -347,6 +383,8 @@ fn main() {
             "hardenkeep" => { hardenkeep(&mut lines,&mut problems) }
             "mergeequivalent" => { merge_equivalent_labels(&mut lines, &mut problems) }
             "criticalrelax" => { critical_relax(&mut lines, &mut problems) }
+            "dot" => { export_diagram(&mut lines, &problems, false) }
+            "svg" => { export_diagram(&mut lines, &problems, true) }
             _ => { println!("unrecognized command"); }
         }
 
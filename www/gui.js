@@ -15,6 +15,11 @@ function handle_result(x, onresult, onerror, progress) {
     if( x.W != null ){
         onerror(x.W,true);
     }
+    if( x.FixpointFailure != null ){
+        // a fixed point that is 0-round solvable is still a result, the report is shown as a warning
+        let trivial = x.FixpointFailure.obstructions.length > 0 && x.FixpointFailure.obstructions.every(o => o.Trivial != null);
+        onerror(x.FixpointFailure.text, trivial);
+    }
     if( x.AutoUb != null ){
         for( let step of x.AutoUb[1] ){
             fix_problem(step[1]);
@@ -33,6 +38,9 @@ function handle_result(x, onresult, onerror, progress) {
         }
         onresult(x.Logstar)
     }
+    if( x.Classification != null ){
+        onresult(x.Classification.text)
+    }
 
     if( x.Event != null ){
         progress.type = x.Event[0];
@@ -48,6 +56,11 @@ function new_problem(left, right, onresult, onerror, progress) {
     api.request({ NewProblem : [left,right] }, ondata , function(){});
 }
 
+function new_problem_nonregular(left, right, onresult, onerror, progress) {
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    api.request({ NewProblemNonregular : [left,right] }, ondata , function(){});
+}
+
 function speedup(problem, onresult, onerror, progress){
     let ondata = x => handle_result(x, onresult, onerror, progress);
     return api.request({ Speedup : problem }, ondata , function(){});
@@ -224,6 +237,11 @@ function autolb(problem, b_max_labels, max_labels, b_branching, branching, b_max
     return api.request({ AutoLb : [problem, b_max_labels, parseInt(max_labels), b_branching, parseInt(branching),  b_max_steps, parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive)] }, ondata, oncomplete);
 }
 
+function classify(problem, max_labels, branching, max_steps, seconds, onresult, onerror, progress, oncomplete){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ Classify : [problem, parseInt(max_labels), parseInt(branching), parseInt(max_steps), parseInt(seconds)] }, ondata, oncomplete);
+}
+
 function autologstar(problem, max_labels, max_depth, active, passive, max_active, max_passive, onlybool, onresult, onerror, progress, oncomplete){
     let ondata = x => handle_result(x, onresult, onerror, progress);
     return api.request({ AutoLogstar : [problem, parseInt(max_labels), parseInt(max_depth), active, passive, parseInt(max_active), parseInt(max_passive), onlybool] }, ondata, oncomplete);
@@ -239,6 +257,11 @@ function check_zero_with_input(problem, active, passive, sat, subset, reverse, o
     return api.request({ CheckZeroWithInput : [problem, active, passive, sat, subset, reverse] }, ondata , function(){});
 }
 
+function give_input(problem, active, passive, onresult, onerror, progress){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ GiveInput : [problem, active, passive] }, ondata , function(){});
+}
+
 function dual(problem, active, passive, onresult, onerror, progress){
     let ondata = x => handle_result(x, onresult, onerror, progress);
     return api.request({ Dual : [problem, active, passive] }, ondata , function(){});
@@ -294,6 +317,8 @@ function fix_problem(p) {
 
     let zero_with_input =  (problem.is_trivial_with_input != null && problem.is_trivial_with_input);
     let non_zero_with_input = (problem.is_trivial_with_input != null && !problem.is_trivial_with_input);
+    let zero_given_input = (problem.trivial_given_input != null && problem.trivial_given_input);
+    let non_zero_given_input = (problem.trivial_given_input != null && !problem.trivial_given_input);
 
     let triviality_with_input = null;
     if( zero_with_input ){
@@ -302,7 +327,7 @@ function fix_problem(p) {
         let mapping = problem.triviality_with_input[1];
         triviality_with_input = mapping.map(x => [labelset_to_string([x[0]],input_to_string),  labelset_to_string(x[1],output_to_string)]);
     }
-    p.info = { orientation_coloringsets:orientation_coloringsets, orientation_numcolors:orientation_numcolors, orientation_zerosets:orientation_zerosets,orientation_is_zero:orientation_is_zero, orientation_is_nonzero:orientation_is_nonzero, numlabels : numlabels, is_zero : is_zero, is_nonzero : is_nonzero, numcolors : numcolors, zerosets : zerosets, coloringsets : coloringsets, is_mergeable : is_mergeable, mergesets : mergesets, is_demisifiable : is_demisifiable, demisifiable : demisifiable, fp_procedure_works : fp_procedure_works, fp_procedure_does_not_work : fp_procedure_does_not_work, marks_works : marks_works, marks_does_not_work : marks_does_not_work, zero_with_input:zero_with_input, non_zero_with_input: non_zero_with_input, triviality_with_input : triviality_with_input};
+    p.info = { orientation_coloringsets:orientation_coloringsets, orientation_numcolors:orientation_numcolors, orientation_zerosets:orientation_zerosets,orientation_is_zero:orientation_is_zero, orientation_is_nonzero:orientation_is_nonzero, numlabels : numlabels, is_zero : is_zero, is_nonzero : is_nonzero, numcolors : numcolors, zerosets : zerosets, coloringsets : coloringsets, is_mergeable : is_mergeable, mergesets : mergesets, is_demisifiable : is_demisifiable, demisifiable : demisifiable, fp_procedure_works : fp_procedure_works, fp_procedure_does_not_work : fp_procedure_does_not_work, marks_works : marks_works, marks_does_not_work : marks_does_not_work, zero_with_input:zero_with_input, non_zero_with_input: non_zero_with_input, triviality_with_input : triviality_with_input, zero_given_input : zero_given_input, non_zero_given_input : non_zero_given_input};
 }
 
 
@@ -352,6 +377,10 @@ function on_new_what(stuff, action, progress, p, what, removeprogress = true){
             substuff.push({ type : "problem", data : step[1] });
         }
         stuff.splice(idx+1,0,{ type : "sub", data : substuff });
+    }else if( what == "classification" ){
+        let action_copy = JSON.parse(JSON.stringify(action));
+        action_copy.text = p;
+        stuff.splice(idx+1,0,{ type : "performed", data : action_copy });
     }
 }
 
@@ -364,6 +393,10 @@ function call_api_generating_sequence(stuff, action, f, params, removeprogress =
     return call_api_generating_what(stuff, action, f, params, "sequence", removeprogress);
 }
 
+function call_api_generating_classification(stuff, action, f, params) {
+    return call_api_generating_what(stuff, action, f, params, "classification", false);
+}
+
 
 function call_api_generating_what(stuff, action, f, params, what, removeprogress = true) {
     let progress = { type : "computing", data : {type : "empty", cur : 1, max : 1, onstop : function(){}} };
@@ -455,6 +488,8 @@ Vue.component('re-performed-action', {
                     return "Performed Hardening by Critical Sets";
                 case "criticalrelax":
                     return "Performed Relaxation by Critical Sets";
+                case "giveinput":
+                    return "Attached input problem";
                 case "orientation":
                     return "Gave input orientation. Outdegree = " + this.action.outdegree;
                 case "speedup":
@@ -501,6 +536,8 @@ Vue.component('re-performed-action', {
                     return "Automatic Upper Bound. Obtained Upper Bound of " + this.action.len + " Rounds.";
                 case "autologstar":
                     return "Automatic Logstar Upper Bound found.";
+                case "classify":
+                    return this.action.text;
                 case "autolb":
                     if(this.action.len == 999 ){
                         return "Automatic Lower Bound. Obtained a Fixed Point."
@@ -545,6 +582,9 @@ Vue.component('re-computing', {
     props: ['action'],
     computed: {
         state: function() {
+            if( this.action.type.startsWith("combining line pairs, ") ) {
+                return {bar : true, msg: "Maximizing, combining lines ("+this.action.max+"), " + this.action.type.substring("combining line pairs, ".length), max : this.action.max, cur : this.action.cur };
+            }
             switch( this.action.type ) {
                 case "fixpoint autofix":
                     return {bar : false, msg: "Fixing diagram ("+this.action.max+" missing nodes)"}; 
@@ -556,6 +596,10 @@ Vue.component('re-computing', {
                     return {bar : false, msg: "Computing a Logstar Upper Bound Automatically"}; 
                 case "autolb":
                     return {bar : false, msg: "Computing a Lower Bound Automatically"}; 
+                case "classify speedups":
+                    return {bar : false, msg: "Classifying, applying speedups"}; 
+                case "marks":
+                    return {bar : false, msg: "Applying Marks' technique"}; 
                 case "coloring graph":
                     return {bar : true, msg: "Computing graph for determining coloring solvability", max : this.action.max, cur : this.action.cur };
                 case "clique":
@@ -737,6 +781,18 @@ Vue.component('re-problem-info', {
                     <div>The problem is NOT zero-round solvable with the given input.</div>
                 </div>
             </div>
+            <div v-if="this.problem.info.zero_given_input" class="col-auto m-2 p-0">
+                <div class="card card-body m-0 p-2">
+                    <div>The problem IS zero-round solvable given the attached input.</div>
+                    <div>The input is attached to the problem, and it is kept when applying round elimination.</div>
+                </div>
+            </div>
+            <div v-if="this.problem.info.non_zero_given_input" class="col-auto m-2 p-0">
+                <div class="card card-body m-0 p-2">
+                    <div>The problem is NOT zero-round solvable given the attached input.</div>
+                    <div>The input is attached to the problem, and it is kept when applying round elimination.</div>
+                </div>
+            </div>
             <div v-if="this.problem.expressions" class="col-auto m-2 p-0">
                 <div class="card card-body m-0 p-2">
                     <div style="white-space: pre;">{{ this.problem.expressions }}</div>
@@ -1735,6 +1791,37 @@ Vue.component('re-auto-ub',{
     `
 })
 
+Vue.component('re-classify',{
+    props: ['problem','stuff'],
+    data: function() {
+        return {
+            max_labels : this.problem.labels.length + 4,
+            branching : 4,
+            max_steps : 15,
+            seconds : 600,
+        }
+    },
+    watch: { 
+        problem: function(newVal, oldVal) { 
+            Object.assign(this.$data, this.$options.data.apply(this))
+        }
+    },
+    methods: {
+        on_classify() {
+            call_api_generating_classification(this.stuff,{type:"classify"},classify,[this.problem, this.max_labels, this.branching, this.max_steps, this.seconds]);
+        },
+    },
+    template: `
+        <re-card title="Classify" subtitle="(combine automatic upper and lower bounds)">
+            <div>Max Labels: <input class="form-control m-2" type="number" v-model="max_labels"></div>
+            <div>Branching: <input class="form-control m-2" type="number" v-model="branching"></div>
+            <div>Max Steps: <input class="form-control m-2" type="number" v-model="max_steps"></div>
+            <div>Time Limit (seconds): <input class="form-control m-2" type="number" v-model="seconds"></div>
+            <button type="button" class="btn btn-primary m-2" v-on:click="on_classify">Classify</button>
+        </re-card>
+    `
+})
+
 Vue.component('re-operations',{
     props: ['problem','stuff'],
     template: `
@@ -1777,6 +1864,7 @@ Vue.component('re-tools', {
             <re-critical :problem="problem" :stuff="stuff"></re-critical>
             <re-auto-lb :problem="problem" :stuff="stuff"></re-auto-lb>
             <re-auto-ub :problem="problem" :stuff="stuff"></re-auto-ub>
+            <re-classify :problem="problem" :stuff="stuff"></re-classify>
             <re-zero-input :problem="problem" :stuff="stuff"></re-zero-input>
             <re-dual :problem="problem" :stuff="stuff"></re-dual>
             <re-ubs :problem="problem" :stuff="stuff"></re-ubs>
@@ -1899,7 +1987,8 @@ Vue.component('re-begin', {
     props: ["all"],
     data : function(){ return {
             active : this.all.active,
-            passive : this.all.passive
+            passive : this.all.passive,
+            nonregular : false
         }
     },
     computed : {
@@ -1909,7 +1998,7 @@ Vue.component('re-begin', {
     },
     methods: {
         on_start() {
-            call_api_generating_problem(this.stuff,{type:"initial"},new_problem,[this.active, this.passive]);
+            call_api_generating_problem(this.stuff,{type:"initial"},this.nonregular ? new_problem_nonregular : new_problem,[this.active, this.passive]);
         },
         on_clear() {
             this.stuff.splice(0)
@@ -1934,6 +2023,9 @@ Vue.component('re-begin', {
                 <textarea rows="4" cols="30" class="form-control" style="resize: both" v-model="passive"></textarea>
             </div>
             <div class="m-2 col-sm mt-auto text-right">
+                <div class="custom-control custom-switch m-2">
+                    <label><input type="checkbox" class="custom-control-input" v-model="nonregular"><p class="form-control-static custom-control-label">Allow Different Degrees</p></label>
+                </div>
                 <button type="button" class="btn btn-primary" v-on:click="on_start">Start</button>
                 <button type="button" class="btn btn-primary" v-on:click="on_clear">Clear</button>
                 <re-export :stuff="stuff" :active="active" :passive="passive"></re-export>
@@ -2209,6 +2301,13 @@ Vue.component('re-zero-input',{
                 false
             );
         },
+        on_give_input(){
+            call_api_generating_problem(
+                this.stuff,
+                {type:"giveinput", active:this.active,passive:this.passive},
+                give_input,[this.problem, this.active,this.passive]
+            );
+        },
         on_zero_reverse(){
             call_api_generating_problem(
                 this.stuff,
@@ -2236,6 +2335,7 @@ Vue.component('re-zero-input',{
             </div>
             <button type="button" class="btn btn-primary ml-1" v-on:click="on_zero">Check</button>
             <button type="button" class="btn btn-primary ml-1" v-on:click="on_zero_reverse">Reverse Check</button>
+            <button type="button" class="btn btn-primary ml-1" v-on:click="on_give_input">Attach as Input</button>
         </re-card>
     `
 })